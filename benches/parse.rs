@@ -0,0 +1,71 @@
+//! Measures how fast a large scene, with long strings and comments,
+//! is parsed. Run with `cargo bench`, or `cargo bench --features simd`.
+
+extern crate ron;
+#[macro_use]
+extern crate serde;
+
+use std::time::{Duration, Instant};
+
+use ron::de::from_str;
+use ron::value::Value;
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Entity {
+    name: String,
+    description: String,
+    position: (f32, f32, f32),
+    tags: Vec<String>,
+}
+
+/// A scene of `entities` entities, each with a block comment,
+/// line comments and a long description.
+fn scene(entities: usize) -> String {
+    let mut scene = String::from("// A generated scene\n[\n");
+
+    for i in 0..entities {
+        scene.push_str(&format!(
+            "    /* Entity {i}, which is described at length below, so that the \
+             comment is as long as the ones tools write into scene files. */\n    \
+             (\n        // The unique name\n        name: \"entity_{i}\",\n        \
+             description: \"{}\",\n        position: ({i}.5, -{i}.25, 0.0),\n        \
+             tags: [\"static\", \"visible\", \"layer_{i}\"],\n    ),\n",
+            "A long description of the entity, which goes on for a while. ".repeat(8),
+        ));
+    }
+    scene.push(']');
+
+    scene
+}
+
+/// Runs `f` until a second has passed, and prints how many
+/// megabytes of `input` it gets through per second.
+fn bench<F: FnMut(&str)>(name: &str, input: &str, mut f: F) {
+    let start = Instant::now();
+    let mut runs = 0;
+
+    while start.elapsed() < Duration::from_secs(1) {
+        f(input);
+        runs += 1;
+    }
+
+    let bytes = (input.len() * runs) as f64;
+    let secs = start.elapsed().as_secs_f64();
+    println!("{:>8}: {:>8.1} MB/s", name, bytes / secs / 1e6);
+}
+
+fn main() {
+    let input = scene(2_000);
+    println!("scene of {:.1} MB", input.len() as f64 / 1e6);
+
+    bench("typed", &input, |input| {
+        from_str::<Vec<Entity>>(input).unwrap();
+    });
+    bench("value", &input, |input| {
+        from_str::<Value>(input).unwrap();
+    });
+    bench("ignored", &input, |input| {
+        from_str::<serde::de::IgnoredAny>(input).unwrap();
+    });
+}
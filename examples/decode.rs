@@ -7,6 +7,7 @@ use std::collections::HashMap;
 use ron::de::from_str;
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 struct Config {
     boolean: bool,
     float: f32,
@@ -17,6 +18,7 @@ struct Config {
 }
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 struct Nested {
     a: String,
     b: char,
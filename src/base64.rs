@@ -0,0 +1,99 @@
+/// The standard base64 alphabet of RFC 4648.
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `bytes`, padded with `=` to a multiple of four characters.
+pub fn encode(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (i, &byte)| group | (byte as u32) << (16 - 8 * i));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(ALPHABET[((group >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+
+    s
+}
+
+/// Decodes `text`, ignoring whitespace, so that long strings can be
+/// split across lines. The padding may be left out.
+pub fn decode(text: &[u8]) -> Option<Vec<u8>> {
+    let mut digits = Vec::with_capacity(text.len());
+    let mut padding = 0;
+
+    for &c in text.iter().filter(|c| !c.is_ascii_whitespace()) {
+        let digit = match c {
+            b'=' => {
+                padding += 1;
+                continue;
+            }
+            // Nothing may follow the padding
+            _ if padding > 0 => return None,
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+
+        digits.push(digit);
+    }
+
+    if digits.len() % 4 == 1 || (padding > 0 && (digits.len() + padding) % 4 != 0) {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(digits.len() * 3 / 4);
+    for chunk in digits.chunks(4) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (i, &digit)| group | (digit as u32) << (18 - 6 * i));
+
+        for i in 0..chunk.len() - 1 {
+            bytes.push((group >> (16 - 8 * i)) as u8);
+        }
+    }
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let cases: &[(&[u8], &str)] = &[
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"\x00\xff\xfe", "AP/+"),
+        ];
+
+        for &(bytes, text) in cases {
+            assert_eq!(encode(bytes), text);
+            assert_eq!(decode(text.as_bytes()).as_ref().map(|b| &b[..]), Some(bytes));
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode(b"Zm9v\n  Yg"), Some(b"foob".to_vec()));
+        assert_eq!(decode(b"Zm9vY"), None);
+        assert_eq!(decode(b"Zg=a"), None);
+        assert_eq!(decode(b"Zg="), None);
+        assert_eq!(decode(b"Zm-v"), None);
+    }
+}
@@ -0,0 +1,62 @@
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use serde::de::DeserializeOwned;
+use tokio::io::{AsyncRead, ReadBuf};
+
+use super::{Options, Result};
+
+/// The future returned by `from_async_reader`, which reads the input
+/// without blocking and deserializes it once it has been read.
+pub struct FromAsyncReader<R, T> {
+    reader: R,
+    options: Options,
+    max_len: Option<usize>,
+    bytes: Vec<u8>,
+    marker: PhantomData<fn() -> T>,
+}
+
+/// Reads at most one byte more than `max_len` from `reader`, which
+/// tells whether the input is too long.
+pub fn read<R, T>(reader: R, options: Options, max_len: Option<usize>) -> FromAsyncReader<R, T> {
+    FromAsyncReader {
+        reader,
+        options,
+        max_len,
+        bytes: Vec::new(),
+        marker: PhantomData,
+    }
+}
+
+impl<R, T> Future for FromAsyncReader<R, T>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    type Output = Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<T>> {
+        let this = &mut *self;
+        let mut chunk = [0; 8 * 1024];
+
+        loop {
+            let wanted = match this.max_len {
+                Some(max) if this.bytes.len() > max => break,
+                Some(max) => chunk.len().min(max + 1 - this.bytes.len()),
+                None => chunk.len(),
+            };
+
+            let mut buf = ReadBuf::new(&mut chunk[..wanted]);
+            match Pin::new(&mut this.reader).poll_read(cx, &mut buf) {
+                Poll::Ready(Ok(())) if buf.filled().is_empty() => break,
+                Poll::Ready(Ok(())) => this.bytes.extend_from_slice(buf.filled()),
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e.into())),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        Poll::Ready(this.options.from_bytes(&this.bytes))
+    }
+}
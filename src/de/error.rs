@@ -1,12 +1,13 @@
 use std::error::Error as StdError;
 use std::fmt;
 use std::io;
+use std::ops::Range;
 use std::str::Utf8Error;
 use std::string::FromUtf8Error;
 
 use serde::de;
 
-use parse::Position;
+use parse::{token_len, Position};
 
 /// Deserialization result.
 pub type Result<T> = ::std::result::Result<T, Error>;
@@ -16,44 +17,108 @@ pub enum Error {
     IoError(String),
     Message(String),
     Parser(ParseError, Position),
+    /// An error in a nested value, which tells where it is,
+    /// see `Deserializer::set_error_paths`.
+    Path {
+        /// The path of the value, like `entities[3].transform.scale`.
+        path: String,
+        error: Box<Error>,
+    },
 }
 
+/// What went wrong while parsing.
+///
+/// The `Option<String>` of the `Expected` variants is the token which
+/// was found instead, or `None` at the end of the input.
 #[derive(Clone, Debug, PartialEq)]
 pub enum ParseError {
     Eof,
-    ExpectedArray,
-    ExpectedArrayEnd,
-    ExpectedAttribute,
-    ExpectedAttributeEnd,
-    ExpectedBoolean,
-    ExpectedComma,
-    ExpectedEnum,
-    ExpectedChar,
-    ExpectedFloat,
-    ExpectedInteger,
-    ExpectedOption,
-    ExpectedOptionEnd,
-    ExpectedMap,
-    ExpectedMapColon,
-    ExpectedMapEnd,
-    ExpectedStruct,
-    ExpectedStructEnd,
-    ExpectedUnit,
-    ExpectedStructName,
-    ExpectedString,
+    ExpectedArray(Option<String>),
+    ExpectedArrayEnd(Option<String>),
+    ExpectedAttribute(Option<String>),
+    ExpectedAttributeEnd(Option<String>),
+    ExpectedBoolean(Option<String>),
+    ExpectedComma(Option<String>),
+    ExpectedEnum(Option<String>),
+    ExpectedChar(Option<String>),
+    ExpectedFloat(Option<String>),
+    ExpectedInteger(Option<String>),
+    ExpectedOption(Option<String>),
+    ExpectedOptionEnd(Option<String>),
+    ExpectedMap(Option<String>),
+    ExpectedMapColon(Option<String>),
+    ExpectedMapEnd(Option<String>),
+    ExpectedStruct(Option<String>),
+    ExpectedStructEnd(Option<String>),
+    ExpectedUnit(Option<String>),
+    ExpectedStructName(Option<String>),
+    /// A struct is named differently than the type it's read as,
+    /// see `Deserializer::set_strict_struct_names`.
+    StructNameMismatch {
+        expected: &'static str,
+        found: String,
+    },
+    ExpectedString(Option<String>),
     ExpectedStringEnd,
-    ExpectedIdentifier,
+    ExpectedIdentifier(Option<String>),
+    ExpectedHexBytes(Option<String>),
+    ExpectedByteString(Option<String>),
+    /// A string of bytes which isn't valid base64,
+    /// see `Deserializer::set_base64_bytes`.
+    ExpectedBase64,
 
     InvalidEscape(&'static str),
+    /// An integer which doesn't fit into the type it's read as,
+    /// like `300` or `-1` for a `u8`.
+    IntegerOutOfRange {
+        /// The integer as it's written.
+        literal: String,
+        /// The type, like `u8`.
+        ty: &'static str,
+    },
 
     NoSuchExtension(String),
+    /// A map key which is written more than once,
+    /// see `Deserializer::set_duplicate_keys`.
+    DuplicateKey {
+        key: String,
+        /// Where the key was written first.
+        first: Position,
+    },
+    /// A struct field which is written more than once,
+    /// see `Deserializer::set_reject_duplicate_fields`.
+    DuplicateField {
+        field: String,
+        /// Where the field was written first.
+        first: Position,
+    },
+    /// An anchor like `&0` or a reference like `*0`, which are only
+    /// read with `Deserializer::set_shared_values`.
+    UnexpectedAnchor,
+    /// A reference like `*0` in place of a value which isn't shared.
+    UntypedReference,
+    ExceededRecursionLimit,
+    /// The input is longer than `Limits::max_input_len`.
+    ExceededInputLimit,
+    /// A string is longer than `Limits::max_string_len`.
+    ExceededStringLimit,
+    /// A collection has more elements than `Limits::max_elements`.
+    ExceededElementLimit,
+    /// The input has more values than `Limits::max_nodes`.
+    ExceededNodeLimit,
 
     UnclosedBlockComment,
     UnexpectedByte(char),
 
     Utf8Error(Utf8Error),
+    /// The input is in an encoding other than UTF-8, like UTF-16.
+    UnsupportedEncoding(&'static str),
     TrailingCharacters,
 
+    /// A custom error emitted by a deserialized value,
+    /// like a missing struct field.
+    Message(String),
+
     #[doc(hidden)] __NonExhaustive,
 }
 
@@ -62,61 +127,246 @@ impl fmt::Display for Error {
         match *self {
             Error::IoError(ref s) => write!(f, "{}", s),
             Error::Message(ref s) => write!(f, "{}", s),
-            Error::Parser(_, pos) => write!(f, "{}: {}", pos, self.description()),
+            Error::Parser(ref kind, pos) => write!(f, "{}: {}", pos, kind),
+            Error::Path {
+                ref path,
+                ref error,
+            } => write!(f, "{}: {}", path, error),
         }
     }
 }
 
+impl Error {
+    /// Where the error occurred, if it's known.
+    pub fn position(&self) -> Option<Position> {
+        match *self {
+            Error::Parser(_, position) => Some(position),
+            Error::Path { ref error, .. } => error.position(),
+            _ => None,
+        }
+    }
+
+    /// The range of bytes in `input` which the error is about, like the
+    /// whole `true` in `[1, true]` where a number was expected, so
+    /// that editors can highlight it.
+    ///
+    /// `input` has to be what was deserialized. The range is empty
+    /// at the end of the input, and `None` without a position.
+    ///
+    /// ```
+    /// use ron::de::{from_str, Error};
+    ///
+    /// let input = "[1, true]";
+    /// let error: Error = from_str::<Vec<f32>>(input).unwrap_err();
+    ///
+    /// assert_eq!(error.position().map(|p| p.col), Some(5));
+    /// assert_eq!(error.span(input.as_bytes()), Some(4..8));
+    /// ```
+    pub fn span(&self, input: &[u8]) -> Option<Range<usize>> {
+        self.position().map(|position| {
+            let start = position.offset.min(input.len());
+
+            start..start + token_len(&input[start..])
+        })
+    }
+}
+
 impl de::Error for Error {
     fn custom<T: fmt::Display>(msg: T) -> Self {
         Error::Message(msg.to_string())
     }
+
+    /// Lists the variants, and suggests the one which is most likely
+    /// meant, if there is one.
+    fn unknown_variant(variant: &str, expected: &'static [&'static str]) -> Self {
+        let error = de::value::Error::unknown_variant(variant, expected);
+
+        match closest(variant, expected) {
+            Some(candidate) => {
+                Error::Message(format!("{} (did you mean `{}`?)", error, candidate))
+            }
+            None => de::Error::custom(error),
+        }
+    }
+
+    /// Lists the fields, and suggests the one which is most likely
+    /// meant, if there is one.
+    fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
+        let error = de::value::Error::unknown_field(field, expected);
+
+        match closest(field, expected) {
+            Some(candidate) => {
+                Error::Message(format!("{} (did you mean `{}`?)", error, candidate))
+            }
+            None => de::Error::custom(error),
+        }
+    }
+}
+
+/// Finds the candidate which is most likely what `name` was meant to be,
+/// if there is one that is similar enough.
+fn closest(name: &str, candidates: &[&'static str]) -> Option<&'static str> {
+    candidates
+        .iter()
+        .map(|&candidate| (edit_distance(name, candidate), candidate))
+        .filter(|&(distance, candidate)| distance * 3 <= name.len().max(candidate.len()))
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + if ca == cb { 0 } else { 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
 }
 
 impl StdError for Error {
-    fn description(&self) -> &str {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match *self {
-            Error::IoError(ref s) => s,
-            Error::Message(ref e) => e,
-            Error::Parser(ref kind, _) => match *kind {
-                ParseError::Eof => "Unexpected end of file",
-                ParseError::ExpectedArray => "Expected array",
-                ParseError::ExpectedArrayEnd => "Expected end of array",
-                ParseError::ExpectedAttribute => "Expected an enable attribute",
-                ParseError::ExpectedAttributeEnd => {
-                    "Expected closing `)` and `]` after the attribute"
-                }
-                ParseError::ExpectedBoolean => "Expected boolean",
-                ParseError::ExpectedComma => "Expected comma",
-                ParseError::ExpectedEnum => "Expected enum",
-                ParseError::ExpectedChar => "Expected char",
-                ParseError::ExpectedFloat => "Expected float",
-                ParseError::ExpectedInteger => "Expected integer",
-                ParseError::ExpectedOption => "Expected option",
-                ParseError::ExpectedOptionEnd => "Expected end of option",
-                ParseError::ExpectedMap => "Expected map",
-                ParseError::ExpectedMapColon => "Expected colon",
-                ParseError::ExpectedMapEnd => "Expected end of map",
-                ParseError::ExpectedStruct => "Expected struct",
-                ParseError::ExpectedStructEnd => "Expected end of struct",
-                ParseError::ExpectedUnit => "Expected unit",
-                ParseError::ExpectedStructName => "Expected struct name",
-                ParseError::ExpectedString => "Expected string",
-                ParseError::ExpectedIdentifier => "Expected identifier",
-
-                ParseError::InvalidEscape(_) => "Invalid escape sequence",
-
-                ParseError::Utf8Error(ref e) => e.description(),
-                ParseError::UnclosedBlockComment => "Unclosed block comment",
-                ParseError::UnexpectedByte(_) => "Unexpected byte",
-                ParseError::TrailingCharacters => "Non-whitespace trailing characters",
-
-                _ => unimplemented!(),
-            },
+            Error::Parser(ParseError::Utf8Error(ref e), _) => Some(e),
+            Error::Path { ref error, .. } => error.source(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::Eof => f.write_str("Unexpected end of file"),
+            ParseError::ExpectedArray(ref found) => expected(f, "an array", found),
+            ParseError::ExpectedArrayEnd(ref found) => {
+                expected(f, "`]` at the end of the array", found)
+            }
+            ParseError::ExpectedAttribute(ref found) => {
+                expected(f, "an `#![enable(...)]` attribute", found)
+            }
+            ParseError::ExpectedAttributeEnd(ref found) => {
+                expected(f, "closing `)` and `]` after the attribute", found)
+            }
+            ParseError::ExpectedBoolean(ref found) => expected(f, "`true` or `false`", found),
+            ParseError::ExpectedComma(ref found) => expected(f, "a comma", found),
+            ParseError::ExpectedEnum(ref found) => expected(f, "an enum", found),
+            ParseError::ExpectedChar(ref found) => expected(f, "a char, like 'c',", found),
+            ParseError::ExpectedFloat(ref found) => expected(f, "a float", found),
+            ParseError::ExpectedInteger(ref found) => expected(f, "an integer", found),
+            ParseError::ExpectedOption(ref found) => {
+                expected(f, "`Some(...)` or `None`", found)
+            }
+            ParseError::ExpectedOptionEnd(ref found) => {
+                expected(f, "`)` at the end of the option", found)
+            }
+            ParseError::ExpectedMap(ref found) => expected(f, "a map", found),
+            ParseError::ExpectedMapColon(ref found) => {
+                expected(f, "`:` after the map key", found)
+            }
+            ParseError::ExpectedMapEnd(ref found) => {
+                expected(f, "`}` at the end of the map", found)
+            }
+            ParseError::ExpectedStruct(ref found) => expected(f, "a struct", found),
+            ParseError::ExpectedStructEnd(ref found) => {
+                expected(f, "`)` at the end of the struct", found)
+            }
+            ParseError::ExpectedUnit(ref found) => expected(f, "unit `()`", found),
+            ParseError::ExpectedStructName(ref found) => expected(f, "a struct name", found),
+            ParseError::StructNameMismatch {
+                expected,
+                ref found,
+            } => write!(f, "Expected struct `{}` but found `{}`", expected, found),
+            ParseError::ExpectedString(ref found) => expected(f, "a string", found),
+            ParseError::ExpectedStringEnd => f.write_str("Expected `\"` at the end of the string"),
+            ParseError::ExpectedIdentifier(ref found) => expected(f, "an identifier", found),
+            ParseError::ExpectedHexBytes(ref found) => {
+                expected(f, "a hex block of byte pairs", found)
+            }
+            ParseError::ExpectedByteString(ref found) => expected(f, "a byte string", found),
+            ParseError::ExpectedBase64 => f.write_str("Expected base64 string"),
+
+            ParseError::InvalidEscape(why) => write!(f, "Invalid escape sequence: {}", why),
+            ParseError::IntegerOutOfRange { ref literal, ty } => {
+                let (min, max) = integer_range(ty);
+
+                write!(
+                    f,
+                    "Integer `{}` doesn't fit into `{}`, which ranges from {} to {}",
+                    literal, ty, min, max
+                )
+            }
+
+            ParseError::NoSuchExtension(ref name) => write!(f, "No extension named `{}`", name),
+            ParseError::DuplicateKey { ref key, first } => {
+                write!(f, "Duplicate map key `{}`, which was first written at {}", key, first)
+            }
+            ParseError::DuplicateField { ref field, first } => {
+                write!(f, "Duplicate field `{}`, which was first written at {}", field, first)
+            }
+            ParseError::UnexpectedAnchor => f.write_str("Shared values aren't enabled"),
+            ParseError::UntypedReference => {
+                f.write_str("A reference can only be read into a shared value")
+            }
+            ParseError::ExceededRecursionLimit => {
+                f.write_str("Values are nested deeper than the recursion limit")
+            }
+            ParseError::ExceededInputLimit => f.write_str("The input is longer than its limit"),
+            ParseError::ExceededStringLimit => f.write_str("A string is longer than its limit"),
+            ParseError::ExceededElementLimit => {
+                f.write_str("A collection has more elements than its limit")
+            }
+            ParseError::ExceededNodeLimit => {
+                f.write_str("The input has more values than its limit")
+            }
+
+            ParseError::UnclosedBlockComment => f.write_str("Unclosed block comment"),
+            ParseError::UnexpectedByte(c) => write!(f, "Unexpected {:?}", c),
+
+            ParseError::Utf8Error(ref e) => write!(f, "Invalid UTF-8: {}", e),
+            ParseError::UnsupportedEncoding(encoding) => {
+                write!(f, "Unsupported encoding {}, the input must be UTF-8", encoding)
+            }
+            ParseError::TrailingCharacters => f.write_str("Non-whitespace trailing characters"),
+
+            ParseError::Message(ref s) => f.write_str(s),
+
+            ParseError::__NonExhaustive => f.write_str("Unknown error"),
         }
     }
 }
 
+/// Writes that `what` was expected, and which token was found instead.
+fn expected(f: &mut fmt::Formatter, what: &str, found: &Option<String>) -> fmt::Result {
+    match *found {
+        Some(ref token) => write!(f, "Expected {} but found `{}`", what, token),
+        None => write!(f, "Expected {} but found the end of the input", what),
+    }
+}
+
+/// The smallest and largest value of the integer type named `ty`.
+fn integer_range(ty: &str) -> (String, String) {
+    macro_rules! integer_range {
+        ($($ty:ident)*) => {
+            match ty {
+                $( stringify!($ty) => ($ty::MIN.to_string(), $ty::MAX.to_string()), )*
+                _ => ("?".to_owned(), "?".to_owned()),
+            }
+        };
+    }
+
+    integer_range!(u8 u16 u32 u64 u128 i8 i16 i32 i64 i128)
+}
+
 impl From<Utf8Error> for ParseError {
     fn from(e: Utf8Error) -> Self {
         ParseError::Utf8Error(e)
@@ -131,12 +381,19 @@ impl From<FromUtf8Error> for ParseError {
 
 impl From<Utf8Error> for Error {
     fn from(e: Utf8Error) -> Self {
-        Error::Parser(ParseError::Utf8Error(e), Position { line: 0, col: 0 })
+        Error::Parser(
+            ParseError::Utf8Error(e),
+            Position {
+                line: 0,
+                col: 0,
+                offset: 0,
+            },
+        )
     }
 }
 
 impl From<io::Error> for Error {
     fn from(e: io::Error) -> Self {
-        Error::IoError(e.description().to_string())
+        Error::IoError(e.to_string())
     }
 }
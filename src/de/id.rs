@@ -1,5 +1,6 @@
 use serde::de::{self, Visitor};
 
+use super::value::UNTYPED_VALUE;
 use super::{Deserializer, Error, Result};
 
 pub struct IdDeserializer<'a, 'b: 'a> {
@@ -12,7 +13,7 @@ impl<'a, 'b: 'a> IdDeserializer<'a, 'b> {
     }
 }
 
-impl<'a, 'b: 'a, 'c> de::Deserializer<'b> for &'c mut IdDeserializer<'a, 'b> {
+impl<'a, 'b: 'a> de::Deserializer<'b> for &mut IdDeserializer<'a, 'b> {
     type Error = Error;
 
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
@@ -113,18 +114,19 @@ impl<'a, 'b: 'a, 'c> de::Deserializer<'b> for &'c mut IdDeserializer<'a, 'b> {
         unimplemented!("IdDeserializer may only be used for identifiers")
     }
 
-    fn deserialize_str<V>(self, _: V) -> Result<V::Value>
+    // Field names can be read as strings, like the keys of a map
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'b>,
     {
-        unimplemented!("IdDeserializer may only be used for identifiers")
+        self.deserialize_identifier(visitor)
     }
 
-    fn deserialize_string<V>(self, _: V) -> Result<V::Value>
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'b>,
     {
-        unimplemented!("IdDeserializer may only be used for identifiers")
+        self.deserialize_identifier(visitor)
     }
 
     fn deserialize_bytes<V>(self, _: V) -> Result<V::Value>
@@ -162,10 +164,15 @@ impl<'a, 'b: 'a, 'c> de::Deserializer<'b> for &'c mut IdDeserializer<'a, 'b> {
         unimplemented!("IdDeserializer may only be used for identifiers")
     }
 
-    fn deserialize_newtype_struct<V>(self, _: &'static str, _: V) -> Result<V::Value>
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'b>,
     {
+        // A `Value` can be read from a name as well
+        if name == UNTYPED_VALUE {
+            return self.deserialize_identifier(visitor);
+        }
+
         unimplemented!("IdDeserializer may only be used for identifiers")
     }
 
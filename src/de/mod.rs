@@ -1,19 +1,45 @@
 /// Deserialization module.
 ///
+#[cfg(feature = "async")]
+pub use self::async_read::FromAsyncReader;
 pub use self::error::{Error, ParseError, Result};
+pub use self::options::{DuplicateKeys, Limits, Options};
+pub use interned::Interned;
+pub use self::push::{Event, Parser};
+pub use self::reader::ReaderDeserializer;
+pub use self::stream::StreamDeserializer;
 pub use parse::Position;
 
 use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::io;
 use std::str;
+use std::sync::Arc;
 
-use serde::de::{self, DeserializeSeed, Deserializer as Deserializer_, Visitor};
+use serde::de::{self, DeserializeSeed, Deserializer as Deserializer_, IntoDeserializer, Visitor};
+use serde::de::value::SeqDeserializer;
 
 use self::id::IdDeserializer;
-use parse::{Bytes, Extensions};
-
+use self::path::Segment;
+use self::value::UNTYPED_VALUE;
+use extensions::Extensions;
+use interned::{self, INTERNED};
+use parse::{Bytes, Expected};
+use raw::RAW_VALUE;
+use shared::SHARED;
+use spanned::{END, SPANNED, START, VALUE};
+
+#[cfg(feature = "async")]
+mod async_read;
 mod error;
 mod id;
+mod options;
+mod path;
+mod push;
+mod reader;
+mod recover;
+mod stream;
 #[cfg(test)]
 mod tests;
 mod value;
@@ -24,9 +50,29 @@ mod value;
 /// you can use the `from_str` convenience function.
 pub struct Deserializer<'de> {
     bytes: Bytes<'de>,
+    depth: usize,
+    recursion_limit: Option<usize>,
+    strict_struct_names: bool,
+    duplicate_keys: DuplicateKeys,
+    reject_duplicate_fields: bool,
+    lenient_field_names: bool,
+    strict_grammar: bool,
+    numeric_variants: bool,
+    maps_as_structs: bool,
+    base64_bytes: bool,
+    shared_values: bool,
+    /// The strings read so far, if strings are interned.
+    strings: Option<HashSet<Arc<str>>>,
+    limits: Limits,
+    /// How many elements have been read so far, for `Limits::max_nodes`.
+    nodes: usize,
+    error_paths: bool,
+    /// Where the value which is read is, if paths are tracked.
+    path: Vec<Segment<'de>>,
 }
 
 impl<'de> Deserializer<'de> {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(input: &'de str) -> Result<Self> {
         Deserializer::from_bytes(input.as_bytes())
     }
@@ -34,25 +80,455 @@ impl<'de> Deserializer<'de> {
     pub fn from_bytes(input: &'de [u8]) -> Result<Self> {
         Ok(Deserializer {
             bytes: Bytes::new(input)?,
+            depth: 0,
+            recursion_limit: None,
+            strict_struct_names: false,
+            duplicate_keys: DuplicateKeys::Error,
+            reject_duplicate_fields: true,
+            lenient_field_names: false,
+            strict_grammar: false,
+            numeric_variants: false,
+            maps_as_structs: false,
+            base64_bytes: false,
+            shared_values: false,
+            strings: None,
+            limits: Limits::default(),
+            nodes: 0,
+            error_paths: false,
+            path: Vec::new(),
         })
     }
 
-    pub fn remainder(&self) -> Cow<str> {
-        String::from_utf8_lossy(&self.bytes.bytes())
+    /// Sets how deeply values may be nested before deserialization fails
+    /// with `ParseError::ExceededRecursionLimit`; `None` removes the limit.
+    ///
+    /// There's no limit by default. Untyped values, like `Value`, and
+    /// ignored ones are read without recursion, so they can be nested
+    /// arbitrarily deep. Typed values are read recursively, as their
+    /// `Deserialize` implementations ask for their contents, so a limit
+    /// keeps untrusted input from overflowing the stack.
+    pub fn set_recursion_limit(&mut self, limit: Option<usize>) {
+        self.recursion_limit = limit;
+    }
+
+    /// Makes structs require their name, e.g. `Point(x: 1, y: 2)`
+    /// instead of `(x: 1, y: 2)`, and fail with
+    /// `ParseError::StructNameMismatch` if it's another one.
+    ///
+    /// This catches files which are read as the wrong type.
+    pub fn set_strict_struct_names(&mut self, strict: bool) {
+        self.strict_struct_names = strict;
+    }
+
+    /// Sets what to do with map keys which are written more than once,
+    /// like `{ "a": 1, "a": 2 }`. By default, this fails with
+    /// `ParseError::DuplicateKey`.
+    ///
+    /// Keys are compared as they're written, so `1` and `0x1`
+    /// aren't recognized as the same key.
+    pub fn set_duplicate_keys(&mut self, policy: DuplicateKeys) {
+        self.duplicate_keys = policy;
+    }
+
+    /// Sets whether struct fields which are written more than once,
+    /// like `(x: 1, x: 2)`, fail with `ParseError::DuplicateField`.
+    ///
+    /// This is the default. Otherwise every field is passed on, and
+    /// the struct decides; derived implementations fail without
+    /// telling where the field was written first.
+    pub fn set_reject_duplicate_fields(&mut self, reject: bool) {
+        self.reject_duplicate_fields = reject;
+    }
+
+    /// Makes struct fields match names which are written in another
+    /// case, so that `maxWidth`, `max-width` and `MAX_WIDTH` are all read
+    /// as `max_width`; a field with exactly the written name is preferred.
+    ///
+    /// This helps with files which were converted from other formats.
+    pub fn set_lenient_field_names(&mut self, lenient: bool) {
+        self.lenient_field_names = lenient;
+    }
+
+    /// Rejects what the grammar doesn't allow, but is read anyway by
+    /// default: structs in place of maps, like `Size(w: 1)` for a
+    /// `HashMap`, and a second trailing comma, like `(1, 2,,)`.
+    ///
+    /// This checks that files conform to the grammar exactly, so that
+    /// other RON implementations can read them as well. The leniencies
+    /// which are opt-in, like `set_json`, are still allowed.
+    pub fn set_strict_grammar(&mut self, strict: bool) {
+        self.strict_grammar = strict;
+    }
+
+    /// Allows enum variants to be written by their index, so that `2`
+    /// is read as the third variant and `1(true)` as the second one
+    /// with its value. Struct fields can be written by index as well.
+    ///
+    /// This is meant for compact, machine-generated files;
+    /// the serializer still writes variants by name.
+    pub fn set_numeric_variants(&mut self, numeric: bool) {
+        self.numeric_variants = numeric;
+    }
+
+    /// Allows structs to be written as maps with quoted field names,
+    /// like `{ "width": 800, "height": 600 }`, which is how JSON
+    /// writes them.
+    ///
+    /// This eases moving JSON configs to RON one file at a time.
+    /// The map keys are checked like `set_duplicate_keys` says,
+    /// and struct names aren't required even if they're strict.
+    pub fn set_maps_as_structs(&mut self, maps: bool) {
+        self.maps_as_structs = maps;
+    }
+
+    /// Reads strings in place of byte arrays as base64, like
+    /// `"R0lGODlh"`, which is how `PrettyConfig::base64_bytes` writes
+    /// them, instead of as the UTF-8 of the string.
+    ///
+    /// This applies to values deserialized as bytes, like
+    /// `serde_bytes::ByteBuf`, and to `Vec<u8>`.
+    pub fn set_base64_bytes(&mut self, base64: bool) {
+        self.base64_bytes = base64;
+    }
+
+    /// Accepts JSON as well, so that JSON configs can be read as they are.
+    ///
+    /// This adds `null` for `None` and units, and objects like
+    /// `{ "width": 800 }` for structs. Optional values don't need
+    /// `Some(...)`, arrays can be tuples, and enums can be written like
+    /// serde_json writes them: `"Variant"` or `{ "Variant": ... }`.
+    /// Strings can have JSON's escapes, like `\u00e9`.
+    pub fn set_json(&mut self, json: bool) {
+        self.bytes.json = json;
+    }
+
+    /// Replaces escaped surrogates which aren't part of a pair, like
+    /// the `\ud83d` of JSON's `"\ud83d!"` or `\u{d83d}`, with U+FFFD,
+    /// rather than failing with `ParseError::InvalidEscape`.
+    ///
+    /// This salvages strings which were cut in the middle of a char
+    /// by a tool counting UTF-16 units; the char is lost either way.
+    pub fn set_replace_lone_surrogates(&mut self, replace: bool) {
+        self.bytes.replace_lone_surrogates = replace;
+    }
+
+    /// Reads the anchors and references which `Shared` values are written
+    /// with, like `&0 (x: 1)` and `*0`; otherwise they fail with
+    /// `ParseError::UnexpectedAnchor`.
+    ///
+    /// References can only be read into a `Shared` or `SharedArc`, which
+    /// shares the value of the anchor without reading it again. Other
+    /// values just skip their anchor. References are resolved by `Options`
+    /// and the functions using it, which keep the anchors of a document.
+    pub fn set_shared_values(&mut self, shared: bool) {
+        self.shared_values = shared;
+    }
+
+    /// Interns the strings deserialized as `Interned`, so that identical
+    /// strings share a single buffer.
+    ///
+    /// The strings are kept until the deserializer is dropped,
+    /// including those of every value of a `StreamDeserializer`.
+    pub fn set_intern_strings(&mut self, intern: bool) {
+        self.strings = match intern {
+            true => Some(self.strings.take().unwrap_or_default()),
+            false => None,
+        };
+    }
+
+    /// Sets limits for reading untrusted input, see `Limits`.
+    ///
+    /// `max_input_len` is only checked by `Options`, as the input
+    /// of a deserializer is already in memory.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Makes errors tell where the value they're about is, by wrapping
+    /// them in `Error::Path` with a path like `entities[3].transform.scale`.
+    ///
+    /// Errors in the outermost value itself aren't wrapped.
+    pub fn set_error_paths(&mut self, error_paths: bool) {
+        self.error_paths = error_paths;
+    }
+
+    /// Reads whitespace separated values until the end of the input,
+    /// see `StreamDeserializer`.
+    #[allow(clippy::should_implement_trait)]
+    pub fn into_iter<T>(self) -> StreamDeserializer<'de, T>
+    where
+        T: de::Deserialize<'de>,
+    {
+        StreamDeserializer::new(self)
+    }
+
+    /// Adds the current position to custom errors of the value.
+    fn locate(&self, error: Error) -> Error {
+        match error {
+            Error::Message(msg) => self.bytes.error(ParseError::Message(msg)),
+            e => e,
+        }
+    }
+
+    /// Skips the whitespace before the end of a sequence, map or struct.
+    ///
+    /// The elements read the comma after them already, but another one
+    /// is skipped as well unless the grammar is strict.
+    fn skip_trailing_comma(&mut self) -> Result<()> {
+        if self.strict_grammar {
+            self.bytes.skip_ws()
+        } else {
+            self.bytes.comma().map(|_| ())
+        }
+    }
+
+    /// Runs `f` for the value at `segment`, which is added to the path
+    /// of the errors it returns if paths are tracked.
+    fn at_path<T, F>(&mut self, segment: Segment<'de>, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        if !self.error_paths {
+            return f(self);
+        }
+
+        self.path.push(segment);
+        let res = f(self).map_err(|e| match e {
+            Error::Path { .. } => e,
+            e => Error::Path {
+                path: path::format(&self.path),
+                error: Box::new(self.locate(e)),
+            },
+        });
+        self.path.pop();
+
+        res
+    }
+
+    /// Counts an element or entry, the `len`th of its collection,
+    /// towards the limits.
+    fn count_element(&mut self, len: usize) -> Result<()> {
+        self.nodes += 1;
+
+        match self.limits {
+            Limits {
+                max_elements: Some(max),
+                ..
+            } if len > max => self.bytes.err(ParseError::ExceededElementLimit),
+            Limits {
+                max_nodes: Some(max),
+                ..
+            } if self.nodes > max => self.bytes.err(ParseError::ExceededNodeLimit),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the next map key or struct field of the collection which
+    /// ends at `terminator` as it's written, together with the input
+    /// after it, if it can be skipped.
+    ///
+    /// Quoted field names, like `"width"`, are returned without the quotes,
+    /// so that they are the same as unquoted ones.
+    fn key_text(&self, terminator: u8) -> Option<(&'de [u8], Bytes<'de>)> {
+        let mut end = self.bytes;
+        let key = end.value_text().ok()?;
+
+        Some((unquote_field(terminator, key), end))
+    }
+
+    /// What to do with the keys of a map, or the fields of a struct,
+    /// which ends at `terminator` and are written more than once.
+    fn duplicate_policy(&self, terminator: u8) -> DuplicateKeys {
+        match terminator {
+            b'}' => self.duplicate_keys,
+            _ if self.reject_duplicate_fields => DuplicateKeys::Error,
+            _ => DuplicateKeys::KeepLast,
+        }
+    }
+
+    /// With `DuplicateKeys::KeepFirst`, checks whether the next map key
+    /// is one of `keys`, and skips its entry up to the comma after it if
+    /// so. Returns whether the entry has been skipped.
+    ///
+    /// This has to look at the key before it's read, so the other
+    /// policies are handled by `check_duplicate_key` instead.
+    fn skip_duplicate_key(
+        &mut self,
+        terminator: u8,
+        keys: &mut HashMap<&'de [u8], Position>,
+    ) -> Result<bool> {
+        if self.duplicate_policy(terminator) != DuplicateKeys::KeepFirst {
+            return Ok(false);
+        }
+
+        let start = self.bytes;
+        let (key, end) = match self.key_text(terminator) {
+            Some(key) => key,
+            // Reading the key reports the error
+            None => return Ok(false),
+        };
+
+        if !keys.contains_key(key) {
+            keys.insert(key, start.position());
+
+            return Ok(false);
+        }
+
+        self.bytes = end;
+        if !self.bytes.consume(":") {
+            return self.bytes.expected(ParseError::ExpectedMapColon);
+        }
+        self.bytes.skip_value()?;
+
+        Ok(true)
+    }
+
+    /// With `DuplicateKeys::Error`, fails if the key which has just been
+    /// read from `start` on is one of `keys`, and adds it otherwise.
+    ///
+    /// The key is compared as it was read, without scanning it again.
+    fn check_duplicate_key(
+        &self,
+        terminator: u8,
+        keys: &mut HashMap<&'de [u8], Position>,
+        start: Bytes<'de>,
+    ) -> Result<()> {
+        if self.duplicate_policy(terminator) != DuplicateKeys::Error {
+            return Ok(());
+        }
+
+        let len = start.bytes().len() - self.bytes.bytes().len();
+        let mut key = &start.bytes()[..len];
+        while let [rest @ .., b' ' | b'\t' | b'\n' | b'\r'] = key {
+            key = rest;
+        }
+        let key = unquote_field(terminator, key);
+
+        let first = match keys.entry(key) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                entry.insert(start.position());
+
+                return Ok(());
+            }
+        };
+
+        let key = String::from_utf8_lossy(key).into_owned();
+        let kind = if terminator == b'}' {
+            ParseError::DuplicateKey { key, first }
+        } else {
+            ParseError::DuplicateField { field: key, first }
+        };
+
+        Err(start.error(kind))
+    }
+
+    /// Runs `f` for a value nested one level deeper.
+    ///
+    /// Values are parsed recursively, as the `Deserialize` implementations
+    /// ask for their contents, so the depth may be limited.
+    fn nested<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        self.depth += 1;
+        let res = match self.recursion_limit {
+            Some(limit) if self.depth > limit => {
+                self.bytes.err(ParseError::ExceededRecursionLimit)
+            }
+            _ => f(self),
+        };
+        self.depth -= 1;
+
+        res
+    }
+
+    pub fn remainder(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.bytes.bytes())
+    }
+
+    /// The extensions which are enabled, by the attributes at the start
+    /// of the input or by `Options::with_default_extensions`.
+    pub fn extensions(&self) -> Extensions {
+        self.bytes.exts
+    }
+
+    /// How many bytes of the input have been read so far, so that
+    /// `&input[de.offset()..]` is what `remainder` returns.
+    pub fn offset(&self) -> usize {
+        self.bytes.offset()
+    }
+
+    /// The line, column and offset of the next byte to be read.
+    ///
+    /// ```
+    /// extern crate ron;
+    /// extern crate serde;
+    ///
+    /// use ron::de::Deserializer;
+    /// use serde::Deserialize;
+    ///
+    /// let input = "[1, 2]\n[3]";
+    /// let mut de = Deserializer::from_str(input).unwrap();
+    /// let first = Vec::<u8>::deserialize(&mut de).unwrap();
+    ///
+    /// assert_eq!(first, vec![1, 2]);
+    /// assert_eq!(de.position().line, 1);
+    /// assert_eq!(de.position().col, 7);
+    /// assert_eq!(&input[de.offset()..], "\n[3]");
+    /// ```
+    pub fn position(&self) -> Position {
+        self.bytes.position()
     }
 }
 
 /// A convenience function for reading data from a reader
 /// and feeding into a deserializer.
-pub fn from_reader<R, T>(mut rdr: R) -> Result<T>
+///
+/// The whole input is read before deserializing it, so that
+/// e.g. a `File` can be passed in directly:
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// let input: &[u8] = b"{ \"width\": 800, \"height\": 600 }";
+/// let size: HashMap<String, u32> = ron::de::from_reader(input).unwrap();
+///
+/// assert_eq!(size["width"], 800);
+/// ```
+///
+/// Errors while reading are returned as `Error::IoError`. Inputs which
+/// are too large to read at once can be read value by value with a
+/// `ReaderDeserializer`.
+pub fn from_reader<R, T>(rdr: R) -> Result<T>
 where
     R: io::Read,
     T: de::DeserializeOwned,
 {
-    let mut bytes = Vec::new();
-    rdr.read_to_end(&mut bytes)?;
+    Options::default().from_reader(rdr)
+}
+
+/// Like `from_reader`, but deserializes with `seed`, see `from_str_seed`.
+pub fn from_reader_seed<R, S, T>(rdr: R, seed: S) -> Result<T>
+where
+    R: io::Read,
+    S: for<'a> DeserializeSeed<'a, Value = T>,
+{
+    Options::default().from_reader_seed(rdr, seed)
+}
 
-    from_bytes(&bytes)
+/// Like `from_reader`, but reads from an `AsyncRead` like a
+/// `tokio::fs::File` without blocking, by awaiting the returned future.
+///
+/// The whole input is read before deserializing it, like
+/// `from_reader` does. This requires the `async` feature.
+#[cfg(feature = "async")]
+pub fn from_async_reader<R, T>(rdr: R) -> FromAsyncReader<R, T>
+where
+    R: ::tokio::io::AsyncRead + Unpin,
+    T: de::DeserializeOwned,
+{
+    Options::default().from_async_reader(rdr)
 }
 
 /// A convenience function for building a deserializer
@@ -61,21 +537,131 @@ pub fn from_str<'a, T>(s: &'a str) -> Result<T>
 where
     T: de::Deserialize<'a>,
 {
-    from_bytes(s.as_bytes())
+    Options::default().from_str(s)
+}
+
+/// Deserializes a value from a string with `seed`, which can carry
+/// state the value needs, like a registry which assets are added to.
+///
+/// ```
+/// extern crate ron;
+/// extern crate serde;
+///
+/// use serde::de::{Deserialize, DeserializeSeed, Deserializer};
+///
+/// /// Adds the names it reads to the registry, and returns their ids.
+/// struct Register<'r>(&'r mut Vec<String>);
+///
+/// impl<'de, 'r> DeserializeSeed<'de> for Register<'r> {
+///     type Value = usize;
+///
+///     fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<usize, D::Error> {
+///         self.0.push(String::deserialize(deserializer)?);
+///
+///         Ok(self.0.len() - 1)
+///     }
+/// }
+///
+/// let mut registry = vec!["grass".to_owned()];
+/// let id = ron::de::from_str_seed("\"rock\"", Register(&mut registry)).unwrap();
+///
+/// assert_eq!(id, 1);
+/// assert_eq!(registry, vec!["grass", "rock"]);
+/// ```
+pub fn from_str_seed<'a, S>(s: &'a str, seed: S) -> Result<S::Value>
+where
+    S: DeserializeSeed<'a>,
+{
+    Options::default().from_str_seed(s, seed)
+}
+
+/// Like `from_str`, but reports every syntax error in the string
+/// instead of only the first one, for editors and validators which
+/// show all the problems of a file at once.
+///
+/// After a syntax error, parsing resumes after the next `,` or at the
+/// end of the enclosing collection. The value is only deserialized if
+/// there are no syntax errors, so a type error is found only then.
+///
+/// ```
+/// use ron::de::{from_str_recovering, Error, ParseError};
+///
+/// let (value, errors) = from_str_recovering::<Vec<(u8, u8)>>("[(1, 2), (3 4), (5, 6]");
+///
+/// assert_eq!(value, None);
+/// assert_eq!(errors.len(), 2);
+///
+/// match errors[1] {
+///     Error::Parser(ParseError::ExpectedComma(_), position) => assert_eq!(position.col, 22),
+///     ref other => panic!("{:?}", other),
+/// }
+/// ```
+pub fn from_str_recovering<'a, T>(s: &'a str) -> (Option<T>, Vec<Error>)
+where
+    T: de::Deserialize<'a>,
+{
+    Options::default().from_str_recovering(s)
+}
+
+/// Deserializes a value of type `T` from the start of a string, and
+/// returns it together with the byte offset right after the value.
+///
+/// Unlike `from_str`, anything may follow the value, e.g. when it's
+/// embedded in a larger file:
+///
+/// ```
+/// use ron::de::from_str_partial;
+///
+/// let message = "(1, 2) -- sent by the server";
+/// let (point, offset): ((u8, u8), _) = from_str_partial(message).unwrap();
+///
+/// assert_eq!(point, (1, 2));
+/// assert_eq!(&message[offset..], " -- sent by the server");
+/// ```
+pub fn from_str_partial<'a, T>(s: &'a str) -> Result<(T, usize)>
+where
+    T: de::Deserialize<'a>,
+{
+    Options::default().from_str_partial(s)
 }
 
 /// A convenience function for building a deserializer
 /// and deserializing a value of type `T` from bytes.
+///
+/// The input doesn't have to be valid UTF-8 as a whole, but strings
+/// and chars do. If they aren't, this fails with `ParseError::Utf8Error`
+/// rather than replacing the invalid bytes. Identifiers are ASCII only,
+/// and other bytes, like the contents of comments, aren't checked.
+///
+/// ```
+/// use ron::de::{from_bytes, Error, ParseError};
+///
+/// assert_eq!(from_bytes::<String>(b"\"caf\xc3\xa9\""), Ok("caf\u{e9}".to_owned()));
+///
+/// match from_bytes::<String>(b"\"caf\xe9\"") {
+///     Err(Error::Parser(ParseError::Utf8Error(_), _)) => {}
+///     other => panic!("{:?}", other),
+/// }
+/// ```
+///
+/// Anchors and references of `Shared` values are only read
+/// with `Options::with_shared_values`.
+///
+/// Custom errors of the value, like a missing struct field, are
+/// returned as `ParseError::Message` with the position they occurred at.
 pub fn from_bytes<'a, T>(s: &'a [u8]) -> Result<T>
 where
     T: de::Deserialize<'a>,
 {
-    let mut deserializer = Deserializer::from_bytes(s)?;
-    let t = T::deserialize(&mut deserializer)?;
-
-    deserializer.end()?;
+    Options::default().from_bytes(s)
+}
 
-    Ok(t)
+/// Like `from_bytes`, but deserializes with `seed`, see `from_str_seed`.
+pub fn from_bytes_seed<'a, S>(s: &'a [u8], seed: S) -> Result<S::Value>
+where
+    S: DeserializeSeed<'a>,
+{
+    Options::default().from_bytes_seed(s, seed)
 }
 
 impl<'de> Deserializer<'de> {
@@ -92,13 +678,18 @@ impl<'de> Deserializer<'de> {
     }
 }
 
-impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     type Error = Error;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        // Without a type, values can't be shared, so their anchors are skipped
+        while self.bytes.peek() == Some(b'&') {
+            self.anchor_id()?;
+        }
+
         if self.bytes.consume_ident("true") {
             return visitor.visit_bool(true);
         } else if self.bytes.consume_ident("false") {
@@ -109,19 +700,52 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
             return visitor.visit_none();
         } else if self.bytes.consume("()") {
             return visitor.visit_unit();
+        } else if self.bytes.json && self.bytes.consume_ident("null") {
+            return visitor.visit_none();
         }
 
-        if self.bytes.identifier().is_ok() {
+        if self.bytes.check_hex_bytes() || self.bytes.check_byte_string() {
+            return self.deserialize_seq(visitor);
+        }
+
+        if self.bytes.peek() == Some(b'*') {
+            return match self.shared_values {
+                true => self.bytes.err(ParseError::UntypedReference),
+                false => self.bytes.err(ParseError::UnexpectedAnchor),
+            };
+        }
+
+        if self.bytes.check_non_finite_float() {
+            return self.deserialize_f64(visitor);
+        }
+
+        if self.bytes.check_raw_string() {
+            return self.deserialize_string(visitor);
+        }
+
+        if let Some(ident) = self.bytes.try_identifier() {
             self.bytes.skip_ws()?;
 
+            if self.bytes.check_tuple() {
+                return self.deserialize_tuple(0, visitor);
+            } else if self.bytes.peek() != Some(b'(') {
+                // A unit enum variant, which serde reads from its name
+                return visitor.visit_str(str::from_utf8(ident).unwrap());
+            }
+
             return self.deserialize_struct("", &[], visitor);
         }
 
+        if self.bytes.check_based_integer() {
+            return self.deserialize_u64(visitor);
+        }
+
         match self.bytes.peek_or_eof()? {
+            b'(' if self.bytes.check_tuple() => self.deserialize_tuple(0, visitor),
             b'(' => self.deserialize_struct("", &[], visitor),
             b'[' => self.deserialize_seq(visitor),
             b'{' => self.deserialize_map(visitor),
-            b'0'...b'9' | b'+' | b'-' | b'.' => self.deserialize_f64(visitor),
+            b'0'..=b'9' | b'+' | b'-' | b'.' => self.deserialize_number(visitor),
             b'"' => self.deserialize_string(visitor),
             b'\'' => self.deserialize_char(visitor),
             other => self.bytes.err(ParseError::UnexpectedByte(other as char)),
@@ -139,56 +763,70 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_i8(self.bytes.signed_integer()?)
+        visitor.visit_i8(self.bytes.integer()?)
     }
 
     fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_i16(self.bytes.signed_integer()?)
+        visitor.visit_i16(self.bytes.integer()?)
     }
 
     fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_i32(self.bytes.signed_integer()?)
+        visitor.visit_i32(self.bytes.integer()?)
     }
 
     fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_i64(self.bytes.signed_integer()?)
+        visitor.visit_i64(self.bytes.integer()?)
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i128(self.bytes.integer()?)
     }
 
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u8(self.bytes.unsigned_integer()?)
+        visitor.visit_u8(self.bytes.integer()?)
     }
 
     fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u16(self.bytes.unsigned_integer()?)
+        visitor.visit_u16(self.bytes.integer()?)
     }
 
     fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u32(self.bytes.unsigned_integer()?)
+        visitor.visit_u32(self.bytes.integer()?)
     }
 
     fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u64(self.bytes.unsigned_integer()?)
+        visitor.visit_u64(self.bytes.integer()?)
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u128(self.bytes.integer()?)
     }
 
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
@@ -218,9 +856,18 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     {
         use parse::ParsedStr;
 
+        let start = self.bytes;
         match self.bytes.string()? {
-            ParsedStr::Allocated(s) => visitor.visit_string(s),
-            ParsedStr::Slice(s) => visitor.visit_str(s),
+            ParsedStr::Allocated(s) => {
+                self.check_string_len(start, s.len())?;
+
+                visitor.visit_string(s)
+            }
+            ParsedStr::Slice(s) => {
+                self.check_string_len(start, s.len())?;
+
+                visitor.visit_str(s)
+            }
         }
     }
 
@@ -235,25 +882,73 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        self.deserialize_byte_buf(visitor)
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        use parse::{ParsedBytes, ParsedStr};
+
+        let start = self.bytes;
+        if self.bytes.check_byte_string() {
+            match self.bytes.byte_string()? {
+                ParsedBytes::Allocated(bytes) => {
+                    self.check_string_len(start, bytes.len())?;
+
+                    visitor.visit_byte_buf(bytes)
+                }
+                ParsedBytes::Slice(bytes) => {
+                    self.check_string_len(start, bytes.len())?;
+
+                    visitor.visit_borrowed_bytes(bytes)
+                }
+            }
+        } else if self.base64_bytes && self.bytes.peek() == Some(b'"') {
+            let bytes = self.bytes.base64_bytes()?;
+            self.check_string_len(start, bytes.len())?;
+
+            visitor.visit_byte_buf(bytes)
+        } else if self.bytes.peek() == Some(b'"') {
+            // Strings without escapes are borrowed from the input
+            match self.bytes.string()? {
+                ParsedStr::Allocated(s) => {
+                    self.check_string_len(start, s.len())?;
+
+                    visitor.visit_byte_buf(s.into_bytes())
+                }
+                ParsedStr::Slice(s) => {
+                    self.check_string_len(start, s.len())?;
+
+                    visitor.visit_borrowed_bytes(s.as_bytes())
+                }
+            }
+        } else if self.bytes.check_hex_bytes() {
+            let bytes = self.bytes.hex_bytes()?;
+            self.check_string_len(start, bytes.len())?;
+
+            visitor.visit_byte_buf(bytes)
+        } else {
+            self.deserialize_seq(visitor)
+        }
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        if self.bytes.consume("None") {
+        let json = self.bytes.json;
+
+        if self.bytes.consume("None") || (json && self.bytes.consume_ident("null")) {
             visitor.visit_none()
         } else {
-            if self.bytes.exts.contains(Extensions::IMPLICIT_SOME) {
-                visitor.visit_some(&mut *self)
+            // An explicit `Some(...)` is still read as one, which is how
+            // `Some(None)` is written with `implicit_some`
+            if (self.bytes.exts.contains(Extensions::IMPLICIT_SOME) || json)
+                && !self.bytes.check_ident("Some")
+            {
+                self.nested(|de| visitor.visit_some(de))
             } else {
                 if self.bytes.consume("Some") && {
                     self.bytes.skip_ws()?;
@@ -261,17 +956,17 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
                 } {
                     self.bytes.skip_ws()?;
 
-                    let v = visitor.visit_some(&mut *self)?;
+                    let v = self.nested(|de| visitor.visit_some(de))?;
 
                     self.bytes.skip_ws()?;
 
                     if self.bytes.consume(")") {
                         Ok(v)
                     } else {
-                        self.bytes.err(ParseError::ExpectedOptionEnd)
+                        self.bytes.expected(ParseError::ExpectedOptionEnd)
                     }
                 } else {
-                    self.bytes.err(ParseError::ExpectedOption)
+                    self.bytes.expected(ParseError::ExpectedOption)
                 }
             }
         }
@@ -282,10 +977,10 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        if self.bytes.consume("()") {
+        if self.bytes.consume("()") || (self.bytes.json && self.bytes.consume_ident("null")) {
             visitor.visit_unit()
         } else {
-            self.bytes.err(ParseError::ExpectedUnit)
+            self.bytes.expected(ParseError::ExpectedUnit)
         }
     }
 
@@ -293,6 +988,12 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        if self.strict_struct_names {
+            self.struct_name(name)?;
+
+            return visitor.visit_unit();
+        }
+
         if self.bytes.consume(name) {
             visitor.visit_unit()
         } else {
@@ -304,44 +1005,66 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        if self.bytes.exts.contains(Extensions::UNWRAP_NEWTYPES) {
-            return visitor.visit_newtype_struct(&mut *self);
+        if name == UNTYPED_VALUE {
+            value::hand_over(value::parse(self)?);
+
+            return visitor.visit_unit();
+        }
+        if name == RAW_VALUE {
+            return self.deserialize_raw_value(visitor);
+        }
+        if name == INTERNED && self.strings.is_some() {
+            return self.deserialize_interned(visitor);
+        }
+        if name == INTERNED {
+            return de::Deserializer::deserialize_str(self, visitor);
+        }
+
+        if self.bytes.exts.contains(Extensions::UNWRAP_NEWTYPES) || self.check_json_value() {
+            return self.nested(|de| visitor.visit_newtype_struct(de));
         }
 
-        self.bytes.consume(name);
+        self.struct_name(name)?;
 
         self.bytes.skip_ws()?;
 
         if self.bytes.consume("(") {
             self.bytes.skip_ws()?;
-            let value = visitor.visit_newtype_struct(&mut *self)?;
+            let value = self.nested(|de| visitor.visit_newtype_struct(de))?;
             self.bytes.comma()?;
 
             if self.bytes.consume(")") {
                 Ok(value)
             } else {
-                self.bytes.err(ParseError::ExpectedStructEnd)
+                self.bytes.expected(ParseError::ExpectedStructEnd)
             }
         } else {
-            self.bytes.err(ParseError::ExpectedStruct)
+            self.bytes.expected(ParseError::ExpectedStruct)
         }
     }
 
-    fn deserialize_seq<V>(mut self, visitor: V) -> Result<V::Value>
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        if self.bytes.check_hex_bytes()
+            || self.bytes.check_byte_string()
+            || (self.base64_bytes && self.bytes.peek() == Some(b'"'))
+        {
+            return self.deserialize_byte_seq(visitor);
+        }
+
         if self.bytes.consume("[") {
-            let value = visitor.visit_seq(CommaSeparated::new(b']', &mut self))?;
-            self.bytes.comma()?;
+            let value = self.nested(|de| visitor.visit_seq(CommaSeparated::new(b']', de)))?;
+            self.skip_trailing_comma()?;
 
             if self.bytes.consume("]") {
                 Ok(value)
             } else {
-                self.bytes.err(ParseError::ExpectedArrayEnd)
+                self.bytes.expected(ParseError::ExpectedArrayEnd)
             }
         } else {
-            self.bytes.err(ParseError::ExpectedArray)
+            self.bytes.expected(ParseError::ExpectedArray)
         }
     }
 
@@ -351,21 +1074,25 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     // As indicated by the length parameter, the `Deserialize` implementation
     // for a tuple in the Serde data model is required to know the length of the
     // tuple before even looking at the input data.
-    fn deserialize_tuple<V>(mut self, _len: usize, visitor: V) -> Result<V::Value>
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        if self.bytes.json && self.bytes.peek() == Some(b'[') {
+            return self.deserialize_seq(visitor);
+        }
+
         if self.bytes.consume("(") {
-            let value = visitor.visit_seq(CommaSeparated::new(b')', &mut self))?;
-            self.bytes.comma()?;
+            let value = self.nested(|de| visitor.visit_seq(CommaSeparated::new(b')', de)))?;
+            self.skip_trailing_comma()?;
 
             if self.bytes.consume(")") {
                 Ok(value)
             } else {
-                self.bytes.err(ParseError::ExpectedArrayEnd)
+                self.bytes.expected(ParseError::ExpectedArrayEnd)
             }
         } else {
-            self.bytes.err(ParseError::ExpectedArray)
+            self.bytes.expected(ParseError::ExpectedArray)
         }
     }
 
@@ -378,71 +1105,126 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.bytes.consume(name);
+        if name == SHARED {
+            return self.deserialize_shared(visitor);
+        }
+
+        self.struct_name(name)?;
         self.deserialize_tuple(len, visitor)
     }
 
-    fn deserialize_map<V>(mut self, visitor: V) -> Result<V::Value>
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
         if self.bytes.consume("{") {
-            let value = visitor.visit_map(CommaSeparated::new(b'}', &mut self))?;
-            self.bytes.comma()?;
+            let value = self.nested(|de| visitor.visit_map(CommaSeparated::new(b'}', de)))?;
+            self.skip_trailing_comma()?;
 
             if self.bytes.consume("}") {
                 Ok(value)
             } else {
-                self.bytes.err(ParseError::ExpectedMapEnd)
+                self.bytes.expected(ParseError::ExpectedMapEnd)
             }
+        } else if self.strict_grammar {
+            self.bytes.expected(ParseError::ExpectedMap)
         } else {
-            self.bytes.err(ParseError::ExpectedMap)
+            // Structs are read as maps of their fields as well,
+            // which is how `#[serde(flatten)]` reads them
+            let start = self.bytes;
+            let _ = self.bytes.identifier();
+            self.bytes.skip_ws()?;
+
+            if self.bytes.peek() == Some(b'(') {
+                self.deserialize_struct("", &[], visitor)
+            } else {
+                self.bytes = start;
+
+                self.bytes.expected(ParseError::ExpectedMap)
+            }
         }
     }
 
     fn deserialize_struct<V>(
-        mut self,
+        self,
         name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.bytes.consume(name);
+        if name == SPANNED {
+            return self.deserialize_spanned(visitor);
+        }
+
+        if (self.maps_as_structs || self.bytes.json) && self.bytes.peek() == Some(b'{') {
+            return self.deserialize_map(visitor);
+        }
+
+        let start = self.bytes;
+        self.struct_name(name)?;
 
         self.bytes.skip_ws()?;
 
         if self.bytes.consume("(") {
-            let value = visitor.visit_map(CommaSeparated::new(b')', &mut self))?;
-            self.bytes.comma()?;
+            let value = self.nested(|de| {
+                let mut access = CommaSeparated::new(b')', de);
+                access.fields = fields;
+
+                match visitor.visit_map(&mut access) {
+                    // Missing fields are reported once all fields have been
+                    // read, and point at the struct they are missing from
+                    Err(Error::Message(msg)) if access.finished => {
+                        Err(start.error(ParseError::Message(msg)))
+                    }
+                    res => res,
+                }
+            })?;
+            self.skip_trailing_comma()?;
 
             if self.bytes.consume(")") {
                 Ok(value)
             } else {
-                self.bytes.err(ParseError::ExpectedStructEnd)
+                self.bytes.expected(ParseError::ExpectedStructEnd)
             }
         } else {
-            self.bytes.err(ParseError::ExpectedStruct)
+            self.bytes.expected(ParseError::ExpectedStruct)
         }
     }
 
     fn deserialize_enum<V>(
         self,
         _name: &'static str,
-        _variants: &'static [&'static str],
+        variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_enum(Enum::new(self))
+        if self.bytes.json && self.bytes.consume("{") {
+            return self.deserialize_json_enum(variants, visitor);
+        }
+
+        visitor.visit_enum(Enum::new(self, variants))
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        // Names can be quoted, like the keys of a flattened map
+        if self.bytes.peek() == Some(b'"') {
+            return self.deserialize_str(visitor);
+        }
+
+        match self.bytes.peek() {
+            Some(b'0'..=b'9') if self.numeric_variants => {
+                return visitor.visit_u64(self.bytes.integer()?);
+            }
+            _ => {}
+        }
+
         visitor.visit_bytes(self.bytes.identifier()?)
     }
 
@@ -450,7 +1232,267 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_any(visitor)
+        self.bytes.skip_value()?;
+
+        visitor.visit_unit()
+    }
+}
+
+impl<'de> Deserializer<'de> {
+    /// Reads the optional name in front of a struct,
+    /// which has to be `name` if struct names are strict.
+    fn struct_name(&mut self, name: &'static str) -> Result<()> {
+        // Struct variants and untyped structs have no name to check
+        if !self.strict_struct_names || name.is_empty() {
+            let start = self.bytes;
+            if self.bytes.try_identifier() != Some(name.as_bytes()) {
+                self.bytes = start;
+            }
+
+            return Ok(());
+        }
+
+        let start = self.bytes;
+
+        match self.bytes.identifier() {
+            Ok(found) if found == name.as_bytes() => Ok(()),
+            Ok(found) => Err(start.error(ParseError::StructNameMismatch {
+                expected: name,
+                found: String::from_utf8_lossy(found).into_owned(),
+            })),
+            Err(_) => self.bytes.expected(ParseError::ExpectedStructName),
+        }
+    }
+
+    /// Reads a hex block or a byte string as a sequence of bytes.
+    ///
+    /// This is kept out of `deserialize_seq`, which is part of the
+    /// recursion of nested sequences.
+    fn deserialize_byte_seq<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let start = self.bytes;
+        let bytes = if self.bytes.check_hex_bytes() {
+            self.bytes.hex_bytes()?
+        } else if self.bytes.peek() == Some(b'"') {
+            self.bytes.base64_bytes()?
+        } else {
+            self.bytes.byte_string()?.into_owned()
+        };
+        self.check_string_len(start, bytes.len())?;
+
+        let mut seq = SeqDeserializer::<_, Error>::new(bytes.into_iter());
+        let value = visitor.visit_seq(&mut seq)?;
+        seq.end()?;
+
+        Ok(value)
+    }
+
+    /// Reads a `Spanned` value, as a map of its start, the value
+    /// and its end.
+    fn deserialize_spanned<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.bytes.skip_ws()?;
+        let start = self.bytes.offset();
+
+        visitor.visit_map(SpannedAccess {
+            de: self,
+            start,
+            state: 0,
+        })
+    }
+
+    /// Reads a `RawValue`, as the text of the next value.
+    fn deserialize_raw_value<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.bytes.skip_ws()?;
+        let start = self.bytes;
+
+        match str::from_utf8(self.bytes.value_text()?) {
+            Ok(ron) => visitor.visit_borrowed_str(ron),
+            Err(e) => Err(start.error(e.into())),
+        }
+    }
+
+    /// Checks for a value which JSON writes without a name, like the
+    /// value of a newtype struct, if JSON is accepted.
+    fn check_json_value(&mut self) -> bool {
+        self.bytes.json
+            && (self.bytes.peek().is_some_and(|b| b"\"[{-0123456789".contains(&b))
+                || ["true", "false", "null"].iter().any(|lit| self.bytes.check_ident(lit)))
+    }
+
+    /// Reads an enum written like `{ "Variant": ... }`, after the `{`.
+    fn deserialize_json_enum<V>(
+        &mut self,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.bytes.skip_ws()?;
+        let value = self.nested(|de| {
+            visitor.visit_enum(Enum {
+                braced: true,
+                ..Enum::new(de, variants)
+            })
+        })?;
+        self.bytes.comma()?;
+
+        if self.bytes.consume("}") {
+            Ok(value)
+        } else {
+            self.bytes.expected(ParseError::ExpectedMapEnd)
+        }
+    }
+
+    /// Reads a string, and hands the interned one over to the visitor
+    /// of an `Interned`.
+    fn deserialize_interned<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        use parse::ParsedStr;
+
+        let start = self.bytes;
+        let parsed = self.bytes.string()?;
+        let s = match parsed {
+            ParsedStr::Allocated(ref s) => &s[..],
+            ParsedStr::Slice(s) => s,
+        };
+        self.check_string_len(start, s.len())?;
+
+        let strings = self.strings.get_or_insert_with(HashSet::new);
+        let interned = match strings.get(s) {
+            Some(interned) => interned.clone(),
+            None => {
+                let interned: Arc<str> = s.into();
+                strings.insert(interned.clone());
+
+                interned
+            }
+        };
+        interned::hand_over(interned);
+
+        visitor.visit_unit()
+    }
+
+    /// Fails with `ParseError::ExceededStringLimit` if the string which
+    /// starts at `start` and is `len` bytes long is longer than its limit.
+    fn check_string_len(&self, start: Bytes, len: usize) -> Result<()> {
+        match self.limits.max_string_len {
+            Some(max) if len > max => Err(start.error(ParseError::ExceededStringLimit)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads a number without a type, as an integer if it's written
+    /// like one and fits into 64 bits, and as a float otherwise.
+    ///
+    /// Tagged enums and other buffered content depend on this,
+    /// as a float can't be read as an integer later. `-0` is read
+    /// as a float, to keep its sign.
+    fn deserialize_number<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.bytes.check_integer() {
+            let backup = self.bytes;
+
+            if self.bytes.peek() == Some(b'-') {
+                match self.bytes.integer() {
+                    Ok(0) => {}
+                    Ok(v) => return visitor.visit_i64(v),
+                    Err(_) => {}
+                }
+            } else if let Ok(v) = self.bytes.integer() {
+                return visitor.visit_u64(v);
+            }
+
+            self.bytes = backup;
+        }
+
+        de::Deserializer::deserialize_f64(self, visitor)
+    }
+
+    /// Reads a `Shared` value, which may have an anchor like `&0 value`
+    /// or refer to an earlier one like `*0`.
+    fn deserialize_shared<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let value = match self.bytes.peek() {
+            Some(b'&') => true,
+            Some(b'*') => false,
+            _ => return visitor.visit_newtype_struct(self),
+        };
+        let id = self.anchor_id()?;
+
+        self.nested(|de| {
+            visitor.visit_seq(SharedAccess {
+                de,
+                id: Some(id),
+                value,
+            })
+        })
+    }
+
+    /// Reads an anchor like `&0` or a reference like `*0`, which
+    /// comes next, and returns its id.
+    fn anchor_id(&mut self) -> Result<u64> {
+        if !self.shared_values {
+            return self.bytes.err(ParseError::UnexpectedAnchor);
+        }
+
+        self.bytes.advance_single()?;
+        let id = self.bytes.integer()?;
+        self.bytes.skip_ws()?;
+
+        Ok(id)
+    }
+}
+
+/// The anchor of a `Shared` value, followed by the value unless
+/// it is a reference.
+struct SharedAccess<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+    id: Option<u64>,
+    value: bool,
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for SharedAccess<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if let Some(id) = self.id.take() {
+            return seed.deserialize(de::value::U64Deserializer::new(id)).map(Some);
+        }
+
+        if self.value {
+            self.value = false;
+
+            return seed.deserialize(&mut *self.de).map(Some);
+        }
+
+        Ok(None)
+    }
+}
+
+/// Strips the quotes of a field name like `"width"` of a struct, which
+/// ends at `terminator`, so that it's the same as an unquoted one.
+fn unquote_field(terminator: u8, key: &[u8]) -> &[u8] {
+    match key {
+        [b'"', name @ .., b'"'] if terminator == b')' && !name.contains(&b'\\') => name,
+        _ => key,
     }
 }
 
@@ -458,6 +1500,16 @@ struct CommaSeparated<'a, 'de: 'a> {
     de: &'a mut Deserializer<'de>,
     terminator: u8,
     had_comma: bool,
+    /// Where the keys read so far were first written.
+    keys: HashMap<&'de [u8], Position>,
+    /// The fields of the struct which is read, if any.
+    fields: &'static [&'static str],
+    /// How many elements or entries have been read so far.
+    len: usize,
+    /// The key of the entry which is read, if paths are tracked.
+    key: Option<&'de [u8]>,
+    /// Whether all elements or entries have been read.
+    finished: bool,
 }
 
 impl<'a, 'de> CommaSeparated<'a, 'de> {
@@ -466,11 +1518,23 @@ impl<'a, 'de> CommaSeparated<'a, 'de> {
             de,
             terminator,
             had_comma: true,
+            keys: HashMap::new(),
+            fields: &[],
+            len: 0,
+            key: None,
+            finished: false,
         }
     }
 
-    fn err<T>(&self, kind: ParseError) -> Result<T> {
-        self.de.bytes.err(kind)
+    /// Counts the next element or entry towards the limits.
+    fn count_element(&mut self) -> Result<()> {
+        self.len += 1;
+
+        self.de.count_element(self.len)
+    }
+
+    fn expected<T>(&self, kind: Expected) -> Result<T> {
+        self.de.bytes.expected(kind)
     }
 
     fn has_element(&mut self) -> Result<bool> {
@@ -478,6 +1542,60 @@ impl<'a, 'de> CommaSeparated<'a, 'de> {
 
         Ok(self.had_comma && self.de.bytes.peek_or_eof()? != self.terminator)
     }
+
+    /// Skips the next entry if its key was written before and the
+    /// first one is kept, and returns whether it has been skipped.
+    fn skip_duplicate_key(&mut self) -> Result<bool> {
+        if !self.de.skip_duplicate_key(self.terminator, &mut self.keys)? {
+            return Ok(false);
+        }
+
+        self.had_comma = self.de.bytes.comma()?;
+
+        Ok(true)
+    }
+
+    /// Reads a field name which may be written in another case,
+    /// and returns the declared field it stands for.
+    fn lenient_field(&mut self) -> Result<Option<&'static str>> {
+        use parse::ParsedStr;
+
+        fn normalize(name: &[u8]) -> Vec<u8> {
+            name.iter()
+                .filter(|&&b| b != b'_' && b != b'-')
+                .map(u8::to_ascii_lowercase)
+                .collect()
+        }
+
+        let start = self.de.bytes;
+        let quoted;
+        let name = match self.de.bytes.peek() {
+            Some(b'"') => {
+                quoted = self.de.bytes.string()?;
+                match quoted {
+                    ParsedStr::Allocated(ref name) => name.as_bytes(),
+                    ParsedStr::Slice(name) => name.as_bytes(),
+                }
+            }
+            _ => self.de.bytes.dashed_identifier()?,
+        };
+        let normalized = normalize(name);
+
+        let field = self.fields
+            .iter()
+            .find(|field| field.as_bytes() == name)
+            .or_else(|| {
+                self.fields
+                    .iter()
+                    .find(|field| normalize(field.as_bytes()) == normalized)
+            });
+
+        if field.is_none() {
+            self.de.bytes = start;
+        }
+
+        Ok(field.cloned())
+    }
 }
 
 impl<'de, 'a> de::SeqAccess<'de> for CommaSeparated<'a, 'de> {
@@ -488,7 +1606,10 @@ impl<'de, 'a> de::SeqAccess<'de> for CommaSeparated<'a, 'de> {
         T: DeserializeSeed<'de>,
     {
         if self.has_element()? {
-            let res = seed.deserialize(&mut *self.de)?;
+            self.count_element()?;
+
+            let index = self.len - 1;
+            let res = self.de.at_path(Segment::Index(index), |de| seed.deserialize(de))?;
 
             self.had_comma = self.de.bytes.comma()?;
 
@@ -506,14 +1627,43 @@ impl<'de, 'a> de::MapAccess<'de> for CommaSeparated<'a, 'de> {
     where
         K: DeserializeSeed<'de>,
     {
+        while self.has_element()? && self.skip_duplicate_key()? {}
+
         if self.has_element()? {
-            if self.terminator == b')' {
-                seed.deserialize(&mut IdDeserializer::new(&mut *self.de))
-                    .map(Some)
+            self.count_element()?;
+
+            if self.de.error_paths {
+                self.key = self.de.key_text(self.terminator).map(|(key, _)| key);
+            }
+
+            // Errors about the key, like an unknown field, point at its start
+            let start = self.de.bytes;
+            let res = if self.terminator == b')' {
+                let field = match self.de.lenient_field_names {
+                    true => self.lenient_field()?,
+                    false => None,
+                };
+
+                match field {
+                    Some(field) => seed.deserialize(field.into_deserializer()),
+                    None => seed.deserialize(&mut IdDeserializer::new(&mut *self.de)),
+                }
             } else {
-                seed.deserialize(&mut *self.de).map(Some)
+                seed.deserialize(&mut *self.de)
+            };
+
+            match res {
+                Ok(key) => {
+                    self.de.check_duplicate_key(self.terminator, &mut self.keys, start)?;
+
+                    Ok(Some(key))
+                }
+                Err(Error::Message(msg)) => Err(start.error(ParseError::Message(msg))),
+                Err(e) => Err(e),
             }
         } else {
+            self.finished = true;
+
             Ok(None)
         }
     }
@@ -527,24 +1677,96 @@ impl<'de, 'a> de::MapAccess<'de> for CommaSeparated<'a, 'de> {
         if self.de.bytes.consume(":") {
             self.de.bytes.skip_ws()?;
 
-            let res = seed.deserialize(&mut *self.de)?;
+            let segment = match self.key.take() {
+                Some(key) if self.terminator == b')' => Segment::Field(key),
+                Some(key) => Segment::Key(key),
+                None => Segment::Unknown,
+            };
+            let res = self.de.at_path(segment, |de| seed.deserialize(de))?;
 
             self.had_comma = self.de.bytes.comma()?;
 
             Ok(res)
         } else {
-            self.err(ParseError::ExpectedMapColon)
+            self.expected(ParseError::ExpectedMapColon)
+        }
+    }
+}
+
+/// Passes a `Spanned` value on as the entries `start`, `value` and `end`.
+struct SpannedAccess<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+    start: usize,
+    /// How many entries have been passed on.
+    state: usize,
+}
+
+impl<'de, 'a> de::MapAccess<'de> for SpannedAccess<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let key = match self.state {
+            0 => START,
+            1 => VALUE,
+            2 => END,
+            _ => return Ok(None),
+        };
+
+        seed.deserialize(key.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        self.state += 1;
+
+        match self.state {
+            1 => seed.deserialize((self.start as u64).into_deserializer()),
+            2 => seed.deserialize(&mut *self.de),
+            _ => seed.deserialize((self.de.offset() as u64).into_deserializer()),
         }
     }
 }
 
 struct Enum<'a, 'de: 'a> {
     de: &'a mut Deserializer<'de>,
+    /// The names of the variants, for errors about unknown ones.
+    variants: &'static [&'static str],
+    /// Whether the enum is written like JSON's `{ "Variant": ... }`,
+    /// where the content follows a `:`.
+    braced: bool,
 }
 
 impl<'a, 'de> Enum<'a, 'de> {
-    fn new(de: &'a mut Deserializer<'de>) -> Self {
-        Enum { de }
+    fn new(de: &'a mut Deserializer<'de>, variants: &'static [&'static str]) -> Self {
+        Enum {
+            de,
+            variants,
+            braced: false,
+        }
+    }
+
+    /// Fails if the variant is numbered, but there are fewer variants.
+    fn check_variant_index(&self) -> Result<()> {
+        let mut bytes = self.de.bytes;
+        let index = match bytes.peek() {
+            Some(b'0'..=b'9') if self.de.numeric_variants && !self.variants.is_empty() => {
+                bytes.integer::<u64>()?
+            }
+            _ => return Ok(()),
+        };
+
+        if index < self.variants.len() as u64 {
+            Ok(())
+        } else {
+            let error = de::Error::unknown_variant(&index.to_string(), self.variants);
+
+            Err(self.de.locate(error))
+        }
     }
 }
 
@@ -557,8 +1779,22 @@ impl<'de, 'a> de::EnumAccess<'de> for Enum<'a, 'de> {
         V: DeserializeSeed<'de>,
     {
         self.de.bytes.skip_ws()?;
+        self.check_variant_index()?;
 
-        let value = seed.deserialize(&mut *self.de)?;
+        // Errors about the name point at its start
+        let start = self.de.bytes;
+        let value = seed.deserialize(&mut *self.de).map_err(|e| match e {
+            Error::Message(msg) => start.error(ParseError::Message(msg)),
+            e => e,
+        })?;
+
+        if self.braced {
+            self.de.bytes.skip_ws()?;
+            if !self.de.bytes.consume(":") {
+                return self.de.bytes.expected(ParseError::ExpectedMapColon);
+            }
+            self.de.bytes.skip_ws()?;
+        }
 
         Ok((value, self))
     }
@@ -568,6 +1804,10 @@ impl<'de, 'a> de::VariantAccess<'de> for Enum<'a, 'de> {
     type Error = Error;
 
     fn unit_variant(self) -> Result<()> {
+        if self.braced {
+            return de::Deserialize::deserialize(&mut *self.de);
+        }
+
         Ok(())
     }
 
@@ -577,20 +1817,24 @@ impl<'de, 'a> de::VariantAccess<'de> for Enum<'a, 'de> {
     {
         self.de.bytes.skip_ws()?;
 
+        if self.braced {
+            return seed.deserialize(&mut *self.de);
+        }
+
         if self.de.bytes.consume("(") {
             self.de.bytes.skip_ws()?;
 
-            let val = seed.deserialize(&mut *self.de)?;
+            let val = self.de.nested(|de| seed.deserialize(de))?;
 
             self.de.bytes.comma()?;
 
             if self.de.bytes.consume(")") {
                 Ok(val)
             } else {
-                self.de.bytes.err(ParseError::ExpectedStructEnd)
+                self.de.bytes.expected(ParseError::ExpectedStructEnd)
             }
         } else {
-            self.de.bytes.err(ParseError::ExpectedStruct)
+            self.de.bytes.expected(ParseError::ExpectedStruct)
         }
     }
 
@@ -0,0 +1,393 @@
+use std::io::{self, Read};
+use std::marker::PhantomData;
+
+use serde::de::{Deserialize, DeserializeOwned, DeserializeSeed};
+
+use extensions::Extensions;
+use shared::AnchorScope;
+
+use parse::Position;
+
+#[cfg(feature = "async")]
+use super::async_read::{self, FromAsyncReader};
+use super::recover::Recovery;
+use super::{Deserializer, Error, ParseError, Result};
+
+/// What to do with map keys which are written more than once,
+/// see `Deserializer::set_duplicate_keys`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Fail with `ParseError::DuplicateKey`.
+    Error,
+    /// Keep the value of the first entry and skip the others.
+    KeepFirst,
+    /// Pass every entry on, so that maps like `HashMap`
+    /// end up with the value of the last one.
+    KeepLast,
+}
+
+/// Limits for reading untrusted input, which make deserialization
+/// fail before it uses up too much memory, see `Deserializer::set_limits`.
+///
+/// Every limit is off by default.
+///
+/// ```
+/// use ron::de::{Error, Limits, Options, ParseError};
+///
+/// let options = Options::new().with_limits(Limits {
+///     max_elements: Some(3),
+///     ..Limits::default()
+/// });
+///
+/// assert_eq!(options.from_str("[1, 2, 3]"), Ok(vec![1, 2, 3]));
+///
+/// match options.from_str::<Vec<u8>>("[1, 2, 3, 4]") {
+///     Err(Error::Parser(ParseError::ExceededElementLimit, position)) => {
+///         assert_eq!(position.col, 11)
+///     }
+///     other => panic!("{:?}", other),
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// How long the input may be in bytes. This is checked by `Options`,
+    /// whose `from_reader` stops reading past the limit.
+    pub max_input_len: Option<usize>,
+    /// How long strings and byte strings may be in bytes.
+    pub max_string_len: Option<usize>,
+    /// How many elements a single sequence, tuple, map or struct may have.
+    pub max_elements: Option<usize>,
+    /// How many elements all sequences, tuples, maps and structs may have
+    /// together.
+    pub max_nodes: Option<usize>,
+}
+
+/// Configures the deserializer, for when the defaults `from_str`
+/// and the other functions use don't fit.
+///
+/// ```
+/// use ron::de::Options;
+///
+/// let options = Options::new().with_recursion_limit(Some(2));
+/// let grid: Vec<Vec<u8>> = options.from_str("[[1, 2], [3, 4]]").unwrap();
+///
+/// assert_eq!(grid, vec![vec![1, 2], vec![3, 4]]);
+/// assert!(options.from_str::<Vec<Vec<Vec<u8>>>>("[[[1]]]").is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    recursion_limit: Option<usize>,
+    strict_struct_names: bool,
+    default_extensions: Extensions,
+    duplicate_keys: DuplicateKeys,
+    reject_duplicate_fields: bool,
+    lenient_field_names: bool,
+    strict_grammar: bool,
+    numeric_variants: bool,
+    maps_as_structs: bool,
+    base64_bytes: bool,
+    shared_values: bool,
+    intern_strings: bool,
+    json: bool,
+    replace_lone_surrogates: bool,
+    limits: Limits,
+    error_paths: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            recursion_limit: None,
+            strict_struct_names: false,
+            default_extensions: Extensions::empty(),
+            duplicate_keys: DuplicateKeys::Error,
+            reject_duplicate_fields: true,
+            lenient_field_names: false,
+            strict_grammar: false,
+            numeric_variants: false,
+            maps_as_structs: false,
+            base64_bytes: false,
+            shared_values: false,
+            intern_strings: false,
+            json: false,
+            replace_lone_surrogates: false,
+            limits: Limits::default(),
+            error_paths: false,
+        }
+    }
+}
+
+impl Options {
+    pub fn new() -> Self {
+        Options::default()
+    }
+
+    /// Sets how deeply values may be nested, see
+    /// `Deserializer::set_recursion_limit`.
+    pub fn with_recursion_limit(mut self, limit: Option<usize>) -> Self {
+        self.recursion_limit = limit;
+
+        self
+    }
+
+    /// Sets whether structs have to be written with their name, see
+    /// `Deserializer::set_strict_struct_names`.
+    pub fn with_strict_struct_names(mut self, strict: bool) -> Self {
+        self.strict_struct_names = strict;
+
+        self
+    }
+
+    /// Enables `extensions` for every input, in addition to the ones
+    /// it enables with an attribute like `#![enable(implicit_some)]`.
+    pub fn with_default_extensions(mut self, extensions: Extensions) -> Self {
+        self.default_extensions = extensions;
+
+        self
+    }
+
+    /// Sets what to do with duplicate map keys, see
+    /// `Deserializer::set_duplicate_keys`.
+    pub fn with_duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicate_keys = policy;
+
+        self
+    }
+
+    /// Sets whether duplicate struct fields are rejected, see
+    /// `Deserializer::set_reject_duplicate_fields`.
+    pub fn with_reject_duplicate_fields(mut self, reject: bool) -> Self {
+        self.reject_duplicate_fields = reject;
+
+        self
+    }
+
+    /// Sets whether field names written in another case match, see
+    /// `Deserializer::set_lenient_field_names`.
+    pub fn with_lenient_field_names(mut self, lenient: bool) -> Self {
+        self.lenient_field_names = lenient;
+
+        self
+    }
+
+    /// Sets whether what the grammar doesn't allow is rejected, see
+    /// `Deserializer::set_strict_grammar`.
+    pub fn with_strict_grammar(mut self, strict: bool) -> Self {
+        self.strict_grammar = strict;
+
+        self
+    }
+
+    /// Sets whether enum variants can be written by index, see
+    /// `Deserializer::set_numeric_variants`.
+    pub fn with_numeric_variants(mut self, numeric: bool) -> Self {
+        self.numeric_variants = numeric;
+
+        self
+    }
+
+    /// Sets whether structs can be written as maps, see
+    /// `Deserializer::set_maps_as_structs`.
+    pub fn with_maps_as_structs(mut self, maps: bool) -> Self {
+        self.maps_as_structs = maps;
+
+        self
+    }
+
+    /// Sets whether strings are read as base64 in place of byte
+    /// arrays, see `Deserializer::set_base64_bytes`.
+    pub fn with_base64_bytes(mut self, base64: bool) -> Self {
+        self.base64_bytes = base64;
+
+        self
+    }
+
+    /// Sets whether the anchors and references of `Shared` values are
+    /// read, see `Deserializer::set_shared_values`.
+    pub fn with_shared_values(mut self, shared: bool) -> Self {
+        self.shared_values = shared;
+
+        self
+    }
+
+    /// Sets whether JSON is accepted as well, see `Deserializer::set_json`.
+    pub fn with_json(mut self, json: bool) -> Self {
+        self.json = json;
+
+        self
+    }
+
+    /// Sets whether escaped surrogates which aren't part of a pair are
+    /// replaced, see `Deserializer::set_replace_lone_surrogates`.
+    pub fn with_replace_lone_surrogates(mut self, replace: bool) -> Self {
+        self.replace_lone_surrogates = replace;
+
+        self
+    }
+
+    /// Sets whether strings deserialized as `Interned` are interned,
+    /// see `Deserializer::set_intern_strings`.
+    pub fn with_intern_strings(mut self, intern: bool) -> Self {
+        self.intern_strings = intern;
+
+        self
+    }
+
+    /// Sets limits for reading untrusted input, see `Limits`.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+
+        self
+    }
+
+    /// Sets whether errors tell where the value they're about is, see
+    /// `Deserializer::set_error_paths`.
+    pub fn with_error_paths(mut self, error_paths: bool) -> Self {
+        self.error_paths = error_paths;
+
+        self
+    }
+
+    /// Creates a deserializer for `input` which uses these options.
+    pub fn deserializer<'de>(&self, input: &'de [u8]) -> Result<Deserializer<'de>> {
+        match self.limits.max_input_len {
+            Some(max) if input.len() > max => {
+                let start = Position {
+                    col: 1,
+                    line: 1,
+                    offset: 0,
+                };
+
+                return Err(Error::Parser(ParseError::ExceededInputLimit, start));
+            }
+            _ => {}
+        }
+
+        let mut de = Deserializer::from_bytes(input)?;
+        de.recursion_limit = self.recursion_limit;
+        de.strict_struct_names = self.strict_struct_names;
+        de.bytes.exts |= self.default_extensions;
+        de.duplicate_keys = self.duplicate_keys;
+        de.reject_duplicate_fields = self.reject_duplicate_fields;
+        de.lenient_field_names = self.lenient_field_names;
+        de.strict_grammar = self.strict_grammar;
+        de.numeric_variants = self.numeric_variants;
+        de.maps_as_structs = self.maps_as_structs;
+        de.base64_bytes = self.base64_bytes;
+        de.shared_values = self.shared_values;
+        de.set_intern_strings(self.intern_strings);
+        de.set_json(self.json);
+        de.set_replace_lone_surrogates(self.replace_lone_surrogates);
+        de.limits = self.limits;
+        de.error_paths = self.error_paths;
+
+        Ok(de)
+    }
+
+    /// Like `ron::de::from_reader`, but with these options.
+    pub fn from_reader<R, T>(&self, rdr: R) -> Result<T>
+    where
+        R: io::Read,
+        T: DeserializeOwned,
+    {
+        self.from_reader_seed(rdr, PhantomData)
+    }
+
+    /// Like `ron::de::from_reader_seed`, but with these options.
+    pub fn from_reader_seed<R, S, T>(&self, mut rdr: R, seed: S) -> Result<T>
+    where
+        R: io::Read,
+        S: for<'a> DeserializeSeed<'a, Value = T>,
+    {
+        let mut bytes = Vec::new();
+        match self.limits.max_input_len {
+            // One more byte tells whether the input is too long
+            Some(max) => rdr.take(max as u64 + 1).read_to_end(&mut bytes)?,
+            None => rdr.read_to_end(&mut bytes)?,
+        };
+
+        self.from_bytes_seed(&bytes, seed)
+    }
+
+    /// Like `ron::de::from_async_reader`, but with these options.
+    #[cfg(feature = "async")]
+    pub fn from_async_reader<R, T>(&self, rdr: R) -> FromAsyncReader<R, T>
+    where
+        R: ::tokio::io::AsyncRead + Unpin,
+        T: DeserializeOwned,
+    {
+        async_read::read(rdr, self.clone(), self.limits.max_input_len)
+    }
+
+    /// Like `ron::de::from_str`, but with these options.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str<'a, T>(&self, s: &'a str) -> Result<T>
+    where
+        T: Deserialize<'a>,
+    {
+        self.from_bytes(s.as_bytes())
+    }
+
+    /// Like `ron::de::from_str_seed`, but with these options.
+    pub fn from_str_seed<'a, S>(&self, s: &'a str, seed: S) -> Result<S::Value>
+    where
+        S: DeserializeSeed<'a>,
+    {
+        self.from_bytes_seed(s.as_bytes(), seed)
+    }
+
+    /// Like `ron::de::from_str_partial`, but with these options.
+    pub fn from_str_partial<'a, T>(&self, s: &'a str) -> Result<(T, usize)>
+    where
+        T: Deserialize<'a>,
+    {
+        let _anchors = AnchorScope::new();
+        let mut deserializer = self.deserializer(s.as_bytes())?;
+        let t = T::deserialize(&mut deserializer).map_err(|e| deserializer.locate(e))?;
+
+        Ok((t, deserializer.offset()))
+    }
+
+    /// Like `ron::de::from_str_recovering`, but with these options.
+    pub fn from_str_recovering<'a, T>(&self, s: &'a str) -> (Option<T>, Vec<Error>)
+    where
+        T: Deserialize<'a>,
+    {
+        let errors = match self.deserializer(s.as_bytes()) {
+            Ok(de) => Recovery::check(de.bytes),
+            Err(e) => vec![e],
+        };
+        if !errors.is_empty() {
+            return (None, errors);
+        }
+
+        match self.from_str(s) {
+            Ok(value) => (Some(value), errors),
+            Err(e) => (None, vec![e]),
+        }
+    }
+
+    /// Like `ron::de::from_bytes`, but with these options.
+    pub fn from_bytes<'a, T>(&self, s: &'a [u8]) -> Result<T>
+    where
+        T: Deserialize<'a>,
+    {
+        self.from_bytes_seed(s, PhantomData)
+    }
+
+    /// Like `ron::de::from_bytes_seed`, but with these options.
+    pub fn from_bytes_seed<'a, S>(&self, s: &'a [u8], seed: S) -> Result<S::Value>
+    where
+        S: DeserializeSeed<'a>,
+    {
+        let _anchors = AnchorScope::new();
+        let mut deserializer = self.deserializer(s)?;
+        let t = seed
+            .deserialize(&mut deserializer)
+            .map_err(|e| deserializer.locate(e))?;
+
+        deserializer.end()?;
+
+        Ok(t)
+    }
+}
@@ -0,0 +1,36 @@
+use std::fmt::Write;
+
+/// A step on the way to a nested value.
+#[derive(Clone, Copy, Debug)]
+pub enum Segment<'de> {
+    /// An element of a sequence or tuple.
+    Index(usize),
+    /// A struct field, as it's written.
+    Field(&'de [u8]),
+    /// A map key, as it's written.
+    Key(&'de [u8]),
+    /// A value whose key couldn't be told.
+    Unknown,
+}
+
+/// Formats `path` like `entities[3].transform.scale`.
+pub fn format(path: &[Segment]) -> String {
+    let mut formatted = String::new();
+
+    for segment in path {
+        let _ = match *segment {
+            Segment::Index(index) => write!(formatted, "[{}]", index),
+            Segment::Field(field) => {
+                if !formatted.is_empty() {
+                    formatted.push('.');
+                }
+
+                write!(formatted, "{}", String::from_utf8_lossy(field))
+            }
+            Segment::Key(key) => write!(formatted, "[{}]", String::from_utf8_lossy(key)),
+            Segment::Unknown => write!(formatted, "[?]"),
+        };
+    }
+
+    formatted
+}
@@ -0,0 +1,513 @@
+use std::str;
+
+use extensions::Extensions;
+use parse::{advance_position, found, shift_error, Bytes, Expected, ParsedStr};
+
+use super::{Error, ParseError, Position, Result};
+
+/// A piece of RON, as reported by `Parser`.
+///
+/// Commas and colons aren't reported; the values of a map alternate
+/// between keys and values.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// The extensions enabled by an attribute like
+    /// `#![enable(implicit_some)]` at the start of the input.
+    Extensions(Extensions),
+    Bool(bool),
+    Integer(i128),
+    Float(f64),
+    Char(char),
+    Str(String),
+    /// A byte string like `b"GIF89a"` or a hex block like `x"00 ff"`.
+    Bytes(Vec<u8>),
+    /// A name, like a unit variant or `None`, or the one before the
+    /// parentheses of a struct or variant, like the `Some` of `Some(1)`.
+    Identifier(String),
+    /// The name of the struct field whose value comes next.
+    Field(String),
+    /// A `(`, which starts a struct or a tuple.
+    StructStart,
+    StructEnd,
+    SeqStart,
+    SeqEnd,
+    MapStart,
+    MapEnd,
+    /// An anchor like `&0` before the value it labels.
+    Anchor(u64),
+    /// A reference like `*0` to the value of an anchor.
+    Reference(u64),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Frame {
+    Struct,
+    Seq,
+    /// A map, whose next value is a key if `key` is set.
+    Map { key: bool },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    /// A value comes next.
+    Value,
+    /// An element or the end of the collection comes next.
+    Element,
+    /// A `,` or the end of the collection comes next.
+    AfterValue,
+    /// A name has been read, which may be followed by `(`.
+    AfterName,
+    /// A map key has been read, so a `:` comes next.
+    Colon,
+}
+
+/// A parser which is fed the input piece by piece, for inputs which
+/// arrive in chunks, like the messages of a websocket.
+///
+/// Every call to `feed` returns the events of the input which is
+/// complete so far; a number or a string which may continue in the
+/// next chunk is held back until it's known where it ends.
+///
+/// ```
+/// use ron::de::{Event, Parser};
+///
+/// let mut parser = Parser::new();
+/// let mut events = parser.feed(b"Point(x: 1, y: 2").unwrap();
+///
+/// assert_eq!(
+///     events,
+///     vec![
+///         Event::Identifier("Point".to_owned()),
+///         Event::StructStart,
+///         Event::Field("x".to_owned()),
+///         Event::Integer(1),
+///         Event::Field("y".to_owned()),
+///     ]
+/// );
+///
+/// events = parser.feed(b"0)").unwrap();
+/// events.extend(parser.finish().unwrap());
+///
+/// assert_eq!(events, vec![Event::Integer(20), Event::StructEnd]);
+/// ```
+///
+/// The syntax is checked, but types are unknown, so e.g. `(1, 2)`
+/// could be a tuple or a struct. After an error, the parser returns
+/// that error again.
+pub struct Parser {
+    /// The input which hasn't been parsed yet.
+    buf: Vec<u8>,
+    /// How much of `buf` has been parsed in the current call.
+    pos: usize,
+    /// The position of `pos` in the whole input.
+    position: Position,
+    stack: Vec<Frame>,
+    state: State,
+    /// Whether the value has started, after which attributes
+    /// aren't allowed.
+    started: bool,
+    error: Option<Error>,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser {
+            buf: Vec::new(),
+            pos: 0,
+            position: Position {
+                col: 1,
+                line: 1,
+                offset: 0,
+            },
+            stack: Vec::new(),
+            state: State::Value,
+            started: false,
+            error: None,
+        }
+    }
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Parser::default()
+    }
+
+    /// Parses `chunk`, which continues the input fed so far, and
+    /// returns the events of what is complete.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Vec<Event>> {
+        if let Some(ref e) = self.error {
+            return Err(e.clone());
+        }
+
+        self.buf.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        let res = self.parse(false, &mut events);
+
+        self.buf.drain(..self.pos);
+        self.pos = 0;
+
+        match res {
+            Ok(()) => Ok(events),
+            Err(e) => {
+                self.error = Some(e.clone());
+
+                Err(e)
+            }
+        }
+    }
+
+    /// Parses the rest of the input, which has ended, and returns its
+    /// events.
+    pub fn finish(mut self) -> Result<Vec<Event>> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+
+        let mut events = Vec::new();
+        self.parse(true, &mut events)?;
+
+        match self.state {
+            State::AfterValue | State::AfterName if self.stack.is_empty() => Ok(events),
+            _ => Err(Error::Parser(ParseError::Eof, self.position)),
+        }
+    }
+
+    fn parse(&mut self, last: bool, events: &mut Vec<Event>) -> Result<()> {
+        loop {
+            match ws_len(&self.buf[self.pos..], last) {
+                Ok(Some(len)) => self.consume(len),
+                Ok(None) => return Ok(()),
+                Err(kind) => {
+                    self.consume(self.buf.len() - self.pos);
+
+                    return Err(Error::Parser(kind, self.position));
+                }
+            }
+
+            let byte = match self.buf.get(self.pos) {
+                Some(&byte) => byte,
+                None => return Ok(()),
+            };
+
+            if self.state == State::AfterName && byte != b'(' {
+                self.value_done(false);
+            }
+
+            match self.state {
+                State::AfterValue if self.stack.is_empty() => {
+                    return self.err(ParseError::TrailingCharacters);
+                }
+                State::Colon if byte == b':' => {
+                    self.consume(1);
+                    self.state = State::Value;
+                }
+                State::Colon => return self.expected(ParseError::ExpectedMapColon),
+                State::AfterValue => match byte {
+                    b',' => {
+                        self.consume(1);
+                        if let Some(&mut Frame::Map { ref mut key }) = self.stack.last_mut() {
+                            *key = true;
+                        }
+                        self.state = State::Element;
+                    }
+                    b')' | b']' | b'}' => events.push(self.close(byte)?),
+                    _ => return self.expected(ParseError::ExpectedComma),
+                },
+                State::Element if byte == b')' || byte == b']' || byte == b'}' => {
+                    events.push(self.close(byte)?);
+                }
+                _ => match self.value(byte, last)? {
+                    Some(event) => events.push(event),
+                    None => return Ok(()),
+                },
+            }
+        }
+    }
+
+    /// Reads the start of a value, and returns its event, or `None` if
+    /// more input is needed.
+    fn value(&mut self, byte: u8, last: bool) -> Result<Option<Event>> {
+        if byte == b'#' && !self.started {
+            return self.attribute(last);
+        }
+        self.started = true;
+
+        let (frame, event) = match byte {
+            b'(' => (Frame::Struct, Event::StructStart),
+            b'[' => (Frame::Seq, Event::SeqStart),
+            b'{' => (Frame::Map { key: true }, Event::MapStart),
+            b')' | b']' | b'}' | b',' | b':' => {
+                return self.err(ParseError::UnexpectedByte(byte as char));
+            }
+            _ => return self.token(last),
+        };
+
+        self.consume(1);
+        self.stack.push(frame);
+        self.state = State::Element;
+
+        Ok(Some(event))
+    }
+
+    /// Reads an attribute like `#![enable(implicit_some)]`.
+    fn attribute(&mut self, last: bool) -> Result<Option<Event>> {
+        let rest = &self.buf[self.pos..];
+        let len = match rest.iter().position(|&b| b == b']') {
+            Some(i) => i + 1,
+            None if last => rest.len(),
+            None => return Ok(None),
+        };
+
+        let exts = Bytes::new(&rest[..len])
+            .map_err(|e| shift_error(e, self.position))?
+            .exts;
+        self.consume(len);
+
+        Ok(Some(Event::Extensions(exts)))
+    }
+
+    /// Reads a value which is a single token, like a number, a string
+    /// or a name.
+    fn token(&mut self, last: bool) -> Result<Option<Event>> {
+        let rest = &self.buf[self.pos..];
+        let len = match token_len(rest, last) {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+
+        let mut bytes = Bytes::new(&rest[..len]).map_err(|e| shift_error(e, self.position))?;
+        let event = match parse_token(&mut bytes) {
+            Ok(event) => event,
+            Err(e) => return Err(shift_error(e, self.position)),
+        };
+        let len = len - bytes.bytes().len();
+
+        // A field name, which may be quoted, is followed by `:`
+        let field = match event {
+            Event::Identifier(_) | Event::Str(_) if self.state == State::Element => {
+                self.stack.last() == Some(&Frame::Struct)
+            }
+            _ => false,
+        };
+        if field {
+            let ws = match ws_len(&rest[len..], last) {
+                Ok(Some(ws)) if rest.len() > len + ws || last => ws,
+                // Wait for what comes after the name
+                _ => return Ok(None),
+            };
+
+            if rest.get(len + ws) == Some(&b':') {
+                let name = match event {
+                    Event::Identifier(name) | Event::Str(name) => name,
+                    _ => unreachable!(),
+                };
+
+                self.consume(len + ws + 1);
+                self.state = State::Value;
+
+                return Ok(Some(Event::Field(name)));
+            }
+        }
+
+        self.consume(len);
+
+        match event {
+            Event::Anchor(_) => {}
+            Event::Identifier(_) => self.value_done(true),
+            _ => self.value_done(false),
+        }
+
+        Ok(Some(event))
+    }
+
+    /// Reads the end of the current collection.
+    fn close(&mut self, byte: u8) -> Result<Event> {
+        let (kind, event): (Expected, _) = match self.stack.last() {
+            Some(&Frame::Struct) => (ParseError::ExpectedStructEnd, Event::StructEnd),
+            Some(&Frame::Seq) => (ParseError::ExpectedArrayEnd, Event::SeqEnd),
+            Some(&Frame::Map { .. }) => (ParseError::ExpectedMapEnd, Event::MapEnd),
+            None => return self.err(ParseError::UnexpectedByte(byte as char)),
+        };
+
+        let expected = match event {
+            Event::StructEnd => b')',
+            Event::SeqEnd => b']',
+            _ => b'}',
+        };
+        if byte != expected {
+            return self.expected(kind);
+        }
+
+        self.consume(1);
+        self.stack.pop();
+        self.value_done(false);
+
+        Ok(event)
+    }
+
+    /// Moves on after a value, which is a name that may be followed by
+    /// parentheses if `name` is set.
+    fn value_done(&mut self, name: bool) {
+        self.state = match self.stack.last_mut() {
+            _ if name => State::AfterName,
+            Some(&mut Frame::Map { ref mut key }) if *key => {
+                *key = false;
+                State::Colon
+            }
+            _ => State::AfterValue,
+        };
+    }
+
+    fn consume(&mut self, len: usize) {
+        advance_position(&mut self.position, &self.buf[self.pos..self.pos + len]);
+        self.pos += len;
+    }
+
+    fn err<T>(&self, kind: ParseError) -> Result<T> {
+        Err(Error::Parser(kind, self.position))
+    }
+
+    /// Fails with `kind`, which is told the token the rest of the
+    /// input starts with.
+    fn expected<T>(&self, kind: Expected) -> Result<T> {
+        self.err(kind(found(&self.buf[self.pos..])))
+    }
+}
+
+/// Parses a value which is a single token.
+fn parse_token(bytes: &mut Bytes) -> Result<Event> {
+    let event = match bytes.peek_or_eof()? {
+        b'&' | b'*' => {
+            let anchor = bytes.peek() == Some(b'&');
+            bytes.advance_single()?;
+            let id = bytes.integer()?;
+
+            match anchor {
+                true => Event::Anchor(id),
+                false => Event::Reference(id),
+            }
+        }
+        b'"' => Event::Str(string(bytes)?),
+        b'\'' => Event::Char(bytes.char()?),
+        _ if bytes.check_raw_string() => Event::Str(string(bytes)?),
+        _ if bytes.check_byte_string() => Event::Bytes(bytes.byte_string()?.into_owned()),
+        _ if bytes.check_hex_bytes() => Event::Bytes(bytes.hex_bytes()?),
+        _ if bytes.check_non_finite_float() => Event::Float(bytes.float()?),
+        _ if bytes.check_based_integer() || bytes.check_integer() => {
+            Event::Integer(bytes.integer()?)
+        }
+        b'0'..=b'9' | b'+' | b'-' | b'.' => Event::Float(bytes.float()?),
+        _ if bytes.consume_ident("true") => Event::Bool(true),
+        _ if bytes.consume_ident("false") => Event::Bool(false),
+        _ => {
+            let start = *bytes;
+            let name = bytes.identifier()?;
+            let name = str::from_utf8(name).map_err(|e| start.error(e.into()))?;
+
+            Event::Identifier(name.to_owned())
+        }
+    };
+
+    Ok(event)
+}
+
+fn string(bytes: &mut Bytes) -> Result<String> {
+    Ok(match bytes.string()? {
+        ParsedStr::Allocated(s) => s,
+        ParsedStr::Slice(s) => s.to_owned(),
+    })
+}
+
+/// The length of the whitespace and comments at the start of `bytes`,
+/// or `None` if a comment may go on after them.
+///
+/// If `bytes` is the last of the input, a comment which isn't closed
+/// is an error.
+fn ws_len(bytes: &[u8], last: bool) -> ::std::result::Result<Option<usize>, ParseError> {
+    let mut i = 0;
+
+    loop {
+        match (bytes.get(i), bytes.get(i + 1)) {
+            (Some(b'\n'), _) | (Some(b'\t'), _) | (Some(b'\r'), _) | (Some(b' '), _) => i += 1,
+            (Some(b'/'), Some(b'/')) => match bytes[i..].iter().position(|&b| b == b'\n') {
+                Some(len) => i += len + 1,
+                None if last => return Ok(Some(bytes.len())),
+                None => return Ok(None),
+            },
+            (Some(b'/'), Some(b'*')) => {
+                let mut level = 0;
+
+                loop {
+                    match (bytes.get(i), bytes.get(i + 1)) {
+                        (Some(b'/'), Some(b'*')) => {
+                            level += 1;
+                            i += 2;
+                        }
+                        (Some(b'*'), Some(b'/')) => {
+                            level -= 1;
+                            i += 2;
+
+                            if level == 0 {
+                                break;
+                            }
+                        }
+                        (Some(_), _) => i += 1,
+                        (None, _) if last => return Err(ParseError::UnclosedBlockComment),
+                        (None, _) => return Ok(None),
+                    }
+                }
+            }
+            // The `/` may start a comment
+            (Some(b'/'), None) if !last => return Ok(None),
+            _ => return Ok(Some(i)),
+        }
+    }
+}
+
+/// The length of the token at the start of `bytes`, or `None` if
+/// more input may belong to it.
+fn token_len(bytes: &[u8], last: bool) -> Option<usize> {
+    let hashes = match bytes.first() {
+        Some(b'r') => bytes[1..].iter().take_while(|&&b| b == b'#').count(),
+        _ => 0,
+    };
+
+    let (prefix, quote, raw) = match bytes {
+        [b'"', ..] => (1, b'"', false),
+        [b'\'', ..] => (1, b'\'', false),
+        [b'b', b'"', ..] | [b'x', b'"', ..] => (2, b'"', false),
+        [b'r', ..] if bytes.get(hashes + 1) == Some(&b'"') => (hashes + 2, b'"', true),
+        _ => {
+            let word = bytes.iter().position(|&b| {
+                b" \t\r\n()[]{},:\"'/".contains(&b)
+            });
+
+            return match word {
+                Some(len) => Some(len),
+                None if last => Some(bytes.len()),
+                None => None,
+            };
+        }
+    };
+
+    let mut i = prefix;
+    while let Some(&byte) = bytes.get(i) {
+        i += 1;
+
+        if byte == b'\\' && !raw {
+            i += 1;
+        } else if byte == quote {
+            let end = i + hashes;
+
+            if bytes.len() >= end && bytes[i..end].iter().all(|&b| b == b'#') {
+                return Some(end);
+            }
+        }
+    }
+
+    match last {
+        true => Some(bytes.len()),
+        false => None,
+    }
+}
@@ -0,0 +1,474 @@
+use std::io;
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+
+use extensions::Extensions;
+use parse::{advance_position, found, shift_error, unsupported_encoding, Bytes, BOM};
+use shared::AnchorScope;
+
+use super::{Error, Options, ParseError, Position, Result};
+
+/// An iterator over the values read from a `BufRead`, which holds only
+/// the value it's reading in memory instead of the whole input, for
+/// exports too large to read at once.
+///
+/// It reads either documents separated by whitespace, like
+/// `StreamDeserializer`, or the elements of a sequence which makes up
+/// the whole input:
+///
+/// ```
+/// use ron::de::ReaderDeserializer;
+///
+/// let input: &[u8] = b"[(1, \"start\"), (2, \"stop\")]";
+/// let records: Vec<(u32, String)> = ReaderDeserializer::elements(input)
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+///
+/// assert_eq!(records, vec![(1, "start".to_owned()), (2, "stop".to_owned())]);
+/// ```
+///
+/// Extensions enabled at the start of a sequence apply to all of its
+/// elements, but anchors of `Shared` values only apply to the value
+/// they are in. After an error, the iterator ends.
+pub struct ReaderDeserializer<R, T> {
+    reader: R,
+    options: Options,
+    /// The input which has been read, but not deserialized yet.
+    buf: Vec<u8>,
+    /// How far `buf` has been scanned.
+    pos: usize,
+    /// Where `buf` starts in the input.
+    start: Position,
+    eof: bool,
+    /// Whether the values are the elements of a sequence.
+    elements: bool,
+    /// Whether the `[` of the sequence has been read.
+    started: bool,
+    /// The extensions enabled at the start of the sequence.
+    exts: Extensions,
+    done: bool,
+    marker: PhantomData<T>,
+}
+
+impl<R, T> ReaderDeserializer<R, T>
+where
+    R: io::BufRead,
+    T: DeserializeOwned,
+{
+    /// Reads documents separated by whitespace from `reader`.
+    pub fn new(reader: R) -> Self {
+        ReaderDeserializer {
+            reader,
+            options: Options::default(),
+            buf: Vec::new(),
+            pos: 0,
+            start: Position {
+                col: 1,
+                line: 1,
+                offset: 0,
+            },
+            eof: false,
+            elements: false,
+            started: false,
+            exts: Extensions::empty(),
+            done: false,
+            marker: PhantomData,
+        }
+    }
+
+    /// Reads the elements of the sequence `reader` holds, like
+    /// `[(id: 1), (id: 2)]`, one by one.
+    pub fn elements(reader: R) -> Self {
+        ReaderDeserializer {
+            elements: true,
+            ..ReaderDeserializer::new(reader)
+        }
+    }
+
+    /// Deserializes the values with `options`. Limits apply to each
+    /// value on its own.
+    pub fn with_options(mut self, options: Options) -> Self {
+        self.options = options;
+
+        self
+    }
+
+    fn next_value(&mut self) -> Result<Option<T>> {
+        if self.elements {
+            self.next_element()
+        } else {
+            self.next_document()
+        }
+    }
+
+    fn next_document(&mut self) -> Result<Option<T>> {
+        self.skip_start()?;
+        self.skip_ws()?;
+        self.discard();
+
+        if self.peek(0)?.is_none() {
+            return Ok(None);
+        }
+
+        self.scan_document()?;
+
+        self.deserialize().map(Some)
+    }
+
+    fn next_element(&mut self) -> Result<Option<T>> {
+        if !self.started {
+            self.start_elements()?;
+        }
+
+        self.skip_ws()?;
+        self.discard();
+
+        match self.peek(0)? {
+            Some(b']') => {
+                self.pos += 1;
+                self.skip_ws()?;
+
+                return match self.peek(0)? {
+                    Some(_) => Err(self.error(ParseError::TrailingCharacters)),
+                    None => Ok(None),
+                };
+            }
+            Some(_) => {}
+            None => return Err(self.error(ParseError::ExpectedArrayEnd(None))),
+        }
+
+        self.scan_element()?;
+        let value = self.deserialize()?;
+
+        if self.peek(0)? == Some(b',') {
+            self.pos += 1;
+        }
+
+        Ok(Some(value))
+    }
+
+    /// Reads the extension attributes and the `[` the input starts with.
+    fn start_elements(&mut self) -> Result<()> {
+        self.started = true;
+
+        self.skip_start()?;
+        self.skip_ws()?;
+        while self.peek(0)? == Some(b'#') {
+            // The `#!` and the brackets after it
+            let mut depth = 0;
+            self.skip_token(&mut depth)?;
+            self.skip_ws()?;
+
+            if self.peek(0)? == Some(b'[') {
+                loop {
+                    self.skip_token(&mut depth)?;
+                    self.skip_ws()?;
+
+                    if depth == 0 || self.peek(0)?.is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.exts = Bytes::new(&self.buf[..self.pos])?.exts;
+
+        if self.peek(0)? != Some(b'[') {
+            let found = found(&self.buf[self.pos..]);
+
+            return Err(self.error(ParseError::ExpectedArray(found)));
+        }
+        self.pos += 1;
+
+        Ok(())
+    }
+
+    /// Scans a document, which ends where a value is complete at the top
+    /// level, unless something which belongs to it comes next, like the
+    /// `(` after a struct name.
+    fn scan_document(&mut self) -> Result<()> {
+        let mut depth = 0;
+        let mut attribute = false;
+
+        while let Some(first) = self.peek(0)? {
+            let start = self.pos;
+            let closes = self.skip_token(&mut depth)?;
+            let end = self.pos;
+            self.skip_ws()?;
+
+            if depth > 0 {
+                continue;
+            }
+
+            let goes_on = match first {
+                // An attribute like `#![enable(...)]`, or an anchor
+                // like `&0`, before the value
+                b'#' | b'&' => {
+                    attribute = first == b'#';
+                    true
+                }
+                _ if closes => ::std::mem::replace(&mut attribute, false),
+                b'"' | b'\'' | b'(' | b'[' | b'{' => false,
+                // Only a struct or enum name goes on with a `(`
+                _ if is_complete_value(&self.buf[start..end]) => false,
+                _ => self.peek(0)? == Some(b'('),
+            };
+
+            if !goes_on {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Scans an element, which ends at the next `,` or `]` outside of it.
+    fn scan_element(&mut self) -> Result<()> {
+        let mut depth = 0;
+
+        while let Some(byte) = self.peek(0)? {
+            if depth == 0 && (byte == b',' || byte == b']') {
+                break;
+            }
+
+            self.skip_token(&mut depth)?;
+            self.skip_ws()?;
+        }
+
+        Ok(())
+    }
+
+    /// Deserializes what has been scanned.
+    fn deserialize(&self) -> Result<T> {
+        let _anchors = AnchorScope::new();
+        let value = self.options.deserializer(&self.buf[..self.pos]).and_then(|mut de| {
+            de.bytes.exts |= self.exts;
+            let value = T::deserialize(&mut de).map_err(|e| de.locate(e))?;
+            de.end()?;
+
+            Ok(value)
+        });
+
+        value.map_err(|e| shift_error(e, self.start))
+    }
+
+    /// Skips a single token, like a string, a bracket or an identifier,
+    /// and returns whether it's a closing bracket.
+    fn skip_token(&mut self, depth: &mut usize) -> Result<bool> {
+        let byte = match self.peek(0)? {
+            Some(byte) => byte,
+            None => return Ok(false),
+        };
+
+        match byte {
+            b'(' | b'[' | b'{' => {
+                *depth += 1;
+                self.pos += 1;
+
+                return Ok(false);
+            }
+            b')' | b']' | b'}' => {
+                *depth = depth.saturating_sub(1);
+                self.pos += 1;
+
+                return Ok(true);
+            }
+            b',' | b':' => {
+                self.pos += 1;
+
+                return Ok(false);
+            }
+            b'"' => return self.skip_quoted(1, b'"', 0).map(|_| false),
+            b'\'' => return self.skip_quoted(1, b'\'', 0).map(|_| false),
+            b'b' | b'x' if self.peek(1)? == Some(b'"') => {
+                return self.skip_quoted(2, b'"', 0).map(|_| false);
+            }
+            b'r' => {
+                let mut hashes = 0;
+                while self.peek(1 + hashes)? == Some(b'#') {
+                    hashes += 1;
+                }
+
+                if self.peek(1 + hashes)? == Some(b'"') {
+                    return self.skip_quoted(2 + hashes, b'"', hashes).map(|_| false);
+                }
+            }
+            _ => {}
+        }
+
+        self.pos += 1;
+        while let Some(byte) = self.peek(0)? {
+            match byte {
+                b'\n' | b'\t' | b'\r' | b' ' | b'(' | b')' | b'[' | b']' | b'{' | b'}' | b','
+                | b':' | b'"' | b'\'' | b'/' => break,
+                _ => self.pos += 1,
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Skips a string or char whose contents start after `prefix` bytes.
+    ///
+    /// Raw strings end at a `quote` followed by `hashes` hashes,
+    /// other ones at a `quote` which isn't escaped.
+    fn skip_quoted(&mut self, prefix: usize, quote: u8, hashes: usize) -> Result<()> {
+        let raw = self.buf[self.pos] == b'r';
+        self.pos += prefix;
+
+        while let Some(byte) = self.peek(0)? {
+            self.pos += 1;
+
+            if byte == b'\\' && !raw {
+                if self.peek(0)?.is_some() {
+                    self.pos += 1;
+                }
+            } else if byte == quote {
+                let mut end = 0;
+                while end < hashes && self.peek(end)? == Some(b'#') {
+                    end += 1;
+                }
+
+                if end == hashes {
+                    self.pos += hashes;
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Skips a byte order mark and a `#!` line which isn't an attribute
+    /// at the start of the input.
+    fn skip_start(&mut self) -> Result<()> {
+        if self.start.offset > 0 || self.pos > 0 {
+            return Ok(());
+        }
+
+        self.peek(BOM.len())?;
+        if let Some(encoding) = unsupported_encoding(&self.buf) {
+            return Err(self.error(ParseError::UnsupportedEncoding(encoding)));
+        }
+        if self.buf.starts_with(BOM) {
+            // The mark isn't a character, so the column stays
+            self.buf.drain(..BOM.len());
+            self.start.offset = BOM.len();
+        }
+
+        if self.peek(0)? == Some(b'#') && self.peek(1)? == Some(b'!') {
+            self.pos = 2;
+            self.skip_ws()?;
+
+            let attribute = self.peek(0)? == Some(b'[');
+            self.pos = 0;
+
+            if !attribute {
+                while self.peek(0)?.is_some_and(|byte| byte != b'\n') {
+                    self.pos += 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Skips whitespace and comments.
+    fn skip_ws(&mut self) -> Result<()> {
+        loop {
+            match (self.peek(0)?, self.peek(1)?) {
+                (Some(b'\n'), _) | (Some(b'\t'), _) | (Some(b'\r'), _) | (Some(b' '), _) => {
+                    self.pos += 1;
+                }
+                (Some(b'/'), Some(b'/')) => {
+                    while self.peek(0)?.is_some_and(|byte| byte != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                (Some(b'/'), Some(b'*')) => {
+                    self.pos += 2;
+
+                    let mut level = 1;
+                    while level > 0 {
+                        match (self.peek(0)?, self.peek(1)?) {
+                            (Some(b'/'), Some(b'*')) => {
+                                level += 1;
+                                self.pos += 2;
+                            }
+                            (Some(b'*'), Some(b'/')) => {
+                                level -= 1;
+                                self.pos += 2;
+                            }
+                            (Some(_), _) => self.pos += 1,
+                            // Deserializing reports the unclosed comment
+                            (None, _) => return Ok(()),
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Returns the byte `ahead` bytes after the scanned ones,
+    /// reading more input if needed.
+    fn peek(&mut self, ahead: usize) -> Result<Option<u8>> {
+        while self.pos + ahead >= self.buf.len() && !self.eof {
+            let len = match self.reader.fill_buf() {
+                Ok(data) => {
+                    self.buf.extend_from_slice(data);
+                    data.len()
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            self.reader.consume(len);
+            self.eof = len == 0;
+        }
+
+        Ok(self.buf.get(self.pos + ahead).copied())
+    }
+
+    /// Drops the scanned input, which has been deserialized.
+    fn discard(&mut self) {
+        advance_position(&mut self.start, &self.buf[..self.pos]);
+
+        self.buf.drain(..self.pos);
+        self.pos = 0;
+    }
+
+    /// Returns `kind` at the scanned position.
+    fn error(&mut self, kind: ParseError) -> Error {
+        self.discard();
+
+        Error::Parser(kind, self.start)
+    }
+}
+
+impl<R, T> Iterator for ReaderDeserializer<R, T>
+where
+    R: io::BufRead,
+    T: DeserializeOwned,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if self.done {
+            return None;
+        }
+
+        let res = self.next_value();
+        self.done = !matches!(res, Ok(Some(_)));
+
+        res.transpose()
+    }
+}
+
+/// Whether `token` is a value on its own, like `None`, `true` or a
+/// number, rather than a name which a `(` may follow.
+fn is_complete_value(token: &[u8]) -> bool {
+    matches!(token.first(), Some(b'0'..=b'9' | b'+' | b'-' | b'.' | b'*'))
+        || matches!(token, b"true" | b"false" | b"None" | b"inf" | b"NaN")
+}
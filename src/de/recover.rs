@@ -0,0 +1,221 @@
+use parse::{Bytes, Expected};
+
+use super::{Error, ParseError, Result};
+
+/// Walks over the syntax of RON without a type, and collects every
+/// error instead of stopping at the first one.
+///
+/// After an error, the walk continues after the next `,` or at the
+/// closing delimiter of the collection the error is in.
+pub struct Recovery<'a> {
+    bytes: Bytes<'a>,
+    errors: Vec<Error>,
+}
+
+impl<'a> Recovery<'a> {
+    /// Returns the syntax errors of the input `bytes` are at, in the
+    /// order they occur.
+    ///
+    /// `bytes` should come from the deserializer which reads the input
+    /// afterwards, so that both accept the same syntax.
+    pub fn check(bytes: Bytes<'a>) -> Vec<Error> {
+        let mut recovery = Recovery {
+            bytes,
+            errors: Vec::new(),
+        };
+
+        if let Err(e) = recovery.value() {
+            recovery.record(e);
+            recovery.resync();
+        }
+
+        match recovery.bytes.skip_ws() {
+            Ok(()) if recovery.bytes.bytes().is_empty() => {}
+            Ok(()) => {
+                let e = recovery.bytes.error(ParseError::TrailingCharacters);
+                recovery.record(e);
+            }
+            Err(e) => recovery.record(e),
+        }
+
+        recovery.errors
+    }
+
+    /// Records `error`, unless an error at the same position has been
+    /// recorded already, like the end of the input in nested values.
+    fn record(&mut self, error: Error) {
+        let position = |e: &Error| match *e {
+            Error::Parser(_, position) => Some(position),
+            _ => None,
+        };
+
+        let last = self.errors.last().and_then(&position);
+
+        if last.is_none() || last != position(&error) {
+            self.errors.push(error);
+        }
+    }
+
+    fn value(&mut self) -> Result<()> {
+        self.bytes.skip_ws()?;
+
+        let byte = self.bytes.peek_or_eof()?;
+
+        match byte {
+            b'(' => self.collection(b')'),
+            b'[' => self.collection(b']'),
+            b'{' => self.collection(b'}'),
+            b'"' => self.bytes.string().map(|_| ()),
+            b'\'' => self.bytes.char().map(|_| ()),
+            b'&' => {
+                self.bytes.advance_single()?;
+                self.bytes.integer::<u64>()?;
+
+                self.value()
+            }
+            b'*' => {
+                self.bytes.advance_single()?;
+
+                self.bytes.integer::<u64>().map(|_| ())
+            }
+            _ if self.bytes.check_raw_string() => self.bytes.string().map(|_| ()),
+            _ if self.bytes.check_byte_string() => self.bytes.byte_string().map(|_| ()),
+            _ if self.bytes.check_hex_bytes() => self.bytes.hex_bytes().map(|_| ()),
+            _ if self.bytes.check_non_finite_float() => self.bytes.float::<f64>().map(|_| ()),
+            _ if self.bytes.check_based_integer() => {
+                self.bytes.integer::<u128>().map(|_| ())
+            }
+            b'0'..=b'9' | b'+' | b'-' | b'.' => self.bytes.float::<f64>().map(|_| ()),
+            _ => {
+                if let Err(e) = self.bytes.identifier() {
+                    return match byte {
+                        0x00..=0x7F => self.bytes.err(ParseError::UnexpectedByte(byte as char)),
+                        _ => Err(e),
+                    };
+                }
+                self.bytes.skip_ws()?;
+
+                match self.bytes.peek() {
+                    Some(b'(') => self.collection(b')'),
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Walks over a collection up to `close`, whose opening delimiter
+    /// comes next.
+    fn collection(&mut self, close: u8) -> Result<()> {
+        let end_error: Expected = match close {
+            b')' => ParseError::ExpectedStructEnd,
+            b']' => ParseError::ExpectedArrayEnd,
+            _ => ParseError::ExpectedMapEnd,
+        };
+
+        self.bytes.advance_single()?;
+
+        loop {
+            if let Err(e) = self.bytes.skip_ws() {
+                self.record(e);
+
+                return Ok(());
+            }
+
+            match self.bytes.peek() {
+                Some(byte) if byte == close => return self.bytes.advance_single(),
+                Some(_) => {}
+                None => return self.bytes.expected(end_error),
+            }
+
+            if let Err(e) = self.element(close) {
+                self.record(e);
+
+                if self.resync() {
+                    if self.bytes.peek() == Some(close) {
+                        self.bytes.advance_single()?;
+                    }
+
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Walks over an element of a collection and the comma after it.
+    fn element(&mut self, close: u8) -> Result<()> {
+        match close {
+            b')' => {
+                // A field name, which may be quoted, or the first value
+                // of a tuple
+                let start = self.bytes;
+                let name = match self.bytes.peek() {
+                    Some(b'"') => self.bytes.string().is_ok(),
+                    _ => self.bytes.try_identifier().is_some(),
+                };
+                let field = name
+                    && self.bytes.skip_ws().is_ok()
+                    && self.bytes.consume(":");
+                if !field {
+                    self.bytes = start;
+                }
+
+                self.value()?;
+            }
+            b'}' => {
+                self.value()?;
+                self.bytes.skip_ws()?;
+
+                if !self.bytes.consume(":") {
+                    return self.bytes.expected(ParseError::ExpectedMapColon);
+                }
+
+                self.value()?;
+            }
+            _ => self.value()?,
+        }
+
+        self.bytes.skip_ws()?;
+
+        if self.bytes.consume(",") || self.bytes.peek() == Some(close) {
+            Ok(())
+        } else {
+            self.bytes.expected(ParseError::ExpectedComma)
+        }
+    }
+
+    /// Skips to after the next `,` of the current collection, or to its
+    /// closing delimiter, and returns whether the collection ended.
+    fn resync(&mut self) -> bool {
+        let mut depth = 0usize;
+
+        loop {
+            let byte = match self.bytes.peek() {
+                Some(byte) => byte,
+                None => return true,
+            };
+
+            match byte {
+                b',' if depth == 0 => {
+                    let _ = self.bytes.advance_single();
+
+                    return false;
+                }
+                // Any closing delimiter ends the current collection,
+                // one which isn't its own is left for the outer ones
+                b')' | b']' | b'}' if depth == 0 => return true,
+                b'(' | b'[' | b'{' => depth += 1,
+                b')' | b']' | b'}' => depth -= 1,
+                b'"' => {
+                    if self.bytes.string().is_err() {
+                        let _ = self.bytes.advance_single();
+                    }
+
+                    continue;
+                }
+                _ => {}
+            }
+
+            let _ = self.bytes.advance_single();
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::marker::PhantomData;
+
+use serde::de::Deserialize;
+
+use shared::AnchorScope;
+
+use super::{Deserializer, Result};
+
+/// An iterator over the values of an input which holds several
+/// documents, separated by whitespace, like a log of records.
+///
+/// Values are read one by one, as the iterator advances. After an error,
+/// the iterator ends, because it can't tell where the next value starts.
+///
+/// ```
+/// use ron::de::Deserializer;
+///
+/// let input = "(1, \"start\")\n(2, \"stop\")\n";
+/// let records: Vec<(u32, String)> = Deserializer::from_str(input)
+///     .unwrap()
+///     .into_iter()
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+///
+/// assert_eq!(records, vec![(1, "start".to_owned()), (2, "stop".to_owned())]);
+/// ```
+///
+/// Anchors of `Shared` values only apply to the document they are in.
+pub struct StreamDeserializer<'de, T> {
+    de: Deserializer<'de>,
+    failed: bool,
+    marker: PhantomData<T>,
+}
+
+impl<'de, T> StreamDeserializer<'de, T>
+where
+    T: Deserialize<'de>,
+{
+    pub fn new(de: Deserializer<'de>) -> Self {
+        StreamDeserializer {
+            de,
+            failed: false,
+            marker: PhantomData,
+        }
+    }
+
+    fn next_value(&mut self) -> Result<Option<T>> {
+        self.de.bytes.skip_ws()?;
+        if self.de.bytes.bytes().is_empty() {
+            return Ok(None);
+        }
+
+        let _anchors = AnchorScope::new();
+
+        T::deserialize(&mut self.de)
+            .map(Some)
+            .map_err(|e| self.de.locate(e))
+    }
+}
+
+impl<'de, T> Iterator for StreamDeserializer<'de, T>
+where
+    T: Deserialize<'de>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if self.failed {
+            return None;
+        }
+
+        let res = self.next_value();
+        self.failed = res.is_err();
+
+        res.transpose()
+    }
+}
@@ -110,6 +110,37 @@ fn test_escape_char() {
 #[test]
 fn test_escape() {
     assert_eq!("\"Quoted\"", from_str::<String>(r#""\"Quoted\"""#).unwrap());
+    assert_eq!(
+        from_str::<String>("\"a\\nb"),
+        err(ParseError::ExpectedStringEnd, 1, 5, 4)
+    );
+    assert_eq!(
+        from_str::<Vec<u8>>("b\"a\\nb"),
+        err(ParseError::ExpectedStringEnd, 1, 7, 6)
+    );
+}
+
+#[test]
+fn test_raw_string() {
+    assert_eq!(from_str(r#"r"C:\temp""#), Ok("C:\\temp".to_owned()));
+    assert_eq!(
+        from_str(r###"r#"say "hi""#"###),
+        Ok("say \"hi\"".to_owned())
+    );
+    assert_eq!(from_str(r###"r##"a"#b"##"###), Ok("a\"#b".to_owned()));
+    assert_eq!(from_str("r\"two\nlines\""), Ok("two\nlines".to_owned()));
+    assert_eq!(
+        from_str(r#"[r"\d+", "\\s"]"#),
+        Ok(vec!["\\d+".to_owned(), "\\s".to_owned()])
+    );
+    assert_eq!(
+        from_str::<::value::Value>(r##"{r"a": r#"b"#}"##),
+        from_str(r#"{"a": "b"}"#)
+    );
+    assert_eq!(
+        from_str::<String>(r###"r#"unclosed""###),
+        err(ParseError::ExpectedStringEnd, 1, 4, 3)
+    );
 }
 
 #[test]
@@ -127,10 +158,15 @@ y: 2.0 // 2!
     );
 }
 
-fn err<T>(kind: ParseError, line: usize, col: usize) -> Result<T> {
+fn err<T>(kind: ParseError, line: usize, col: usize, offset: usize) -> Result<T> {
     use parse::Position;
 
-    Err(Error::Parser(kind, Position { line, col }))
+    Err(Error::Parser(kind, Position { line, col, offset }))
+}
+
+/// The token an `Expected` error found.
+fn found(token: &str) -> Option<String> {
+    Some(token.to_owned())
 }
 
 #[test]
@@ -138,23 +174,23 @@ fn test_err_wrong_value() {
     use self::ParseError::*;
     use std::collections::HashMap;
 
-    assert_eq!(from_str::<f32>("'c'"), err(ExpectedFloat, 1, 1));
-    assert_eq!(from_str::<String>("'c'"), err(ExpectedString, 1, 1));
-    assert_eq!(from_str::<HashMap<u32, u32>>("'c'"), err(ExpectedMap, 1, 1));
-    assert_eq!(from_str::<[u8; 5]>("'c'"), err(ExpectedArray, 1, 1));
-    assert_eq!(from_str::<Vec<u32>>("'c'"), err(ExpectedArray, 1, 1));
-    assert_eq!(from_str::<MyEnum>("'c'"), err(ExpectedIdentifier, 1, 1));
-    assert_eq!(from_str::<MyStruct>("'c'"), err(ExpectedStruct, 1, 1));
-    assert_eq!(from_str::<(u8, bool)>("'c'"), err(ExpectedArray, 1, 1));
-    assert_eq!(from_str::<bool>("notabool"), err(ExpectedBoolean, 1, 1));
+    assert_eq!(from_str::<f32>("'c'"), err(ExpectedFloat(found("'c'")), 1, 1, 0));
+    assert_eq!(from_str::<String>("'c'"), err(ExpectedString(found("'c'")), 1, 1, 0));
+    assert_eq!(from_str::<HashMap<u32, u32>>("'c'"), err(ExpectedMap(found("'c'")), 1, 1, 0));
+    assert_eq!(from_str::<[u8; 5]>("'c'"), err(ExpectedArray(found("'c'")), 1, 1, 0));
+    assert_eq!(from_str::<Vec<u32>>("'c'"), err(ExpectedArray(found("'c'")), 1, 1, 0));
+    assert_eq!(from_str::<MyEnum>("'c'"), err(ExpectedIdentifier(found("'c'")), 1, 1, 0));
+    assert_eq!(from_str::<MyStruct>("'c'"), err(ExpectedStruct(found("'c'")), 1, 1, 0));
+    assert_eq!(from_str::<(u8, bool)>("'c'"), err(ExpectedArray(found("'c'")), 1, 1, 0));
+    assert_eq!(from_str::<bool>("notabool"), err(ExpectedBoolean(found("notabool")), 1, 1, 0));
 
     assert_eq!(
         from_str::<MyStruct>("MyStruct(\n    x: true)"),
-        err(ExpectedFloat, 2, 8)
+        err(ExpectedFloat(found("true")), 2, 8, 17)
     );
     assert_eq!(
         from_str::<MyStruct>("MyStruct(\n    x: 3.5, \n    y:)"),
-        err(ExpectedFloat, 3, 7)
+        err(ExpectedFloat(found(")")), 3, 7, 29)
     );
 }
 
@@ -182,24 +218,24 @@ fn untagged() {
 fn forgot_apostrophes() {
     let de: Result<(i32, String)> = from_str("(4, \"Hello)");
 
-    assert!(match de {
-        Err(Error::Parser(ParseError::ExpectedStringEnd, _)) => true,
-        _ => false,
-    });
+    assert!(matches!(
+        de,
+        Err(Error::Parser(ParseError::ExpectedStringEnd, _))
+    ));
 }
 
 #[test]
 fn expected_attribute() {
     let de: Result<String> = from_str("#\"Hello\"");
 
-    assert_eq!(de, err(ParseError::ExpectedAttribute, 1, 2));
+    assert_eq!(de, err(ParseError::ExpectedAttribute(found("\"Hello\"")), 1, 2, 1));
 }
 
 #[test]
 fn expected_attribute_end() {
     let de: Result<String> = from_str("#![enable(unwrap_newtypes) \"Hello\"");
 
-    assert_eq!(de, err(ParseError::ExpectedAttributeEnd, 1, 28));
+    assert_eq!(de, err(ParseError::ExpectedAttributeEnd(found("\"Hello\"")), 1, 28, 27));
 }
 
 #[test]
@@ -208,7 +244,7 @@ fn invalid_attribute() {
 
     assert_eq!(
         de,
-        err(ParseError::NoSuchExtension("invalid".to_string()), 1, 18)
+        err(ParseError::NoSuchExtension("invalid".to_string()), 1, 18, 17)
     );
 }
 
@@ -263,3 +299,916 @@ fn implicit_some() {
 fn ws_tuple_newtype_variant() {
     assert_eq!(Ok(MyEnum::B(true)), from_str("B  ( \n true \n ) "));
 }
+
+#[test]
+fn test_ignored_fields() {
+    use self::ParseError::*;
+    use serde::de::IgnoredAny;
+
+    assert_eq!(
+        Ok(MyStruct { x: 1.0, y: 2.0 }),
+        from_str(
+            "(
+                x: 1,
+                unknown: Some([\"),]\\\"\", ')', /* ] */ { (a: 1): [1, 2] }]),
+                other: inf,
+                y: 2,
+            )"
+        )
+    );
+    assert_eq!(Ok(IgnoredAny), from_str("Unknown(a: [(), \"\\u{5d}\"])"));
+    assert_eq!(
+        from_str::<MyStruct>("(x: 1, unknown: [(1, 2)"),
+        err(Eof, 1, 24, 23)
+    );
+    assert_eq!(
+        from_str::<MyStruct>("(x: 1, unknown: , y: 2)"),
+        err(UnexpectedByte(','), 1, 17, 16)
+    );
+    assert_eq!(
+        from_str::<IgnoredAny>("\"open"),
+        err(ExpectedStringEnd, 1, 6, 5)
+    );
+    assert_eq!(
+        from_str::<IgnoredAny>("[1, 2)"),
+        err(UnexpectedByte(')'), 1, 6, 5)
+    );
+    assert_eq!(
+        from_str::<MyStruct>("(x: 1, unknown: 1 2 3, y: 2)"),
+        err(ExpectedComma(found("2")), 1, 19, 18)
+    );
+    assert_eq!(
+        from_str::<MyStruct>("(x: 1, unknown: (1)(2), y: 2)"),
+        err(ExpectedComma(found("(")), 1, 20, 19)
+    );
+    assert_eq!(
+        Ok(MyStruct { x: 1.0, y: 2.0 }),
+        from_str("(x: 1, a: Some (1), b: &0 [x\"00\"], c: -inf, y: 2)")
+    );
+}
+
+#[test]
+fn test_from_reader() {
+    use std::io::{self, Read};
+
+    struct Broken;
+
+    impl Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("connection reset"))
+        }
+    }
+
+    let input: &[u8] = b"MyStruct(x: 1, y: 2)";
+
+    assert_eq!(Ok(MyStruct { x: 1.0, y: 2.0 }), from_reader(input));
+    assert_eq!(
+        from_reader::<_, MyStruct>(Broken),
+        Err(Error::IoError("connection reset".to_owned()))
+    );
+}
+
+#[test]
+fn test_from_bytes_utf8() {
+    fn utf8_error<T>(result: Result<T>) -> bool {
+        matches!(result, Err(Error::Parser(ParseError::Utf8Error(_), _)))
+    }
+
+    assert_eq!(Ok("caf\u{e9}".to_owned()), from_bytes(b"\"caf\xc3\xa9\""));
+    assert_eq!(Ok('\u{e9}'), from_bytes(b"'\xc3\xa9'"));
+    assert_eq!(Ok(5), from_bytes(b"// \xff\n5"));
+
+    assert!(utf8_error(from_bytes::<String>(b"\"caf\xe9\"")));
+    assert!(utf8_error(from_bytes::<String>(b"\"\\n\xe9\"")));
+    assert!(utf8_error(from_bytes::<&str>(b"\"\xff\"")));
+    assert!(utf8_error(from_bytes::<char>(b"'\xe9'")));
+}
+
+#[test]
+fn test_error_positions() {
+    use self::ParseError::*;
+
+    assert_eq!(
+        from_str::<MyStruct>("(\n    x: 1,\n)"),
+        err(Message("missing field `y`".to_owned()), 1, 1, 0)
+    );
+    // Missing fields point at the struct they are missing from
+    assert_eq!(
+        from_str::<Vec<MyStruct>>("[(x: 1, y: 2),\n MyStruct(x: 3)]"),
+        err(Message("missing field `y`".to_owned()), 2, 2, 16)
+    );
+    assert_eq!(
+        from_str::<Vec<MyEnum>>("[\n    A,\n    E,\n]"),
+        err(
+            Message("unknown variant `E`, expected one of `A`, `B`, `C`, `D`".to_owned()),
+            3,
+            5,
+            13
+        )
+    );
+    // Columns count characters
+    assert_eq!(
+        from_str::<String>("\"\u{e9}t\u{e9}\" x"),
+        err(TrailingCharacters, 1, 7, 8)
+    );
+
+    let error = from_str::<MyStruct>("(\n    x: 1,\n)").unwrap_err();
+
+    assert_eq!(error.to_string(), "1:1: missing field `y`");
+}
+
+#[test]
+fn test_error_span() {
+    let span = |input: &str| from_str::<MyStruct>(input).unwrap_err().span(input.as_bytes());
+
+    assert_eq!(span("(x: 1, y: true)"), Some(10..14));
+    assert_eq!(span("(x: 1, y: \"t\\\"wo\")"), Some(10..17));
+    assert_eq!(span("(x: 1, y: \u{e9})"), Some(10..12));
+    assert_eq!(span("(x: 1, y: 'c')"), Some(10..13));
+    assert_eq!(span("(x: 1, y: 2"), Some(11..11));
+    assert_eq!(Error::Message("custom".to_owned()).span(b""), None);
+}
+
+#[test]
+fn test_error_display() {
+    let display = |input: &str| from_str::<MyStruct>(input).unwrap_err().to_string();
+
+    assert_eq!(
+        display("(x: 1, y: \"2)"),
+        "1:11: Expected a float but found `\"2)`"
+    );
+    assert_eq!(
+        display("(x: 1, y: 2"),
+        "1:12: Expected `)` at the end of the struct but found the end of the input"
+    );
+    assert_eq!(
+        from_str::<Vec<u8>>("[1 2]").unwrap_err().to_string(),
+        "1:4: Expected `]` at the end of the array but found `2`"
+    );
+    assert_eq!(
+        display("(x: 1, unknown: 1 2, y: 2)"),
+        "1:19: Expected a comma but found `2`"
+    );
+    assert_eq!(
+        from_str::<char>("5").unwrap_err().to_string(),
+        "1:1: Expected a char, like 'c', but found `5`"
+    );
+    assert_eq!(
+        from_str::<bool>("an_identifier_which_goes_on_and_on").unwrap_err().to_string(),
+        "1:1: Expected `true` or `false` but found `an_identifier_which_goes...`"
+    );
+    assert_eq!(display("(x: 1, y: 2) ?"), "1:14: Non-whitespace trailing characters");
+    assert_eq!(display("#![enable(what)] ()"), "1:15: No extension named `what`");
+    assert_eq!(
+        from_str::<String>("\"open").unwrap_err().to_string(),
+        "1:2: Expected `\"` at the end of the string"
+    );
+    assert_eq!(
+        from_str::<String>("\"\\q\"").unwrap_err().to_string(),
+        "1:4: Invalid escape sequence: Unknown escape character"
+    );
+    assert_eq!(
+        from_bytes::<String>(b"\"\xff\"").unwrap_err().to_string(),
+        "1:2: Invalid UTF-8: invalid utf-8 sequence of 1 bytes from index 0"
+    );
+
+    let error = from_bytes::<String>(b"\"\xff\"").unwrap_err();
+
+    assert!(::std::error::Error::source(&error).is_some());
+}
+
+#[test]
+fn test_suggestions() {
+    use self::ParseError::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Style {
+        color: u32,
+        border_width: u32,
+    }
+
+    assert_eq!(
+        from_str::<Style>("(colr: 1)"),
+        err(
+            Message(
+                "unknown field `colr`, expected `color` or `border_width` \
+                 (did you mean `color`?)"
+                    .to_owned()
+            ),
+            1,
+            2,
+            1
+        )
+    );
+    assert_eq!(
+        from_str::<Style>("(color: 1, borderwidth: 1)"),
+        err(
+            Message(
+                "unknown field `borderwidth`, expected `color` or `border_width` \
+                 (did you mean `border_width`?)"
+                    .to_owned()
+            ),
+            1,
+            12,
+            11
+        )
+    );
+    assert_eq!(
+        from_str::<Style>("(size: 1)"),
+        err(
+            Message("unknown field `size`, expected `color` or `border_width`".to_owned()),
+            1,
+            2,
+            1
+        )
+    );
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Shape {
+        Circle,
+        Square,
+    }
+
+    assert_eq!(
+        from_str::<Shape>("Cirle"),
+        err(
+            Message(
+                "unknown variant `Cirle`, expected `Circle` or `Square` \
+                 (did you mean `Circle`?)"
+                    .to_owned()
+            ),
+            1,
+            1,
+            0
+        )
+    );
+    // Short names are too different from each other for suggestions
+    assert_eq!(
+        from_str::<MyEnum>("E"),
+        err(
+            Message("unknown variant `E`, expected one of `A`, `B`, `C`, `D`".to_owned()),
+            1,
+            1,
+            0
+        )
+    );
+}
+
+#[test]
+fn test_options() {
+    let options = Options::new().with_recursion_limit(Some(1));
+
+    assert_eq!(options.from_str("[1, 2]"), Ok(vec![1, 2]));
+    assert_eq!(
+        options.from_str::<Vec<Vec<u8>>>("[[1]]"),
+        err(ParseError::ExceededRecursionLimit, 1, 3, 2)
+    );
+    assert_eq!(
+        options.from_bytes(b"(x: 1, y: 2)"),
+        Ok(MyStruct { x: 1.0, y: 2.0 })
+    );
+    assert_eq!(options.from_reader(&b"Some(3)"[..]), Ok(Some(3)));
+
+    let options = options.with_recursion_limit(None);
+
+    assert_eq!(
+        options.from_str::<Vec<Vec<u8>>>("[[1]]"),
+        Ok(vec![vec![1]])
+    );
+    assert_eq!(Options::default(), Options::new());
+}
+
+#[test]
+fn test_strict_struct_names() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Meters(f32);
+
+    let options = Options::new().with_strict_struct_names(true);
+
+    assert_eq!(
+        options.from_str("MyStruct(x: 1, y: 2)"),
+        Ok(MyStruct { x: 1.0, y: 2.0 })
+    );
+    assert_eq!(
+        options.from_str::<MyStruct>("Point(x: 1, y: 2)"),
+        err(
+            ParseError::StructNameMismatch {
+                expected: "MyStruct",
+                found: "Point".to_owned(),
+            },
+            1,
+            1,
+            0
+        )
+    );
+    assert_eq!(
+        options.from_str::<MyStruct>("(x: 1, y: 2)"),
+        err(ParseError::ExpectedStructName(found("(")), 1, 1, 0)
+    );
+    // The name has to match completely
+    assert!(options.from_str::<MyStruct>("MyStructs(x: 1, y: 2)").is_err());
+    assert_eq!(options.from_str("Meters(5)"), Ok(Meters(5.0)));
+    assert!(options.from_str::<Meters>("(5)").is_err());
+    assert_eq!(options.from_str("EmptyStruct1"), Ok(EmptyStruct1));
+    assert_eq!(options.from_str("D(a: 1, b: 2)"), Ok(MyEnum::D { a: 1, b: 2 }));
+    assert!(options.from_str::<EmptyStruct1>("()").is_err());
+
+    // Without the option, the name can be left out
+    assert_eq!(from_str("(x: 1, y: 2)"), Ok(MyStruct { x: 1.0, y: 2.0 }));
+    assert_eq!(
+        from_str::<MyStruct>("Point(x: 1, y: 2)"),
+        err(ParseError::ExpectedStruct(found("Point")), 1, 1, 0)
+    );
+}
+
+#[test]
+fn test_line_continuation() {
+    assert_eq!(from_str("\"a\\\n   b\""), Ok("ab".to_owned()));
+    assert_eq!(from_str("\"a\\\r\n\t\n  b\""), Ok("ab".to_owned()));
+    assert_eq!(from_str("\"a\\\n\\n b\""), Ok("a\n b".to_owned()));
+    assert_eq!(from_str("\"a\n   b\""), Ok("a\n   b".to_owned()));
+    assert_eq!(from_str("b\"a\\\n   b\""), Ok(b"ab".to_vec()));
+    // A backslash followed by anything else is still an unknown escape
+    assert_eq!(
+        from_str::<String>("\"a\\ \n b\""),
+        err(ParseError::InvalidEscape("Unknown escape character"), 1, 5, 4)
+    );
+}
+
+#[test]
+fn test_from_str_partial() {
+    let input = "  (x: 1, y: 2) {{ template }}";
+
+    assert_eq!(from_str_partial(input), Ok((MyStruct { x: 1.0, y: 2.0 }, 14)));
+    assert_eq!(from_str_partial("[1, 2]"), Ok((vec![1, 2], 6)));
+    assert_eq!(from_str_partial("\"\u{e9}\"rest"), Ok(("\u{e9}".to_owned(), 4)));
+    assert_eq!(from_str_partial("5 6"), Ok((5, 1)));
+    assert_eq!(
+        from_str_partial::<MyStruct>("(x: 1) rest"),
+        err(ParseError::Message("missing field `y`".to_owned()), 1, 1, 0)
+    );
+}
+
+#[test]
+fn test_position() {
+    let input = "// caf\u{e9}\n  (x: 1, y: 2)\n rest";
+    let mut de = Deserializer::from_str(input).unwrap();
+
+    // The leading whitespace and comments are skipped right away
+    assert_eq!(de.position(), Position { line: 2, col: 3, offset: 11 });
+
+    let _ = <MyStruct as de::Deserialize>::deserialize(&mut de).unwrap();
+    assert_eq!(de.position(), Position { line: 2, col: 15, offset: 23 });
+    assert_eq!(de.offset(), 23);
+    assert_eq!(&input[de.offset()..], "\n rest");
+    assert_eq!(de.remainder(), "\n rest");
+}
+
+#[test]
+fn test_duplicate_keys() {
+    let input = "{ \"a\": 1, \"b\": 2, \"a\" : 3 }";
+
+    assert_eq!(
+        from_str::<HashMap<String, u8>>(input),
+        err(
+            ParseError::DuplicateKey {
+                key: "\"a\"".to_owned(),
+                first: Position {
+                    line: 1,
+                    col: 3,
+                    offset: 2,
+                },
+            },
+            1,
+            19,
+            18
+        )
+    );
+
+    let map = |policy| {
+        Options::new()
+            .with_duplicate_keys(policy)
+            .from_str::<HashMap<String, u8>>(input)
+            .unwrap()
+    };
+
+    assert_eq!(map(DuplicateKeys::KeepFirst)["a"], 1);
+    assert_eq!(map(DuplicateKeys::KeepFirst)["b"], 2);
+    assert_eq!(map(DuplicateKeys::KeepLast)["a"], 3);
+
+    // Keys of nested maps are separate
+    let nested: HashMap<u8, HashMap<u8, u8>> = from_str("{ 1: { 1: 2 }, 2: { 1: 3 } }").unwrap();
+
+    assert_eq!(nested[&2][&1], 3);
+    assert_eq!(
+        Options::new()
+            .with_duplicate_keys(DuplicateKeys::KeepFirst)
+            .from_str("{ (1, 2): [3], (1, 2): [4, 5], (2, 1): [] }"),
+        Ok(vec![((1, 2), vec![3]), ((2, 1), vec![])]
+            .into_iter()
+            .collect::<HashMap<(u8, u8), Vec<u8>>>())
+    );
+    assert!(from_str::<::value::Value>("{ 1: 2, 1: 2 }").is_err());
+    assert!(from_str::<HashMap<String, u8>>("{ \"a\": 1, \"a\" /* again */ : 2 }").is_err());
+}
+
+#[test]
+fn test_duplicate_fields() {
+    let input = "(\n    x: 1,\n    y: 2,\n    x: 3,\n)";
+
+    assert_eq!(
+        from_str::<MyStruct>(input),
+        err(
+            ParseError::DuplicateField {
+                field: "x".to_owned(),
+                first: Position {
+                    line: 2,
+                    col: 5,
+                    offset: 6,
+                },
+            },
+            4,
+            5,
+            26
+        )
+    );
+    assert!(from_str::<MyEnum>("D(a: 1, b: 2, b: 3)").is_err());
+    assert!(from_str::<::value::Value>("(a: 1, a: 1)").is_err());
+
+    // Without the check, the struct itself decides
+    let legacy = Options::new().with_reject_duplicate_fields(false);
+
+    assert_eq!(
+        legacy.from_str::<MyStruct>(input),
+        err(ParseError::Message("duplicate field `x`".to_owned()), 4, 6, 27)
+    );
+    assert!(legacy.from_str::<::value::Value>("(a: 1, a: 1)").is_ok());
+}
+
+#[test]
+fn test_lenient_field_names() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Window {
+        max_width: u32,
+        title_bar: bool,
+    }
+
+    let lenient = Options::new().with_lenient_field_names(true);
+    let window = Window {
+        max_width: 800,
+        title_bar: true,
+    };
+
+    for input in &[
+        "(max_width: 800, title_bar: true)",
+        "(maxWidth: 800, titleBar: true)",
+        "(max-width: 800, title-bar: true)",
+        "(MAX_WIDTH: 800, TitleBar: true)",
+    ] {
+        assert_eq!(lenient.from_str::<Window>(input).as_ref(), Ok(&window));
+    }
+
+    assert_eq!(
+        lenient.from_str::<MyEnum>("D(A: 1, B: 2)"),
+        Ok(MyEnum::D { a: 1, b: 2 })
+    );
+    assert!(lenient
+        .from_str::<Window>("(max_width: 800, maxWidth: 800, title_bar: true)")
+        .is_err());
+
+    // Names which match no field are passed on as they are
+    assert_eq!(
+        lenient.from_str::<MyStruct>("(x: 1, y: 2, zed: 3)"),
+        Ok(MyStruct { x: 1.0, y: 2.0 })
+    );
+    assert!(from_str::<Window>("(maxWidth: 800, titleBar: true)").is_err());
+}
+
+#[test]
+fn test_struct_as_map() {
+    let expected: HashMap<String, u8> = vec![("a".to_owned(), 1), ("b".to_owned(), 2)]
+        .into_iter()
+        .collect();
+
+    assert_eq!(from_str("(a: 1, b: 2)"), Ok(expected.clone()));
+    assert_eq!(from_str("Named (a: 1, b: 2)"), Ok(expected));
+    assert_eq!(
+        from_str::<HashMap<String, u8>>("Named"),
+        err(ParseError::ExpectedMap(found("Named")), 1, 1, 0)
+    );
+    // Quoted names
+    assert_eq!(from_str("\"B\"(true)"), Ok(MyEnum::B(true)));
+}
+
+#[test]
+fn test_strict_grammar() {
+    use std::collections::HashMap;
+
+    let strict = Options::new().with_strict_grammar(true);
+
+    assert_eq!(strict.from_str("(1, 2,)"), Ok((1, 2)));
+    assert_eq!(strict.from_str("[\n  1,\n  2, // two\n]"), Ok(vec![1, 2]));
+    assert_eq!(
+        strict.from_str("MyStruct(\"x\": 1, y: 2,)"),
+        Ok(MyStruct { x: 1.0, y: 2.0 })
+    );
+    assert_eq!(
+        strict.from_str("{\"x\": 1}"),
+        Ok(vec![("x".to_owned(), 1)].into_iter().collect::<HashMap<_, _>>())
+    );
+
+    assert_eq!(from_str("(1, 2,,)"), Ok((1, 2)));
+    assert_eq!(
+        strict.from_str::<(u8, u8)>("(1, 2,,)"),
+        err(ParseError::ExpectedArrayEnd(found(",")), 1, 7, 6)
+    );
+    assert_eq!(
+        strict.from_str::<MyEnum>("C(true, 1,,)"),
+        err(ParseError::ExpectedArrayEnd(found(",")), 1, 11, 10)
+    );
+
+    assert!(from_str::<HashMap<String, u8>>("Size(w: 1)").is_ok());
+    assert_eq!(
+        strict.from_str::<HashMap<String, u8>>("Size(w: 1)"),
+        err(ParseError::ExpectedMap(found("Size")), 1, 1, 0)
+    );
+}
+
+#[test]
+fn test_numeric_variants() {
+    let numeric = Options::new().with_numeric_variants(true);
+
+    assert_eq!(numeric.from_str("0"), Ok(MyEnum::A));
+    assert_eq!(numeric.from_str("1(true)"), Ok(MyEnum::B(true)));
+    assert_eq!(numeric.from_str("2 (false, 1.5)"), Ok(MyEnum::C(false, 1.5)));
+    assert_eq!(
+        numeric.from_str("[3(a: 1, b: 2), D(a: 3, b: 4)]"),
+        Ok(vec![MyEnum::D { a: 1, b: 2 }, MyEnum::D { a: 3, b: 4 }])
+    );
+    assert_eq!(
+        numeric.from_str("(0: 1, 1: 2)"),
+        Ok(MyStruct { x: 1.0, y: 2.0 })
+    );
+    assert_eq!(
+        numeric.from_str::<Vec<MyEnum>>("[0, 4]"),
+        err(
+            ParseError::Message(
+                "unknown variant `4`, expected one of `A`, `B`, `C`, `D`".to_owned()
+            ),
+            1,
+            5,
+            4
+        )
+    );
+    assert!(from_str::<MyEnum>("0").is_err());
+}
+
+#[test]
+fn test_maps_as_structs() {
+    let maps = Options::new()
+        .with_maps_as_structs(true)
+        .with_strict_struct_names(true);
+
+    assert_eq!(
+        maps.from_str("{ \"x\": 1, \"y\": 2 }"),
+        Ok(MyStruct { x: 1.0, y: 2.0 })
+    );
+    assert_eq!(
+        maps.from_str("[MyStruct(x: 1, y: 2), {\"y\": 4, \"x\": 3,}]"),
+        Ok(vec![MyStruct { x: 1.0, y: 2.0 }, MyStruct { x: 3.0, y: 4.0 }])
+    );
+    assert_eq!(
+        maps.from_str::<MyStruct>("{\"x\": 1, \"x\": 2}"),
+        err(
+            ParseError::DuplicateKey {
+                key: "\"x\"".to_owned(),
+                first: Position {
+                    col: 2,
+                    line: 1,
+                    offset: 1,
+                },
+            },
+            1,
+            10,
+            9
+        )
+    );
+    assert_eq!(
+        from_str::<MyStruct>("{\"x\": 1, \"y\": 2}"),
+        err(ParseError::ExpectedStruct(found("{")), 1, 1, 0)
+    );
+}
+
+#[test]
+fn test_quoted_field_names() {
+    assert_eq!(
+        from_str("(\"x\": 1, y: 2)"),
+        Ok(MyStruct { x: 1.0, y: 2.0 })
+    );
+    assert_eq!(
+        from_str("MyStruct(\"y\": 2, \"x\": 1,)"),
+        Ok(MyStruct { x: 1.0, y: 2.0 })
+    );
+    assert_eq!(from_str("D(\"a\": 1, \"b\": 2)"), Ok(MyEnum::D { a: 1, b: 2 }));
+    assert_eq!(
+        from_str::<MyStruct>("(x: 1, \"x\": 2)"),
+        err(
+            ParseError::DuplicateField {
+                field: "x".to_owned(),
+                first: Position {
+                    col: 2,
+                    line: 1,
+                    offset: 1,
+                },
+            },
+            1,
+            8,
+            7
+        )
+    );
+    assert_eq!(
+        Options::new()
+            .with_lenient_field_names(true)
+            .from_str("(\"X\": 1, \"y\": 2)"),
+        Ok(MyStruct { x: 1.0, y: 2.0 })
+    );
+    assert_eq!(
+        from_str_recovering("(\"x\": 1, \"y\": 2)"),
+        (Some(MyStruct { x: 1.0, y: 2.0 }), vec![])
+    );
+}
+
+#[test]
+fn test_raw_identifiers() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Ranks {
+        #[serde(rename = "1st")]
+        first: u8,
+        #[serde(rename = "type")]
+        kind: MyEnum,
+    }
+
+    assert_eq!(
+        from_str("(r#1st: 3, r#type: A)"),
+        Ok(Ranks {
+            first: 3,
+            kind: MyEnum::A,
+        })
+    );
+    assert_eq!(from_str("r#MyStruct(r#x: 1, y: 2)"), Ok(MyStruct { x: 1.0, y: 2.0 }));
+    assert_eq!(from_str("r#B(true)"), Ok(MyEnum::B(true)));
+    assert_eq!(
+        from_str::<Ranks>("(r#: 3)"),
+        err(ParseError::ExpectedMapColon(found("#")), 1, 3, 2)
+    );
+}
+
+#[test]
+fn test_limits() {
+    use super::Limits;
+
+    let limited = |limits| Options::new().with_limits(limits);
+
+    let strings = limited(Limits {
+        max_string_len: Some(3),
+        ..Limits::default()
+    });
+
+    assert_eq!(strings.from_str("[\"abc\", \"x\"]"), Ok(vec!["abc".to_owned(), "x".to_owned()]));
+    assert_eq!(
+        strings.from_str::<Vec<String>>("[\"x\", \"abcd\"]"),
+        err(ParseError::ExceededStringLimit, 1, 7, 6)
+    );
+    assert_eq!(
+        strings.from_str::<&[u8]>("b\"abcd\""),
+        err(ParseError::ExceededStringLimit, 1, 1, 0)
+    );
+    assert_eq!(
+        strings.from_str::<Vec<u8>>("x\"00 01 02 03\""),
+        err(ParseError::ExceededStringLimit, 1, 1, 0)
+    );
+
+    let elements = limited(Limits {
+        max_elements: Some(2),
+        ..Limits::default()
+    });
+
+    assert_eq!(
+        elements.from_str("[[1, 2], [3, 4]]"),
+        Ok(vec![vec![1, 2], vec![3, 4]])
+    );
+    assert_eq!(
+        elements.from_str::<MyStruct>("(x: 1, y: 2, z: 3)"),
+        err(ParseError::ExceededElementLimit, 1, 14, 13)
+    );
+
+    let input = limited(Limits {
+        max_input_len: Some(5),
+        ..Limits::default()
+    });
+
+    assert_eq!(input.from_str("[1,2]"), Ok(vec![1, 2]));
+    assert_eq!(
+        input.from_reader::<_, Vec<u8>>(&b"[1, 2]"[..]),
+        err(ParseError::ExceededInputLimit, 1, 1, 0)
+    );
+}
+
+#[test]
+fn test_from_str_recovering() {
+    use super::from_str_recovering;
+
+    let input = "[
+        (x: 1, y: 2),
+        (x: 1 y: 2),
+        (x: 3, y: \"4),
+        (x: 5, y: 6),
+        (x: , y: 7),
+    ]";
+    let (value, errors) = from_str_recovering::<Vec<MyStruct>>(input);
+
+    assert_eq!(value, None);
+    assert_eq!(
+        errors,
+        vec![
+            err::<()>(ParseError::ExpectedComma(found("y")), 3, 15, 38).unwrap_err(),
+            err::<()>(ParseError::ExpectedStringEnd, 4, 20, 64).unwrap_err(),
+            err::<()>(ParseError::UnexpectedByte(','), 6, 13, 102).unwrap_err(),
+        ]
+    );
+
+    // Errors at the end of the input are reported once
+    let (_, errors) = from_str_recovering::<Vec<Vec<u8>>>("[[1, [2,");
+
+    assert_eq!(errors, vec![err::<()>(ParseError::ExpectedArrayEnd(None), 1, 9, 8).unwrap_err()]);
+
+    let (_, errors) = from_str_recovering::<MyStruct>("(x: 1, y: 2) )");
+
+    assert_eq!(errors, vec![err::<()>(ParseError::TrailingCharacters, 1, 14, 13).unwrap_err()]);
+
+    // Type errors are found if the syntax is fine
+    assert_eq!(
+        from_str_recovering("(x: 1, y: 2)"),
+        (Some(MyStruct { x: 1.0, y: 2.0 }), vec![])
+    );
+    assert_eq!(
+        from_str_recovering::<MyStruct>("(x: 1, y: true)").1,
+        vec![err::<()>(ParseError::ExpectedFloat(found("true")), 1, 11, 10).unwrap_err()]
+    );
+
+    // The syntax options apply to the recovering walk as well
+    assert_eq!(
+        Options::new().with_json(true).from_str_recovering("\"caf\\u00e9\""),
+        (Some("caf\u{e9}".to_owned()), vec![])
+    );
+    assert_eq!(
+        Options::new()
+            .with_replace_lone_surrogates(true)
+            .from_str_recovering("\"\\u{d83d}!\""),
+        (Some("\u{fffd}!".to_owned()), vec![])
+    );
+}
+
+#[test]
+fn test_error_paths() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Transform {
+        scale: f32,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Entity {
+        transform: Transform,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Scene {
+        entities: Vec<Entity>,
+        tags: HashMap<String, (u8, u8)>,
+    }
+
+    let paths = Options::new().with_error_paths(true);
+    let scene = |entity, tag| {
+        format!(
+            "(entities: [(transform: (scale: 1)), {}], tags: {{\"a\": (1, 2), \"b\": {}}})",
+            entity, tag
+        )
+    };
+
+    let error = paths
+        .from_str::<Scene>(&scene("(transform: (scale: true))", "(3, 4)"))
+        .unwrap_err();
+
+    assert_eq!(
+        error,
+        Error::Path {
+            path: "entities[1].transform.scale".to_owned(),
+            error: Box::new(
+                err::<()>(ParseError::ExpectedFloat(found("true")), 1, 58, 57).unwrap_err(),
+            ),
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "entities[1].transform.scale: 1:58: Expected a float but found `true`"
+    );
+    assert_eq!(error.position(), Some(Position { col: 58, line: 1, offset: 57 }));
+
+    match paths.from_str::<Scene>(&scene("(transform: (scale: 2))", "(3, -4)")) {
+        Err(Error::Path { ref path, .. }) if path == "tags[\"b\"][1]" => {}
+        other => panic!("{:?}", other),
+    }
+    match paths.from_str::<Scene>(&scene("(transform: ())", "(3, 4)")) {
+        Err(Error::Path { ref path, ref error }) if path == "entities[1].transform" => {
+            assert!(error.to_string().ends_with("missing field `scale`"))
+        }
+        other => panic!("{:?}", other),
+    }
+
+    // Errors in the outermost value aren't wrapped
+    assert_eq!(
+        paths.from_str::<Scene>("[]").unwrap_err(),
+        err::<()>(ParseError::ExpectedStruct(found("[")), 1, 1, 0).unwrap_err()
+    );
+    assert_eq!(
+        from_str::<Scene>(&scene("(transform: (scale: true))", "(3, 4)")).unwrap_err(),
+        err::<()>(ParseError::ExpectedFloat(found("true")), 1, 58, 57).unwrap_err()
+    );
+}
+
+#[test]
+fn test_raw_value() {
+    use value::RawValue;
+
+    #[derive(Debug, Deserialize)]
+    struct Plugin {
+        name: String,
+        config: RawValue,
+    }
+
+    let plugin: Plugin = from_str(
+        "(name: \"lights\", config: Lights(levels: [1, 2], mode: ')') // dim\n)",
+    )
+    .unwrap();
+
+    assert_eq!(plugin.name, "lights");
+    assert_eq!(plugin.config.get_ron(), "Lights(levels: [1, 2], mode: ')')");
+
+    let raw: Vec<RawValue> = from_str("[1 , \"a, b\" ,(x: 1.0, y: 2.0)]").unwrap();
+    let raw: Vec<&str> = raw.iter().map(RawValue::get_ron).collect();
+
+    assert_eq!(raw, ["1", "\"a, b\"", "(x: 1.0, y: 2.0)"]);
+    assert_eq!(
+        RawValue::from_ron(" (x: 1.0, y: 2.0) ").unwrap().into_rust::<MyStruct>(),
+        Ok(MyStruct { x: 1.0, y: 2.0 })
+    );
+    assert_eq!(
+        RawValue::from_ron("(x: 1.0,"),
+        err(ParseError::Eof, 1, 9, 8)
+    );
+    assert_eq!(
+        from_str::<RawValue>("[1, 2"),
+        err(ParseError::Eof, 1, 6, 5)
+    );
+}
+
+#[test]
+fn test_seed() {
+    /// Reads a list of names, and adds them to the registry.
+    struct Register<'r>(&'r mut Vec<String>);
+
+    impl<'de, 'r> DeserializeSeed<'de> for Register<'r> {
+        type Value = usize;
+
+        fn deserialize<D>(self, deserializer: D) -> std::result::Result<usize, D::Error>
+        where
+            D: de::Deserializer<'de>,
+        {
+            let names: Vec<String> = de::Deserialize::deserialize(deserializer)?;
+            self.0.extend(names);
+
+            Ok(self.0.len())
+        }
+    }
+
+    let mut registry = Vec::new();
+
+    assert_eq!(from_str_seed("[\"a\", \"b\"]", Register(&mut registry)), Ok(2));
+    assert_eq!(from_bytes_seed(b"[\"c\"]", Register(&mut registry)), Ok(3));
+    assert_eq!(from_reader_seed(&b"[]"[..], Register(&mut registry)), Ok(3));
+    assert_eq!(registry, vec!["a", "b", "c"]);
+    assert_eq!(
+        from_str_seed("[\"d\"] 5", Register(&mut registry)),
+        err(ParseError::TrailingCharacters, 1, 7, 6)
+    );
+    assert_eq!(
+        Options::new()
+            .with_default_extensions(Extensions::IMPLICIT_SOME)
+            .from_str_seed("[\"e\"]", Register(&mut registry)),
+        Ok(5)
+    );
+}
@@ -1,14 +1,36 @@
-use std::collections::BTreeMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 
 use serde::{Deserialize, Deserializer};
 use serde::de::{Error, MapAccess, SeqAccess, Visitor};
 
-use de;
-use value::{Number, Value};
+use de::{self, ParseError, Position};
+use parse::{Bytes, Expected};
+use value::{Number, UnknownFields, Value};
+
+use super::id::IdDeserializer;
+use super::path::{self, Segment};
+
+/// The name `Value` is deserialized with, which the RON deserializer
+/// recognizes, so that it can read the value without recursion.
+pub const UNTYPED_VALUE: &str = "$ron::Value";
+
+thread_local! {
+    /// The value the RON deserializer hands over to the visitor
+    /// of a `Value`.
+    static HANDED_OVER: RefCell<Option<Value>> = const { RefCell::new(None) };
+}
+
+/// Hands `value` over to the visitor of a `Value`, which the
+/// deserializer visits with `visit_unit` next.
+pub fn hand_over(value: Value) {
+    HANDED_OVER.with(|handed_over| *handed_over.borrow_mut() = Some(value));
+}
 
 impl Value {
     /// Creates a value from a string reference.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> de::Result<Self> {
         Value::deserialize(&mut super::Deserializer::from_str(s)?)
     }
@@ -19,7 +41,16 @@ impl<'de> Deserialize<'de> for Value {
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_any(ValueVisitor)
+        deserializer.deserialize_newtype_struct(UNTYPED_VALUE, ValueVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for UnknownFields {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        BTreeMap::deserialize(deserializer).map(UnknownFields)
     }
 }
 
@@ -111,11 +142,14 @@ impl<'de> Visitor<'de> for ValueVisitor {
         )))
     }
 
+    /// Takes the value the RON deserializer has handed over, if any.
     fn visit_unit<E>(self) -> Result<Self::Value, E>
     where
         E: Error,
     {
-        Ok(Value::Unit)
+        let handed_over = HANDED_OVER.with(|handed_over| handed_over.borrow_mut().take());
+
+        Ok(handed_over.unwrap_or(Value::Unit))
     }
 
     fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
@@ -155,6 +189,353 @@ impl<'de> Visitor<'de> for ValueVisitor {
     }
 }
 
+/// What a collection which `parse` is in becomes.
+enum Collection {
+    Seq(Vec<Value>),
+    /// A map or struct.
+    Map(BTreeMap<Value, Value>),
+    /// The contents of a `Some(...)`, once they've been read.
+    Some(Option<Value>),
+}
+
+/// A collection which `parse` is in.
+struct Frame<'de> {
+    collection: Collection,
+    /// The byte which ends the collection.
+    terminator: u8,
+    /// The error if it doesn't end with `terminator`.
+    end_error: Expected,
+    had_comma: bool,
+    /// How many elements or entries have been read so far.
+    len: usize,
+    /// Where the keys read so far were first written.
+    keys: HashMap<&'de [u8], Position>,
+    /// The key of the entry whose value comes next.
+    key: Option<Value>,
+    /// Where the map key which is being read starts, and the path
+    /// segment of its entry.
+    key_start: Option<(Bytes<'de>, Segment<'de>)>,
+}
+
+/// Reads an untyped value, like `deserialize_any` with `ValueVisitor`
+/// would, but keeps the collections it's in on a stack of its own
+/// instead of recursing, so that deeply nested values can't exhaust
+/// the call stack.
+///
+/// Values without contents, like numbers and strings, are still read
+/// by `deserialize_any`.
+pub fn parse(de: &mut super::Deserializer) -> de::Result<Value> {
+    let depth = de.depth;
+    let path_len = de.path.len();
+    let res = de.parse_values(&mut Vec::new());
+    de.depth = depth;
+
+    // Like `at_path`, but only once for the innermost value
+    let res = res.map_err(|e| match e {
+        de::Error::Path { .. } => e,
+        e if de.path.len() > path_len => de::Error::Path {
+            path: path::format(&de.path),
+            error: Box::new(de.locate(e)),
+        },
+        e => e,
+    });
+    de.path.truncate(path_len);
+
+    res
+}
+
+impl<'de> super::Deserializer<'de> {
+    fn parse_values(&mut self, stack: &mut Vec<Frame<'de>>) -> de::Result<Value> {
+        loop {
+            let mut value = match self.open_collection()? {
+                Some(frame) => {
+                    stack.push(frame);
+
+                    None
+                }
+                None => match Deserializer::deserialize_any(&mut *self, ValueVisitor) {
+                    // Errors about a key point at its start
+                    Err(de::Error::Message(msg)) => match stack.last() {
+                        Some(&Frame { key_start: Some((start, _)), .. }) => {
+                            return Err(start.error(ParseError::Message(msg)));
+                        }
+                        _ => return Err(de::Error::Message(msg)),
+                    },
+                    value => Some(value?),
+                },
+            };
+
+            // Adds the value to its collection, and closes every collection
+            // which ends afterwards, until another value comes next
+            loop {
+                let frame = match stack.last_mut() {
+                    Some(frame) => frame,
+                    None => return Ok(value.expect("Bug: no value has been read")),
+                };
+
+                if let Some(value) = value.take() {
+                    self.add_value(frame, value)?;
+                }
+
+                if self.next_value(frame)? {
+                    break;
+                }
+
+                let frame = stack.pop().expect("Bug: collection has been popped already");
+                value = Some(self.close_collection(frame)?);
+            }
+        }
+    }
+
+    /// Opens the collection which comes next, if it's one, in the same
+    /// cases as `deserialize_any`.
+    fn open_collection(&mut self) -> de::Result<Option<Frame<'de>>> {
+        while self.bytes.peek() == Some(b'&') {
+            self.anchor_id()?;
+        }
+
+        if self.bytes.check_ident("Some") {
+            self.bytes.consume("Some");
+            self.bytes.skip_ws()?;
+            if !self.bytes.consume("(") {
+                return self.bytes.expected(ParseError::ExpectedOption);
+            }
+            self.bytes.skip_ws()?;
+
+            return self.open(Collection::Some(None), b')', ParseError::ExpectedOptionEnd);
+        }
+
+        let mut probe = self.bytes;
+        let scalar = ["true", "false", "None"].iter().any(|name| probe.check_ident(name))
+            || (self.bytes.json && probe.check_ident("null"))
+            || probe.consume("()")
+            || self.bytes.check_hex_bytes()
+            || self.bytes.check_byte_string()
+            || self.bytes.peek() == Some(b'*')
+            || probe.check_non_finite_float()
+            || self.bytes.check_raw_string();
+        if scalar {
+            return Ok(None);
+        }
+
+        let start = self.bytes;
+        if self.bytes.try_identifier().is_some() {
+            self.bytes.skip_ws()?;
+
+            if self.bytes.peek() != Some(b'(') {
+                // A unit enum variant
+                self.bytes = start;
+
+                return Ok(None);
+            }
+        } else {
+            self.bytes = start;
+        }
+
+        if self.bytes.check_based_integer() {
+            return Ok(None);
+        }
+
+        let (collection, terminator, end_error): (_, _, Expected) = match self.bytes.peek() {
+            Some(b'(') if self.bytes.check_tuple() => {
+                (Collection::Seq(Vec::new()), b')', ParseError::ExpectedArrayEnd)
+            }
+            Some(b'(') => (
+                Collection::Map(BTreeMap::new()),
+                b')',
+                ParseError::ExpectedStructEnd,
+            ),
+            Some(b'[') => (Collection::Seq(Vec::new()), b']', ParseError::ExpectedArrayEnd),
+            Some(b'{') => (
+                Collection::Map(BTreeMap::new()),
+                b'}',
+                ParseError::ExpectedMapEnd,
+            ),
+            _ => return Ok(None),
+        };
+        self.bytes.advance_single()?;
+
+        self.open(collection, terminator, end_error)
+    }
+
+    /// Opens a collection one level deeper, whose opening
+    /// delimiter has been read.
+    fn open(
+        &mut self,
+        collection: Collection,
+        terminator: u8,
+        end_error: Expected,
+    ) -> de::Result<Option<Frame<'de>>> {
+        self.depth += 1;
+        match self.recursion_limit {
+            Some(limit) if self.depth > limit => {
+                return self.bytes.err(ParseError::ExceededRecursionLimit);
+            }
+            _ => {}
+        }
+
+        Ok(Some(Frame {
+            collection,
+            terminator,
+            end_error,
+            had_comma: true,
+            len: 0,
+            keys: HashMap::new(),
+            key: None,
+            key_start: None,
+        }))
+    }
+
+    fn add_value(&mut self, frame: &mut Frame<'de>, value: Value) -> de::Result<()> {
+        match frame.collection {
+            Collection::Seq(ref mut seq) => seq.push(value),
+            Collection::Map(_) if frame.key_start.is_some() => {
+                let (start, segment) = frame.key_start.take().expect("Bug: no key is read");
+                self.check_duplicate_key(frame.terminator, &mut frame.keys, start)?;
+                frame.key = Some(value);
+
+                return self.entry_value(segment);
+            }
+            Collection::Map(ref mut map) => {
+                let key = frame.key.take().expect("Bug: value without a key");
+                map.insert(key, value);
+            }
+            Collection::Some(ref mut some) => {
+                *some = Some(value);
+
+                return Ok(());
+            }
+        }
+
+        if self.error_paths {
+            self.path.pop();
+        }
+        frame.had_comma = self.bytes.comma()?;
+
+        Ok(())
+    }
+
+    /// Reads up to the next value of `frame`, if there's one,
+    /// and returns whether there is.
+    fn next_value(&mut self, frame: &mut Frame<'de>) -> de::Result<bool> {
+        if let Collection::Some(ref some) = frame.collection {
+            return Ok(some.is_none());
+        }
+        // The value of the entry whose key has just been read
+        if frame.key.is_some() {
+            return Ok(true);
+        }
+
+        loop {
+            self.bytes.skip_ws()?;
+
+            if !frame.had_comma || self.bytes.peek_or_eof()? == frame.terminator {
+                return Ok(false);
+            }
+
+            match frame.collection {
+                Collection::Map(..) if self.skip_duplicate_key(frame.terminator, &mut frame.keys)? => {
+                    frame.had_comma = self.bytes.comma()?;
+                }
+                _ => break,
+            }
+        }
+
+        frame.len += 1;
+        self.count_element(frame.len)?;
+
+        if let Collection::Seq(_) = frame.collection {
+            if self.error_paths {
+                self.path.push(Segment::Index(frame.len - 1));
+            }
+
+            return Ok(true);
+        }
+
+        let text = match self.error_paths {
+            true => self.key_text(frame.terminator).map(|(text, _)| text),
+            false => None,
+        };
+        let segment = match text {
+            Some(text) if frame.terminator == b')' => Segment::Field(text),
+            Some(text) => Segment::Key(text),
+            None => Segment::Unknown,
+        };
+
+        if frame.terminator == b'}' {
+            // Map keys can be collections themselves, so they're read
+            // like values, and `add_value` picks them up
+            frame.key_start = Some((self.bytes, segment));
+
+            return Ok(true);
+        }
+
+        frame.key = Some(self.read_field(frame)?);
+        self.entry_value(segment)?;
+
+        Ok(true)
+    }
+
+    /// Reads the field name of the next entry of the struct `frame`.
+    fn read_field(&mut self, frame: &mut Frame<'de>) -> de::Result<Value> {
+        // Errors about the field point at its start
+        let start: Bytes<'de> = self.bytes;
+        let key = match Deserializer::deserialize_any(&mut IdDeserializer::new(self), ValueVisitor) {
+            Ok(key) => key,
+            Err(de::Error::Message(msg)) => return Err(start.error(ParseError::Message(msg))),
+            Err(e) => return Err(e),
+        };
+        self.check_duplicate_key(frame.terminator, &mut frame.keys, start)?;
+
+        Ok(key)
+    }
+
+    /// Reads up to the value of an entry whose key has been read.
+    fn entry_value(&mut self, segment: Segment<'de>) -> de::Result<()> {
+        self.bytes.skip_ws()?;
+        if !self.bytes.consume(":") {
+            return self.bytes.expected(ParseError::ExpectedMapColon);
+        }
+        self.bytes.skip_ws()?;
+
+        if self.error_paths {
+            self.path.push(segment);
+        }
+
+        Ok(())
+    }
+
+    fn close_collection(&mut self, frame: Frame<'de>) -> de::Result<Value> {
+        self.depth -= 1;
+
+        let value = match frame.collection {
+            Collection::Some(some) => {
+                self.bytes.skip_ws()?;
+
+                Value::Option(some.map(Box::new))
+            }
+            Collection::Seq(seq) => {
+                self.skip_trailing_comma()?;
+
+                Value::Seq(seq)
+            }
+            Collection::Map(map) => {
+                self.skip_trailing_comma()?;
+
+                Value::Map(map)
+            }
+        };
+
+        if self.bytes.peek() == Some(frame.terminator) {
+            self.bytes.advance_single()?;
+
+            Ok(value)
+        } else {
+            self.bytes.expected(frame.end_error)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -177,6 +558,13 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_based_integers() {
+        assert_eq!(eval("0xFF00"), Value::Number(Number::new(65280.0)));
+        assert_eq!(eval("0o17"), Value::Number(Number::new(15.0)));
+        assert_eq!(eval("0b101"), Value::Number(Number::new(5.0)));
+    }
+
     #[test]
     fn test_complex() {
         assert_eq!(
@@ -0,0 +1,28 @@
+//! Extensions to the RON syntax.
+
+bitflags! {
+    /// Extensions to the RON syntax, which a document enables with
+    /// an attribute at its start, like `#![enable(implicit_some)]`.
+    ///
+    /// They can also be enabled for all documents with
+    /// `de::Options::with_default_extensions`.
+    pub struct Extensions: usize {
+        /// Newtype structs are written as just their value,
+        /// like `5` instead of `Meters(5)`.
+        const UNWRAP_NEWTYPES = 0x1;
+        /// `Some(value)` can be written as just `value`.
+        const IMPLICIT_SOME = 0x2;
+    }
+}
+
+impl Extensions {
+    /// Creates an extension flag from its name in an attribute,
+    /// like `implicit_some`.
+    pub fn from_ident(ident: &[u8]) -> Option<Extensions> {
+        match ident {
+            b"unwrap_newtypes" => Some(Extensions::UNWRAP_NEWTYPES),
+            b"implicit_some" => Some(Extensions::IMPLICIT_SOME),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use serde::de::{self, Deserialize, Deserializer, Unexpected, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+/// The name `Interned` is deserialized with, which the RON deserializer
+/// recognizes.
+pub const INTERNED: &str = "$ron::Interned";
+
+thread_local! {
+    /// The string the RON deserializer hands over to the visitor
+    /// of an `Interned`.
+    static HANDED_OVER: RefCell<Option<Arc<str>>> = const { RefCell::new(None) };
+}
+
+/// A string which shares its buffer with the identical strings of the
+/// input, if the deserializer interns strings, for inputs which repeat
+/// the same strings, like asset paths, a lot.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use ron::de::{Interned, Options};
+///
+/// let input = r#"["textures/grass.png", "textures/grass.png"]"#;
+/// let paths: Vec<Interned> = Options::new()
+///     .with_intern_strings(true)
+///     .from_str(input)
+///     .unwrap();
+///
+/// assert_eq!(&*paths[0], "textures/grass.png");
+/// assert!(Arc::ptr_eq(&paths[0].0, &paths[1].0));
+/// ```
+///
+/// Otherwise, and with other formats, every `Interned` has a buffer
+/// of its own. It's serialized as a plain string.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interned(pub Arc<str>);
+
+impl Deref for Interned {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Interned {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'a> From<&'a str> for Interned {
+    fn from(s: &'a str) -> Self {
+        Interned(s.into())
+    }
+}
+
+impl From<String> for Interned {
+    fn from(s: String) -> Self {
+        Interned(s.into())
+    }
+}
+
+impl fmt::Display for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for Interned {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Interned {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(INTERNED, InternedVisitor)
+    }
+}
+
+/// Hands `s` over to the visitor of an `Interned`, which the
+/// deserializer visits with `visit_unit` next.
+pub fn hand_over(s: Arc<str>) {
+    HANDED_OVER.with(|handed_over| *handed_over.borrow_mut() = Some(s));
+}
+
+struct InternedVisitor;
+
+impl<'de> Visitor<'de> for InternedVisitor {
+    type Value = Interned;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(v.into())
+    }
+
+    /// Takes the string the RON deserializer has handed over.
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        HANDED_OVER
+            .with(|handed_over| handed_over.borrow_mut().take())
+            .map(Interned)
+            .ok_or_else(|| de::Error::invalid_type(Unexpected::Unit, &self))
+    }
+
+    /// Reads the string other formats see.
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(Interned::from)
+    }
+}
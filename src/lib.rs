@@ -63,9 +63,21 @@ Serializing / Deserializing is as simple as calling `to_string` / `from_str`.
 extern crate bitflags;
 #[macro_use]
 extern crate serde;
+#[cfg(feature = "async")]
+extern crate tokio;
+extern crate unicode_xid;
 
 pub mod de;
+pub mod extensions;
 pub mod ser;
 pub mod value;
 
+pub use spanned::Spanned;
+
+mod base64;
+mod interned;
 mod parse;
+mod raw;
+mod scan;
+mod shared;
+mod spanned;
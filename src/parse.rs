@@ -1,24 +1,37 @@
+use std::borrow::Cow;
 use std::char::from_u32 as char_from_u32;
 use std::fmt::{Display, Formatter, Result as FmtResult};
-use std::ops::Neg;
+use std::num::IntErrorKind;
 use std::result::Result as StdResult;
 use std::str::{FromStr, from_utf8, from_utf8_unchecked};
 
+use unicode_xid::UnicodeXID;
+
+use base64;
 use de::{Error, ParseError, Result};
+use extensions::Extensions;
+use scan::{self, Lines};
 
-const DIGITS: &[u8] = b"0123456789ABCDEFabcdef";
-const FLOAT_CHARS: &[u8] = b"0123456789.+-eE";
-const IDENT_FIRST: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz_";
+const DIGITS: &[u8] = b"0123456789ABCDEFabcdef_";
+const FLOAT_CHARS: &[u8] = b"0123456789.+-eE_";
+const NON_FINITE_FLOATS: &[&str] = &["inf", "+inf", "-inf", "NaN"];
 const IDENT_CHAR: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz_0123456789";
+const IDENT_RAW_CHAR: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz_0123456789.+-";
 const WHITE_SPACE: &[u8] = b"\n\t\r ";
 
 #[derive(Clone, Copy, Debug)]
 pub struct Bytes<'a> {
     /// Bits set according to `Extension` enum.
     pub exts: Extensions,
+    /// Whether JSON's escapes, like `\u00e9` and `\/`, are allowed.
+    pub json: bool,
+    /// Whether escaped surrogates which aren't part of a pair are
+    /// replaced with U+FFFD, rather than rejected.
+    pub replace_lone_surrogates: bool,
     bytes: &'a [u8],
     column: usize,
     line: usize,
+    offset: usize,
 }
 
 impl<'a> Bytes<'a> {
@@ -27,9 +40,14 @@ impl<'a> Bytes<'a> {
             bytes,
             column: 1,
             exts: Extensions::empty(),
+            json: false,
+            replace_lone_surrogates: false,
             line: 1,
+            offset: 0,
         };
 
+        b.skip_bom()?;
+        b.skip_shebang();
         b.skip_ws()?;
         // Loop over all extensions attributes
         loop {
@@ -47,20 +65,40 @@ impl<'a> Bytes<'a> {
     }
 
     pub fn advance(&mut self, bytes: usize) -> Result<()> {
-        for _ in 0..bytes {
-            self.advance_single()?;
+        let len = bytes.min(self.bytes.len());
+        let lines = scan::lines(&self.bytes[..len]);
+        self.skip(len, lines);
+
+        match len < bytes {
+            true => self.err(ParseError::Eof),
+            false => Ok(()),
         }
+    }
 
-        Ok(())
+    /// Moves past `len` bytes, whose lines have been counted already.
+    fn skip(&mut self, len: usize, lines: Lines) {
+        match lines.newlines {
+            0 => self.column += lines.chars,
+            newlines => {
+                self.line += newlines;
+                self.column = 1 + lines.chars;
+            }
+        }
+        self.offset += len;
+        self.bytes = &self.bytes[len..];
     }
 
     pub fn advance_single(&mut self) -> Result<()> {
-        if self.peek_or_eof()? == b'\n' {
-            self.line += 1;
-            self.column = 1;
-        } else {
-            self.column += 1;
+        match self.peek_or_eof()? {
+            b'\n' => {
+                self.line += 1;
+                self.column = 1;
+            }
+            // Columns count characters, not the bytes which continue them
+            byte if byte & 0xC0 == 0x80 => {}
+            _ => self.column += 1,
         }
+        self.offset += 1;
 
         self.bytes = &self.bytes[1..];
 
@@ -73,27 +111,32 @@ impl<'a> Bytes<'a> {
         } else if self.consume("false") {
             Ok(false)
         } else {
-            self.err(ParseError::ExpectedBoolean)
+            self.expected(ParseError::ExpectedBoolean)
         }
     }
 
-    pub fn bytes(&self) -> &[u8] {
-        &self.bytes
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
     }
 
     pub fn char(&mut self) -> Result<char> {
         use std::cmp::min;
 
         if !self.consume("'") {
-            return self.err(ParseError::ExpectedChar);
+            return self.expected(ParseError::ExpectedChar);
         }
 
         let c = self.peek_or_eof()?;
 
         let c = if c == b'\\' {
+            let escape = *self;
             let _ = self.advance(1);
 
-            self.parse_escape()?
+            self.parse_escape(escape)?
         } else {
             // Check where the end of the char (') is and try to
             // interpret the rest as UTF-8
@@ -102,15 +145,15 @@ impl<'a> Bytes<'a> {
             let pos: usize = self.bytes[..max]
                 .iter()
                 .position(|&x| x == b'\'')
-                .ok_or_else(|| self.error(ParseError::ExpectedChar))?;
+                .ok_or_else(|| self.expected_error(ParseError::ExpectedChar))?;
             let s = from_utf8(&self.bytes[0..pos]).map_err(|e| self.error(e.into()))?;
             let mut chars = s.chars();
 
             let first = chars
                 .next()
-                .ok_or_else(|| self.error(ParseError::ExpectedChar))?;
+                .ok_or_else(|| self.expected_error(ParseError::ExpectedChar))?;
             if chars.next().is_some() {
-                return self.err(ParseError::ExpectedChar);
+                return self.expected(ParseError::ExpectedChar);
             }
 
             let _ = self.advance(pos);
@@ -119,7 +162,7 @@ impl<'a> Bytes<'a> {
         };
 
         if !self.consume("'") {
-            return self.err(ParseError::ExpectedChar);
+            return self.expected(ParseError::ExpectedChar);
         }
 
         Ok(c)
@@ -144,9 +187,8 @@ impl<'a> Bytes<'a> {
     }
 
     fn check_ident_char(&self, index: usize) -> bool {
-        self.bytes
-            .get(index)
-            .map(|b| IDENT_CHAR.contains(b))
+        char_at(self.bytes, index)
+            .map(|(c, _)| c.is_xid_continue())
             .unwrap_or(false)
     }
 
@@ -173,19 +215,15 @@ impl<'a> Bytes<'a> {
     }
 
     fn consume_all(&mut self, all: &[&str]) -> Result<bool> {
-        all.iter()
-            .map(|elem| {
-                if self.consume(elem) {
-                    self.skip_ws()?;
-
-                    Ok(true)
-                } else {
-                    Ok(false)
-                }
-            })
-            .fold(Ok(true), |acc, x| {
-                acc.and_then(|val| x.map(|x| x && val))
-            })
+        all.iter().try_fold(true, |acc, elem| {
+            if self.consume(elem) {
+                self.skip_ws()?;
+
+                Ok(acc)
+            } else {
+                Ok(false)
+            }
+        })
     }
 
     pub fn eat_byte(&mut self) -> Result<u8> {
@@ -200,13 +238,26 @@ impl<'a> Bytes<'a> {
     }
 
     pub fn error(&self, kind: ParseError) -> Error {
-        Error::Parser(
-            kind,
-            Position {
-                line: self.line,
-                col: self.column,
-            },
-        )
+        Error::Parser(kind, self.position())
+    }
+
+    /// Fails with `kind`, which is told the token found instead
+    /// of what was expected.
+    pub fn expected<T>(&self, kind: Expected) -> Result<T> {
+        self.err(kind(found(self.bytes)))
+    }
+
+    /// Like `expected`, but returns the error.
+    pub fn expected_error(&self, kind: Expected) -> Error {
+        self.error(kind(found(self.bytes)))
+    }
+
+    pub fn position(&self) -> Position {
+        Position {
+            line: self.line,
+            col: self.column,
+            offset: self.offset,
+        }
     }
 
     pub fn expect_byte(&mut self, byte: u8, error: ParseError) -> Result<()> {
@@ -223,7 +274,7 @@ impl<'a> Bytes<'a> {
         }
 
         if !self.consume_all(&["#", "!", "[", "enable", "("])? {
-            return self.err(ParseError::ExpectedAttribute);
+            return self.expected(ParseError::ExpectedAttribute);
         }
 
         self.skip_ws()?;
@@ -243,7 +294,7 @@ impl<'a> Bytes<'a> {
 
             // If we have no comma but another item, return an error
             if !comma && self.check_ident_char(0) {
-                return self.err(ParseError::ExpectedComma);
+                return self.expected(ParseError::ExpectedComma);
             }
 
             // If there's no comma, assume the list ended.
@@ -258,35 +309,225 @@ impl<'a> Bytes<'a> {
 
         match self.consume_all(&[")", "]"])? {
             true => Ok(extensions),
-            false => Err(self.error(ParseError::ExpectedAttributeEnd)),
+            false => Err(self.expected_error(ParseError::ExpectedAttributeEnd)),
         }
     }
 
+    /// Parses a float with the `FromStr` of the standard library, which
+    /// is an Eisel-Lemire parser with a slow path for the rare inputs it
+    /// can't decide. It rounds correctly, so the shortest forms the
+    /// serializer writes read back as the same floats.
     pub fn float<T>(&mut self) -> Result<T>
     where
         T: FromStr,
     {
+        if let Some(&literal) = NON_FINITE_FLOATS.iter().find(|&&lit| self.check_ident(lit)) {
+            let res = FromStr::from_str(literal)
+                .map_err(|_| self.expected_error(ParseError::ExpectedFloat));
+            let _ = self.advance(literal.len());
+
+            return res;
+        }
+
         let num_bytes = self.next_bytes_contained_in(FLOAT_CHARS);
 
         let s = unsafe { from_utf8_unchecked(&self.bytes[0..num_bytes]) };
-        let res = FromStr::from_str(s).map_err(|_| self.error(ParseError::ExpectedFloat));
+        let res = without_underscores(s, u8::is_ascii_digit)
+            .and_then(|s| FromStr::from_str(&s).ok())
+            .ok_or_else(|| self.expected_error(ParseError::ExpectedFloat));
 
         let _ = self.advance(num_bytes);
 
         res
     }
 
+    /// Checks for a `(` which starts a tuple, rather than
+    /// struct fields like `(x: 1)`.
+    pub fn check_tuple(&self) -> bool {
+        let mut bytes = *self;
+
+        if !bytes.consume("(") || bytes.skip_ws().is_err() || bytes.peek() == Some(b')') {
+            return false;
+        }
+
+        let key = match bytes.peek() {
+            Some(b'"') => bytes.string().is_ok(),
+            _ => bytes.try_identifier().is_some(),
+        };
+
+        !(key && bytes.skip_ws().is_ok() && bytes.consume(":"))
+    }
+
+    /// Checks for a decimal number without a fraction or an exponent,
+    /// like `-12`.
+    pub fn check_integer(&self) -> bool {
+        let num = &self.bytes[..self.next_bytes_contained_in(FLOAT_CHARS)];
+
+        num.iter().any(u8::is_ascii_digit) && !num.iter().any(|b| b".eE".contains(b))
+    }
+
+    /// Checks for `inf`, `-inf` or `NaN`.
+    pub fn check_non_finite_float(&mut self) -> bool {
+        NON_FINITE_FLOATS.iter().any(|lit| self.check_ident(lit))
+    }
+
+    /// Checks for a hex block like `x"00 ff"`.
+    pub fn check_hex_bytes(&self) -> bool {
+        self.test_for("x\"")
+    }
+
+    /// Parses a hex block like `x"00 ff"`, ignoring any whitespace
+    /// between the bytes. Comments aren't allowed inside of it.
+    pub fn hex_bytes(&mut self) -> Result<Vec<u8>> {
+        if !self.consume("x\"") {
+            return self.expected(ParseError::ExpectedHexBytes);
+        }
+
+        let mut bytes = Vec::new();
+        loop {
+            let (len, lines) = scan::whitespace(self.bytes);
+            self.skip(len, lines);
+
+            if self.consume("\"") {
+                break Ok(bytes);
+            }
+
+            let high = self.eat_byte()?;
+            let low = self.eat_byte()?;
+            match (self.decode_hex(high), self.decode_hex(low)) {
+                (Ok(high), Ok(low)) => bytes.push(high << 4 | low),
+                _ => break self.expected(ParseError::ExpectedHexBytes),
+            }
+        }
+    }
+
+    /// Parses a string of base64 like `"R0lGODlh"` as bytes,
+    /// see `Deserializer::set_base64_bytes`.
+    pub fn base64_bytes(&mut self) -> Result<Vec<u8>> {
+        let start = *self;
+        if !self.consume("\"") {
+            return self.err(ParseError::ExpectedBase64);
+        }
+
+        let len = self.bytes
+            .iter()
+            .position(|&b| b == b'"')
+            .ok_or(self.error(ParseError::ExpectedStringEnd))?;
+        let bytes = base64::decode(&self.bytes[..len])
+            .ok_or_else(|| start.error(ParseError::ExpectedBase64))?;
+        let _ = self.advance(len + 1);
+
+        Ok(bytes)
+    }
+
+    /// Checks for a byte string like `b"GIF89a"`.
+    pub fn check_byte_string(&self) -> bool {
+        self.test_for("b\"")
+    }
+
+    /// Parses a byte string, which is borrowed from the input
+    /// unless it contains escapes.
+    pub fn byte_string(&mut self) -> Result<ParsedBytes<'a>> {
+        if !self.consume("b\"") {
+            return self.expected(ParseError::ExpectedByteString);
+        }
+
+        let (i, lines) = scan::find2(b'\\', b'"', self.bytes);
+        let i = i.ok_or_else(|| self.error(ParseError::ExpectedStringEnd))?;
+
+        if self.bytes[i] == b'"' {
+            let bytes = &self.bytes[..i];
+            self.skip(i, lines);
+            let _ = self.advance_single();
+
+            return Ok(ParsedBytes::Slice(bytes));
+        }
+
+        let mut bytes = self.bytes[..i].to_vec();
+        self.skip(i, lines);
+
+        loop {
+            let byte = self.eat_byte()
+                .map_err(|_| self.error(ParseError::ExpectedStringEnd))?;
+
+            match byte {
+                b'"' => break Ok(ParsedBytes::Allocated(bytes)),
+                b'\\' if self.skip_line_continuation() => {}
+                b'\\' => {
+                    let byte = self.parse_byte_escape()?;
+                    bytes.push(byte);
+                }
+                byte => bytes.push(byte),
+            }
+        }
+    }
+
+    /// Parses an identifier, or a raw one like `r#1st`,
+    /// whose name is returned without the `r#`.
     pub fn identifier(&mut self) -> Result<&'a [u8]> {
-        if IDENT_FIRST.contains(&self.peek_or_eof()?) {
-            let bytes = self.next_bytes_contained_in(IDENT_CHAR);
+        match self.try_identifier() {
+            Some(ident) => Ok(ident),
+            None => {
+                self.peek_or_eof()?;
 
-            let ident = &self.bytes[..bytes];
-            let _ = self.advance(bytes);
+                self.expected(ParseError::ExpectedIdentifier)
+            }
+        }
+    }
 
-            Ok(ident)
-        } else {
-            self.err(ParseError::ExpectedIdentifier)
+    /// Like `identifier`, but returns `None` rather than building
+    /// an error, for checking whether an identifier comes next.
+    pub fn try_identifier(&mut self) -> Option<&'a [u8]> {
+        let raw = self.raw_identifier_len();
+        if raw > 0 {
+            let ident = &self.bytes[2..2 + raw];
+            let _ = self.advance(2 + raw);
+
+            return Some(ident);
+        }
+
+        match identifier_len(self.bytes, false) {
+            0 => None,
+            bytes => {
+                let ident = &self.bytes[..bytes];
+                let _ = self.advance(bytes);
+
+                Some(ident)
+            }
+        }
+    }
+
+    /// Like `identifier`, but `-` is allowed after the first character,
+    /// for names like `max-width`.
+    pub fn dashed_identifier(&mut self) -> Result<&'a [u8]> {
+        if self.raw_identifier_len() > 0 {
+            return self.identifier();
+        }
+
+        self.peek_or_eof()?;
+
+        match identifier_len(self.bytes, true) {
+            0 => self.expected(ParseError::ExpectedIdentifier),
+            bytes => {
+                let ident = &self.bytes[..bytes];
+                let _ = self.advance(bytes);
+
+                Ok(ident)
+            }
+        }
+    }
+
+    /// The length of the name of a raw identifier like `r#1st`
+    /// at the start, or 0 if there is none.
+    fn raw_identifier_len(&self) -> usize {
+        if !self.test_for("r#") {
+            return 0;
         }
+
+        self.bytes[2..]
+            .iter()
+            .take_while(|b| IDENT_RAW_CHAR.contains(b))
+            .count()
     }
 
     pub fn next_bytes_contained_in(&self, allowed: &[u8]) -> usize {
@@ -297,11 +538,10 @@ impl<'a> Bytes<'a> {
     }
 
     pub fn skip_ws(&mut self) -> Result<()> {
-        while self.peek()
-            .map(|c| WHITE_SPACE.contains(&c))
-            .unwrap_or(false)
-        {
-            let _ = self.advance_single();
+        // Most values are followed by a comma or bracket right away
+        if self.peek().is_some_and(|b| WHITE_SPACE.contains(&b)) {
+            let (len, lines) = scan::whitespace(self.bytes);
+            self.skip(len, lines);
         }
 
         if self.skip_comment()? {
@@ -312,84 +552,211 @@ impl<'a> Bytes<'a> {
     }
 
     pub fn peek(&self) -> Option<u8> {
-        self.bytes.get(0).map(|b| *b)
+        self.bytes.first().copied()
     }
 
     pub fn peek_or_eof(&self) -> Result<u8> {
         self.bytes
-            .get(0)
-            .map(|b| *b)
-            .ok_or(self.error(ParseError::Eof))
+            .first()
+            .copied()
+            .ok_or_else(|| self.error(ParseError::Eof))
     }
 
-    pub fn signed_integer<T>(&mut self) -> Result<T>
-    where
-        T: Neg<Output = T> + Num,
-    {
-        match self.peek_or_eof()? {
-            b'+' => {
-                let _ = self.advance_single();
+    /// Skips a whole value without parsing or allocating, stopping in
+    /// front of the `,`, `:` or closing bracket which follows it.
+    ///
+    /// Brackets have to be balanced and closed by the same kind, but
+    /// the syntax of what's inside them isn't checked.
+    pub fn skip_value(&mut self) -> Result<()> {
+        self.value_text().map(|_| ())
+    }
+
+    /// Like `skip_value`, but returns the text of the value,
+    /// without the whitespace and comments after it.
+    pub fn value_text(&mut self) -> Result<&'a [u8]> {
+        /// What may follow at the outermost level.
+        #[derive(PartialEq)]
+        enum Next {
+            /// A value, which isn't optional.
+            Value,
+            /// The contents of a name or a number, like `Some(1)`.
+            Contents,
+            /// Nothing, as the value is complete.
+            End,
+        }
 
-                self.unsigned_integer()
+        let start = *self;
+        let mut len = 0;
+        // The closing brackets of the open ones, innermost last
+        let mut closers = Vec::new();
+        let mut next = Next::Value;
+
+        loop {
+            self.skip_ws()?;
+
+            let byte = match self.peek() {
+                Some(byte) => byte,
+                None if closers.is_empty() && next != Next::Value => {
+                    return Ok(&start.bytes[..len]);
+                }
+                None => return self.err(ParseError::Eof),
+            };
+
+            let offset = self.offset;
+
+            if closers.is_empty() {
+                match byte {
+                    b')' | b']' | b'}' | b',' | b':' => {
+                        if next == Next::Value {
+                            return self.err(ParseError::UnexpectedByte(byte as char));
+                        }
+
+                        return Ok(&start.bytes[..len]);
+                    }
+                    b'(' if next == Next::Contents => {}
+                    // Two values in a row, like `1 2`
+                    _ if next != Next::Value => return self.expected(ParseError::ExpectedComma),
+                    _ => {}
+                }
             }
-            b'-' => {
+
+            let mut after = Next::End;
+            match byte {
+                b'(' => closers.push(b')'),
+                b'[' => closers.push(b']'),
+                b'{' => closers.push(b'}'),
+                b')' | b']' | b'}' => match closers.pop() {
+                    Some(closer) if closer == byte => {}
+                    _ => return self.err(ParseError::UnexpectedByte(byte as char)),
+                },
+                b'"' => self.skip_string()?,
+                b'\'' => {
+                    self.char()?;
+                }
+                b'r' if self.check_raw_string() => {
+                    self.raw_string()?;
+                }
+                b'b' | b'x' if self.bytes.get(1) == Some(&b'"') => {
+                    let _ = self.advance_single();
+                    self.skip_string()?;
+                }
+                _ if closers.is_empty() => {
+                    let word = self.bytes
+                        .iter()
+                        .take_while(|b| !b" \t\n\r()[]{},:\"'/".contains(b))
+                        .count();
+                    let _ = self.advance(word.max(1));
+
+                    // An anchor, like `&0`, is followed by its value
+                    after = match byte {
+                        b'&' => Next::Value,
+                        _ => Next::Contents,
+                    };
+                }
+                _ => {}
+            }
+
+            if self.offset == offset {
                 let _ = self.advance_single();
+            }
+            if closers.is_empty() {
+                next = after;
+            }
+            len = self.offset - start.offset;
+        }
+    }
 
-                self.unsigned_integer::<T>().map(Neg::neg)
+    /// Skips a string, including its quotes, without decoding its escapes.
+    fn skip_string(&mut self) -> Result<()> {
+        if !self.consume("\"") {
+            return self.expected(ParseError::ExpectedString);
+        }
+
+        loop {
+            match self.eat_byte().map_err(|_| self.error(ParseError::ExpectedStringEnd))? {
+                b'"' => return Ok(()),
+                b'\\' => {
+                    self.eat_byte()?;
+                }
+                _ => {}
             }
-            _ => self.unsigned_integer(),
         }
     }
 
-    pub fn string(&mut self) -> Result<ParsedStr> {
-        use std::iter::repeat;
+    /// Checks for a raw string like `r"..."` or `r#"..."#`.
+    pub fn check_raw_string(&self) -> bool {
+        let hashes = self.raw_string_hashes();
+
+        self.bytes.first() == Some(&b'r') && self.bytes.get(hashes + 1) == Some(&b'"')
+    }
+
+    fn raw_string_hashes(&self) -> usize {
+        self.bytes
+            .iter()
+            .skip(1)
+            .take_while(|&&b| b == b'#')
+            .count()
+    }
+
+    /// Parses a raw string, which has no escapes and ends at the first `"`
+    /// followed by as many `#` as it started with.
+    fn raw_string(&mut self) -> Result<&'a str> {
+        let hashes = self.raw_string_hashes();
+        let _ = self.advance(hashes + 2);
+
+        let bytes = self.bytes;
+        let end = bytes
+            .windows(hashes + 1)
+            .position(|w| w[0] == b'"' && w[1..].iter().all(|&b| b == b'#'))
+            .ok_or_else(|| self.error(ParseError::ExpectedStringEnd))?;
+        let s = from_utf8(&bytes[..end]).map_err(|e| self.error(e.into()))?;
+
+        let _ = self.advance(end + hashes + 1);
+
+        Ok(s)
+    }
+
+    pub fn string(&mut self) -> Result<ParsedStr<'a>> {
+        if self.check_raw_string() {
+            return self.raw_string().map(ParsedStr::Slice);
+        }
 
         if !self.consume("\"") {
-            return self.err(ParseError::ExpectedString);
+            return self.expected(ParseError::ExpectedString);
         }
 
-        let (i, end_or_escape) = self.bytes
-            .iter()
-            .enumerate()
-            .find(|&(_, &b)| b == b'\\' || b == b'"')
-            .ok_or(self.error(ParseError::ExpectedStringEnd))?;
+        let (i, lines) = scan::find2(b'\\', b'"', self.bytes);
+        let i = i.ok_or_else(|| self.error(ParseError::ExpectedStringEnd))?;
 
-        if *end_or_escape == b'"' {
+        if self.bytes[i] == b'"' {
             let s = from_utf8(&self.bytes[..i]).map_err(|e| self.error(e.into()))?;
 
-            // Advance by the number of bytes of the string
-            // + 1 for the `"`.
-            let _ = self.advance(i + 1);
+            // Skip the string, then the `"`
+            self.skip(i, lines);
+            let _ = self.advance_single();
 
             Ok(ParsedStr::Slice(s))
         } else {
-            let mut i = i;
             let mut s: Vec<_> = self.bytes[..i].to_vec();
+            self.skip(i, lines);
 
             loop {
-                let _ = self.advance(i + 1);
-                let character = self.parse_escape()?;
-                match character.len_utf8() {
-                    1 => s.push(character as u8),
-                    len => {
-                        let start = s.len();
-                        s.extend(repeat(0).take(len));
-                        character.encode_utf8(&mut s[start..]);
-                    }
-                }
+                let escape = *self;
+                let _ = self.advance_single();
 
-                let (new_i, end_or_escape) = self.bytes
-                    .iter()
-                    .enumerate()
-                    .find(|&(_, &b)| b == b'\\' || b == b'"')
-                    .ok_or(ParseError::Eof)
-                    .map_err(|e| self.error(e))?;
+                if !self.skip_line_continuation() {
+                    let character = self.parse_escape(escape)?;
+                    let mut buf = [0; 4];
+                    s.extend_from_slice(character.encode_utf8(&mut buf).as_bytes());
+                }
 
-                i = new_i;
+                let (i, lines) = scan::find2(b'\\', b'"', self.bytes);
+                let i = i.ok_or_else(|| self.error(ParseError::ExpectedStringEnd))?;
                 s.extend_from_slice(&self.bytes[..i]);
+                self.skip(i, lines);
 
-                if *end_or_escape == b'"' {
-                    let _ = self.advance(i + 1);
+                if self.peek() == Some(b'"') {
+                    let _ = self.advance_single();
 
                     let s = String::from_utf8(s).map_err(|e| self.error(e.into()))?;
                     break Ok(ParsedStr::Allocated(s));
@@ -404,8 +771,13 @@ impl<'a> Bytes<'a> {
             .all(|(i, b)| self.bytes.get(i).map(|t| *t == b).unwrap_or(false))
     }
 
-    pub fn unsigned_integer<T: Num>(&mut self) -> Result<T> {
-        let base = if self.peek() == Some(b'0') {
+    /// Checks for an integer prefixed with `0x`, `0b` or `0o`.
+    pub fn check_based_integer(&self) -> bool {
+        self.integer_base() != 10
+    }
+
+    fn integer_base(&self) -> u32 {
+        if self.peek() == Some(b'0') {
             match self.bytes.get(1).cloned() {
                 Some(b'x') => 16,
                 Some(b'b') => 2,
@@ -414,24 +786,65 @@ impl<'a> Bytes<'a> {
             }
         } else {
             10
+        }
+    }
+
+    /// Reads an integer, which fails with `ParseError::IntegerOutOfRange`
+    /// if it doesn't fit into `T`, like `300` or `-1` for a `u8`.
+    pub fn integer<T: Num>(&mut self) -> Result<T> {
+        let start = *self;
+        let negative = match self.peek_or_eof()? {
+            b'+' => {
+                let _ = self.advance_single();
+
+                false
+            }
+            b'-' => {
+                let _ = self.advance_single();
+
+                true
+            }
+            _ => false,
         };
+        let base = self.integer_base();
 
         if base != 10 {
             // If we have `0x45A` for example,
             // cut it to `45A`.
             let _ = self.advance(2);
+
+            // Like in Rust, `0x_FF` separates the prefix from the digits
+            let underscores = self.bytes.iter().take_while(|&&b| b == b'_').count();
+            let _ = self.advance(underscores);
         }
 
         let num_bytes = self.next_bytes_contained_in(DIGITS);
 
         if num_bytes == 0 {
-            return self.err(ParseError::ExpectedInteger);
+            return self.expected(ParseError::ExpectedInteger);
         }
 
-        let res = Num::from_str(
-            unsafe { from_utf8_unchecked(&self.bytes[0..num_bytes]) },
-            base,
-        ).map_err(|_| self.error(ParseError::ExpectedInteger));
+        let digits = unsafe { from_utf8_unchecked(&self.bytes[0..num_bytes]) };
+        let parsed = match without_underscores(digits, u8::is_ascii_hexdigit) {
+            Some(ref digits) if !negative => T::from_str(digits, base),
+            // Parses the sign together with the digits,
+            // so the minimum value doesn't overflow
+            Some(ref digits) if T::SIGNED => T::from_str(&format!("-{}", digits), base),
+            // `-0` is the only negative integer an unsigned type has
+            Some(ref digits) if digits.bytes().all(|b| b == b'0') => T::from_str(digits, base),
+            Some(_) => Err(IntErrorKind::NegOverflow),
+            None => Err(IntErrorKind::InvalidDigit),
+        };
+        let res = match parsed {
+            Ok(n) => Ok(n),
+            Err(IntErrorKind::PosOverflow) | Err(IntErrorKind::NegOverflow) => {
+                let len = start.bytes.len() - self.bytes.len() + num_bytes;
+                let literal = String::from_utf8_lossy(&start.bytes[..len]);
+
+                Err(start.error(T::out_of_range(&literal)))
+            }
+            Err(_) => self.expected(ParseError::ExpectedInteger),
+        };
 
         let _ = self.advance(num_bytes);
 
@@ -441,7 +854,7 @@ impl<'a> Bytes<'a> {
     fn decode_ascii_escape(&mut self) -> Result<u8> {
         let mut n = 0;
         for _ in 0..2 {
-            n = n << 4;
+            n <<= 4;
             let byte = self.eat_byte()?;
             let decoded = self.decode_hex(byte)?;
             n |= decoded;
@@ -452,22 +865,48 @@ impl<'a> Bytes<'a> {
 
     fn decode_hex(&self, c: u8) -> Result<u8> {
         match c {
-            c @ b'0'...b'9' => Ok(c - b'0'),
-            c @ b'a'...b'f' => Ok(10 + c - b'a'),
-            c @ b'A'...b'F' => Ok(10 + c - b'A'),
+            c @ b'0'..=b'9' => Ok(c - b'0'),
+            c @ b'a'..=b'f' => Ok(10 + c - b'a'),
+            c @ b'A'..=b'F' => Ok(10 + c - b'A'),
             _ => self.err(ParseError::InvalidEscape("Non-hex digit found")),
         }
     }
 
-    fn parse_escape(&mut self) -> Result<char> {
+    /// Skips the line break after a `\` at the end of a line, and the
+    /// whitespace the next line starts with, like Rust does in strings.
+    fn skip_line_continuation(&mut self) -> bool {
+        if !self.consume("\n") && !self.consume("\r\n") {
+            return false;
+        }
+
+        while self.peek()
+            .map(|c| WHITE_SPACE.contains(&c))
+            .unwrap_or(false)
+        {
+            let _ = self.advance_single();
+        }
+
+        true
+    }
+
+    /// Parses the escape after the `\` at `escape`.
+    fn parse_escape(&mut self, escape: Bytes<'a>) -> Result<char> {
         let c = match self.eat_byte()? {
             b'\'' => '\'',
             b'"' => '"',
             b'\\' => '\\',
+            b'0' => '\0',
             b'n' => '\n',
             b'r' => '\r',
             b't' => '\t',
-            b'x' => self.decode_ascii_escape()? as char,
+            b'x' => match self.decode_ascii_escape()? {
+                byte @ 0x00..=0x7F => byte as char,
+                _ => return self.err(ParseError::InvalidEscape("Not an ASCII character")),
+            },
+            b'/' if self.json => '/',
+            b'b' if self.json => '\x08',
+            b'f' if self.json => '\x0c',
+            b'u' if self.json && self.peek() != Some(b'{') => self.json_unicode_escape(escape)?,
             b'u' => {
                 self.expect_byte(b'{', ParseError::InvalidEscape("Missing {"))?;
 
@@ -484,7 +923,7 @@ impl<'a> Bytes<'a> {
                     }
 
                     let byte = self.decode_hex(byte)?;
-                    bytes = bytes << 4;
+                    bytes <<= 4;
                     bytes |= byte as u32;
 
                     num_digits += 1;
@@ -497,9 +936,13 @@ impl<'a> Bytes<'a> {
                 }
 
                 self.expect_byte(b'}', ParseError::InvalidEscape("No } at the end"))?;
-                let character = char_from_u32(bytes)
-                    .ok_or_else(|| self.error(ParseError::InvalidEscape("Not a valid char")))?;
-                character
+
+                match char_from_u32(bytes) {
+                    Some(c) => c,
+                    // Chars can't be split into surrogates, unlike in JSON
+                    None if (0xD800..0xE000).contains(&bytes) => self.lone_surrogate(escape)?,
+                    None => return self.err(ParseError::InvalidEscape("Not a valid char")),
+                }
             }
             _ => {
                 return self.err(ParseError::InvalidEscape("Unknown escape character"));
@@ -509,28 +952,132 @@ impl<'a> Bytes<'a> {
         Ok(c)
     }
 
+    /// Parses the digits of a JSON escape like `\u00e9`, or of two for
+    /// the UTF-16 surrogates of a char, like `\ud83d\ude00`.
+    fn json_unicode_escape(&mut self, escape: Bytes<'a>) -> Result<char> {
+        let high = self.json_utf16_unit()?;
+
+        if (0xD800..0xDC00).contains(&high) {
+            // The next escape is only read if it completes the pair
+            let mut next = *self;
+
+            if next.consume("\\u") {
+                let low = next.json_utf16_unit()?;
+
+                if (0xDC00..0xE000).contains(&low) {
+                    *self = next;
+                    let c = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+
+                    return char_from_u32(c)
+                        .ok_or_else(|| self.error(ParseError::InvalidEscape("Not a valid char")));
+                }
+            }
+        }
+
+        match char_from_u32(high) {
+            Some(c) => Ok(c),
+            None => self.lone_surrogate(escape),
+        }
+    }
+
+    /// Replaces the surrogate escaped at `escape`, which isn't part of
+    /// a pair, with U+FFFD if that's allowed, and fails otherwise.
+    fn lone_surrogate(&self, escape: Bytes<'a>) -> Result<char> {
+        if self.replace_lone_surrogates {
+            Ok(char::REPLACEMENT_CHARACTER)
+        } else {
+            Err(escape.error(ParseError::InvalidEscape("Lone surrogate")))
+        }
+    }
+
+    /// Parses the four hex digits of a UTF-16 code unit.
+    fn json_utf16_unit(&mut self) -> Result<u32> {
+        let mut unit = 0;
+
+        for _ in 0..4 {
+            let digit = self.decode_hex(self.peek_or_eof()?)?;
+            self.advance_single()?;
+
+            unit = unit << 4 | digit as u32;
+        }
+
+        Ok(unit)
+    }
+
+    /// Like `parse_escape`, but `\xNN` can be any byte
+    /// and there are no unicode escapes.
+    fn parse_byte_escape(&mut self) -> Result<u8> {
+        let byte = match self.eat_byte()? {
+            b'\'' => b'\'',
+            b'"' => b'"',
+            b'\\' => b'\\',
+            b'0' => b'\0',
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'x' => self.decode_ascii_escape()?,
+            _ => {
+                return self.err(ParseError::InvalidEscape("Unknown escape character"));
+            }
+        };
+
+        Ok(byte)
+    }
+
+    /// Skips the byte order mark some editors start UTF-8 files with,
+    /// and rejects other encodings, which would only give confusing
+    /// syntax errors.
+    fn skip_bom(&mut self) -> Result<()> {
+        if let Some(encoding) = unsupported_encoding(self.bytes) {
+            return self.err(ParseError::UnsupportedEncoding(encoding));
+        }
+
+        if self.bytes.starts_with(BOM) {
+            // The mark isn't a character, so the column stays
+            self.bytes = &self.bytes[BOM.len()..];
+            self.offset += BOM.len();
+        }
+
+        Ok(())
+    }
+
+    /// Skips a first line like `#!/usr/bin/env launcher`, so that RON
+    /// files can be made executable. A `#!` followed by a `[` starts an
+    /// attribute like `#![enable(implicit_some)]` instead.
+    fn skip_shebang(&mut self) {
+        if !self.test_for("#!") {
+            return;
+        }
+
+        let mut rest = *self;
+        let _ = rest.advance(2);
+        if rest.skip_ws().is_ok() && rest.peek() == Some(b'[') {
+            return;
+        }
+
+        let bytes = self.bytes.iter().take_while(|&&b| b != b'\n').count();
+        let _ = self.advance(bytes);
+    }
+
     fn skip_comment(&mut self) -> Result<bool> {
         if self.consume("/") {
             match self.eat_byte()? {
                 b'/' => {
-                    let bytes = self.bytes.iter().take_while(|&&b| b != b'\n').count();
+                    let (bytes, lines) = scan::find(b'\n', self.bytes);
 
-                    let _ = self.advance(bytes);
+                    self.skip(bytes.unwrap_or(self.bytes.len()), lines);
                 }
                 b'*' => {
                     let mut level = 1;
 
                     while level > 0 {
-                        let bytes = self.bytes
-                            .iter()
-                            .take_while(|&&b| b != b'/' && b != b'*')
-                            .count();
+                        let (bytes, lines) = scan::find2(b'/', b'*', self.bytes);
 
-                        if self.bytes.len() == 0 {
+                        if self.bytes.is_empty() {
                             return self.err(ParseError::UnclosedBlockComment);
                         }
 
-                        let _ = self.advance(bytes);
+                        self.skip(bytes.unwrap_or(self.bytes.len()), lines);
 
                         // check whether / or * and take action
                         if self.consume("/*") {
@@ -553,33 +1100,30 @@ impl<'a> Bytes<'a> {
     }
 }
 
-bitflags! {
-    pub struct Extensions: usize {
-        const UNWRAP_NEWTYPES = 0x1;
-        const IMPLICIT_SOME = 0x2;
-    }
-}
+pub trait Num: Sized {
+    /// Whether the type has negative values.
+    const SIGNED: bool;
 
-impl Extensions {
-    /// Creates an extension flag from an ident.
-    pub fn from_ident(ident: &[u8]) -> Option<Extensions> {
-        match ident {
-            b"unwrap_newtypes" => Some(Extensions::UNWRAP_NEWTYPES),
-            b"implicit_some" => Some(Extensions::IMPLICIT_SOME),
-            _ => None,
-        }
-    }
-}
+    fn from_str(src: &str, radix: u32) -> StdResult<Self, IntErrorKind>;
 
-pub trait Num: Sized {
-    fn from_str(src: &str, radix: u32) -> StdResult<Self, ()>;
+    /// The error for `literal`, which is too large or small for the type.
+    fn out_of_range(literal: &str) -> ParseError;
 }
 
 macro_rules! impl_num {
     ($ty:ident) => {
         impl Num for $ty {
-            fn from_str(src: &str, radix: u32) -> StdResult<Self, ()> {
-                $ty::from_str_radix(src, radix).map_err(|_| ())
+            const SIGNED: bool = $ty::MIN != 0;
+
+            fn from_str(src: &str, radix: u32) -> StdResult<Self, IntErrorKind> {
+                $ty::from_str_radix(src, radix).map_err(|e| e.kind().clone())
+            }
+
+            fn out_of_range(literal: &str) -> ParseError {
+                ParseError::IntegerOutOfRange {
+                    literal: literal.to_owned(),
+                    ty: stringify!($ty),
+                }
             }
         }
     };
@@ -588,7 +1132,7 @@ macro_rules! impl_num {
     };
 }
 
-impl_num!(u8 u16 u32 u64 i8 i16 i32 i64);
+impl_num!(u8 u16 u32 u64 u128 i8 i16 i32 i64 i128);
 
 #[derive(Clone, Debug)]
 pub enum ParsedStr<'a> {
@@ -596,10 +1140,216 @@ pub enum ParsedStr<'a> {
     Slice(&'a str),
 }
 
+#[derive(Clone, Debug)]
+pub enum ParsedBytes<'a> {
+    Allocated(Vec<u8>),
+    Slice(&'a [u8]),
+}
+
+impl<'a> ParsedBytes<'a> {
+    pub fn into_owned(self) -> Vec<u8> {
+        match self {
+            ParsedBytes::Allocated(bytes) => bytes,
+            ParsedBytes::Slice(bytes) => bytes.to_vec(),
+        }
+    }
+}
+
+/// Where in the input an error occurred.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Position {
+    /// The column, counted in characters from 1.
     pub col: usize,
+    /// The line, counted from 1.
     pub line: usize,
+    /// The offset in bytes from the start of the input.
+    pub offset: usize,
+}
+
+/// The byte order mark of UTF-8.
+pub const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// The encoding of `bytes`, the start of the input, if it's told apart
+/// from UTF-8 by a byte order mark, or by the zero bytes UTF-16 has
+/// between the first two ASCII characters.
+///
+/// A single zero byte is left to the parser, which rejects it.
+pub fn unsupported_encoding(bytes: &[u8]) -> Option<&'static str> {
+    match *bytes {
+        [0xFF, 0xFE, 0, 0, ..] => Some("UTF-32LE"),
+        [0, 0, 0xFE, 0xFF, ..] => Some("UTF-32BE"),
+        [0xFF, 0xFE, ..] => Some("UTF-16LE"),
+        [0xFE, 0xFF, ..] => Some("UTF-16BE"),
+        [a, 0, b, 0, ..] if a != 0 && b != 0 => Some("UTF-16LE"),
+        [0, a, 0, b, ..] if a != 0 && b != 0 => Some("UTF-16BE"),
+        _ => None,
+    }
+}
+
+/// Moves `position` past `bytes`.
+pub fn advance_position(position: &mut Position, bytes: &[u8]) {
+    let lines = scan::lines(bytes);
+
+    match lines.newlines {
+        0 => position.col += lines.chars,
+        newlines => {
+            position.line += newlines;
+            position.col = 1 + lines.chars;
+        }
+    }
+    position.offset += bytes.len();
+}
+
+/// Moves the position of `error`, which is relative to a part of the
+/// input starting at `start`, to the whole input.
+pub fn shift_error(error: Error, start: Position) -> Error {
+    match error {
+        Error::Parser(kind, position) => {
+            let col = match position.line {
+                1 => position.col + start.col - 1,
+                _ => position.col,
+            };
+            let position = Position {
+                col,
+                line: position.line + start.line - 1,
+                offset: position.offset + start.offset,
+            };
+
+            Error::Parser(kind, position)
+        }
+        Error::Path { path, error } => Error::Path {
+            path,
+            error: Box::new(shift_error(*error, start)),
+        },
+        e => e,
+    }
+}
+
+/// Decodes the char which starts at `index` of `bytes`,
+/// and returns it with its length in bytes.
+fn char_at(bytes: &[u8], index: usize) -> Option<(char, usize)> {
+    let len = match *bytes.get(index)? {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => return None,
+    };
+    let s = from_utf8(bytes.get(index..index + len)?).ok()?;
+
+    s.chars().next().map(|c| (c, len))
+}
+
+/// The length in bytes of the identifier at the start of `bytes`,
+/// or 0 if there is none. Like in Rust, identifiers start with `_`
+/// or an XID_Start char, followed by XID_Continue chars.
+fn identifier_len(bytes: &[u8], dashed: bool) -> usize {
+    let mut len = 0;
+
+    while let Some((c, width)) = char_at(bytes, len) {
+        let valid = if len == 0 {
+            c == '_' || c.is_xid_start()
+        } else {
+            c.is_xid_continue() || (dashed && c == '-')
+        };
+        if !valid {
+            break;
+        }
+
+        len += width;
+    }
+
+    len
+}
+
+/// Whether `name` has to be written as a raw identifier like `r#1st`,
+/// because it isn't a valid identifier otherwise.
+pub fn needs_raw_identifier(name: &str) -> bool {
+    let plain = identifier_len(name.as_bytes(), false) == name.len();
+
+    !plain && !name.is_empty() && name.bytes().all(|b| IDENT_RAW_CHAR.contains(&b))
+}
+
+/// The length of the token at the start of `bytes`,
+/// as far as it can be told without parsing it.
+pub fn token_len(bytes: &[u8]) -> usize {
+    let word = |b: &u8| IDENT_CHAR.contains(b) || FLOAT_CHARS.contains(b);
+
+    match bytes.first() {
+        None => 0,
+        Some(&quote) if quote == b'"' || quote == b'\'' => {
+            let mut escaped = false;
+            let end = bytes[1..].iter().position(|&b| {
+                let end = b == quote && !escaped;
+                escaped = b == b'\\' && !escaped;
+
+                end
+            });
+
+            end.map(|i| i + 2).unwrap_or(bytes.len())
+        }
+        Some(b) if word(b) => bytes.iter().take_while(|b| word(b)).count(),
+        Some(&b) => {
+            let len = match b {
+                0xF0..=0xFF => 4,
+                0xE0..=0xEF => 3,
+                0xC0..=0xDF => 2,
+                _ => 1,
+            };
+
+            len.min(bytes.len())
+        }
+    }
+}
+
+/// A `ParseError` which is told the token found instead
+/// of what was expected.
+pub type Expected = fn(Option<String>) -> ParseError;
+
+/// The token at the start of `bytes`, shortened if it's long, for
+/// errors which tell what was found. `None` at the end of the input.
+pub fn found(bytes: &[u8]) -> Option<String> {
+    const MAX_CHARS: usize = 24;
+
+    // Enough bytes for one character more than is shown, so that
+    // a long string isn't scanned to its end
+    let bytes = &bytes[..bytes.len().min(4 * (MAX_CHARS + 1))];
+
+    match token_len(bytes) {
+        0 => None,
+        len => {
+            let token = String::from_utf8_lossy(&bytes[..len]);
+
+            Some(match token.char_indices().nth(MAX_CHARS) {
+                Some((i, _)) => format!("{}...", &token[..i]),
+                None => token.into_owned(),
+            })
+        }
+    }
+}
+
+/// Removes the `_` separating the digits of a number like `1_000_000`,
+/// or returns `None` if one isn't between two digits.
+fn without_underscores(s: &str, is_digit: fn(&u8) -> bool) -> Option<Cow<'_, str>> {
+    if !s.contains('_') {
+        return Some(Cow::Borrowed(s));
+    }
+
+    if s.starts_with('_') || s.ends_with('_') {
+        return None;
+    }
+
+    let parts: Vec<&str> = s.split('_').filter(|part| !part.is_empty()).collect();
+    let separated = parts.windows(2).all(|pair| {
+        pair[0].as_bytes().last().map(is_digit).unwrap_or(false)
+            && pair[1].as_bytes().first().map(is_digit).unwrap_or(false)
+    });
+
+    if separated {
+        Some(Cow::Owned(parts.concat()))
+    } else {
+        None
+    }
 }
 
 impl Display for Position {
@@ -617,4 +1367,34 @@ mod tests {
         let mut bytes = Bytes::new(b"10").unwrap();
         assert_eq!(bytes.decode_ascii_escape(), Ok(0x10));
     }
+
+    #[test]
+    fn hex_bytes() {
+        let mut bytes = Bytes::new(b"x\"00 7f\n  FF\", x\"\"").unwrap();
+        assert_eq!(bytes.hex_bytes(), Ok(vec![0x00, 0x7f, 0xff]));
+        assert!(bytes.consume(", "));
+        assert_eq!(bytes.hex_bytes(), Ok(vec![]));
+
+        let mut bytes = Bytes::new(b"x\"0 1\"").unwrap();
+        assert!(bytes.hex_bytes().is_err());
+
+        let mut bytes = Bytes::new(b"x\"0g\"").unwrap();
+        assert!(bytes.hex_bytes().is_err());
+    }
+
+    #[test]
+    fn underscores() {
+        assert_eq!(
+            without_underscores("1_000__000", u8::is_ascii_digit),
+            Some(Cow::Owned("1000000".to_owned()))
+        );
+        assert_eq!(
+            without_underscores("1.5", u8::is_ascii_digit),
+            Some(Cow::Borrowed("1.5"))
+        );
+        assert_eq!(without_underscores("_1", u8::is_ascii_digit), None);
+        assert_eq!(without_underscores("1_", u8::is_ascii_digit), None);
+        assert_eq!(without_underscores("1_.5", u8::is_ascii_digit), None);
+        assert_eq!(without_underscores("1e_5", u8::is_ascii_digit), None);
+    }
 }
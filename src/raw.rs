@@ -0,0 +1,125 @@
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, IgnoredAny, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+/// The name `RawValue` is serialized with, which the RON serializer
+/// and deserializer recognize.
+pub const RAW_VALUE: &str = "$ron::RawValue";
+
+/// A value kept as the RON it was written as, to be deserialized
+/// later, e.g. once it's known which plugin the value is for.
+///
+/// ```
+/// use ron::value::RawValue;
+///
+/// #[macro_use]
+/// extern crate serde;
+/// extern crate ron;
+///
+/// #[derive(Deserialize)]
+/// struct Plugin {
+///     name: String,
+///     config: RawValue,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Lights {
+///     brightness: u8,
+/// }
+///
+/// fn main() {
+///     let plugin: Plugin = ron::de::from_str(
+///         "(name: \"lights\", config: (brightness: 80) /* dim */)",
+///     ).unwrap();
+///
+///     assert_eq!(plugin.config.get_ron(), "(brightness: 80)");
+///
+///     let lights: Lights = plugin.config.into_rust().unwrap();
+///
+///     assert_eq!(lights.brightness, 80);
+/// }
+/// ```
+///
+/// The RON excludes the whitespace and comments around the value.
+/// Serializing a `RawValue` writes it verbatim, so it isn't laid out
+/// by a `PrettyConfig`. Other formats see a string of the RON.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RawValue {
+    ron: String,
+}
+
+impl RawValue {
+    /// Checks that `ron` is a single valid value, and wraps it.
+    pub fn from_ron(ron: &str) -> ::de::Result<Self> {
+        ::de::from_str::<IgnoredAny>(ron)?;
+
+        Ok(RawValue {
+            ron: ron.trim().to_owned(),
+        })
+    }
+
+    /// Serializes `value` into a `RawValue`.
+    pub fn from_rust<T: Serialize>(value: &T) -> ::ser::Result<Self> {
+        ::ser::to_string(value).map(|ron| RawValue { ron })
+    }
+
+    pub fn get_ron(&self) -> &str {
+        &self.ron
+    }
+
+    /// Deserializes the value, which may borrow from `self`.
+    pub fn into_rust<'de, T: Deserialize<'de>>(&'de self) -> ::de::Result<T> {
+        ::de::from_str(&self.ron)
+    }
+}
+
+impl fmt::Display for RawValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.ron)
+    }
+}
+
+impl Serialize for RawValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(RAW_VALUE, &self.ron)
+    }
+}
+
+impl<'de> Deserialize<'de> for RawValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(RAW_VALUE, RawValueVisitor)
+    }
+}
+
+struct RawValueVisitor;
+
+impl<'de> Visitor<'de> for RawValueVisitor {
+    type Value = RawValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a raw RON value")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(RawValue { ron: v.to_owned() })
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(RawValue { ron: v })
+    }
+
+    /// Reads the string other formats see.
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(|ron| RawValue { ron })
+    }
+}
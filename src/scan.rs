@@ -0,0 +1,300 @@
+//! Searches for bytes a word at a time, for the long stretches of
+//! strings, comments and whitespace which the parser skips over.
+//! The newlines and characters skipped over are counted in the same
+//! pass, so that the position doesn't need another one.
+//!
+//! With the `simd` feature, x86-64 searches 16 bytes at a time
+//! with SSE2 instead.
+
+const LO: u64 = 0x0101_0101_0101_0101;
+const HI: u64 = 0x8080_8080_8080_8080;
+const WORD: usize = 8;
+
+/// The newlines in some bytes, and the characters after the last one.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Lines {
+    /// How many newlines there are.
+    pub newlines: usize,
+    /// How many characters follow the last newline, or how many
+    /// there are if there's none.
+    ///
+    /// Bytes which continue a character aren't counted as one.
+    pub chars: usize,
+}
+
+impl Lines {
+    /// Adds a chunk of bytes, given as masks with a bit for each byte
+    /// which is a newline or starts a character. Later bytes have
+    /// higher bits.
+    fn add(&mut self, newlines: u64, starts: u64) {
+        if newlines == 0 {
+            self.chars += starts.count_ones() as usize;
+        } else {
+            self.newlines += newlines.count_ones() as usize;
+            // The bit of the last newline is the highest one set
+            let last = 63 - newlines.leading_zeros();
+            self.chars = starts.checked_shr(last + 1).unwrap_or(0).count_ones() as usize;
+        }
+    }
+
+    fn add_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            match byte {
+                b'\n' => {
+                    self.newlines += 1;
+                    self.chars = 0;
+                }
+                // Continuation bytes are 0b10xxxxxx
+                byte if byte & 0xC0 == 0x80 => {}
+                _ => self.chars += 1,
+            }
+        }
+    }
+}
+
+/// The index of the first `a` in `haystack`, and the lines of the
+/// bytes before it, or of all of them if there's none.
+pub fn find(a: u8, haystack: &[u8]) -> (Option<usize>, Lines) {
+    find2(a, a, haystack)
+}
+
+/// The index of the first `a` or `b` in `haystack`, and the lines of
+/// the bytes before it, or of all of them if there's none.
+pub fn find2(a: u8, b: u8, haystack: &[u8]) -> (Option<usize>, Lines) {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    {
+        sse2::find2(a, b, haystack)
+    }
+
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    {
+        swar_find2(a, b, haystack, Lines::default())
+    }
+}
+
+/// How long the whitespace `haystack` starts with is, and its lines.
+pub fn whitespace(haystack: &[u8]) -> (usize, Lines) {
+    let mut lines = Lines::default();
+    let mut chunks = haystack.chunks_exact(WORD);
+
+    for (i, chunk) in chunks.by_ref().enumerate() {
+        let word = read_word(chunk);
+        let newlines = exact_zero_bytes(word ^ (LO * b'\n' as u64));
+        let space = newlines
+            | exact_zero_bytes(word ^ (LO * b' ' as u64))
+            | exact_zero_bytes(word ^ (LO * b'\t' as u64))
+            | exact_zero_bytes(word ^ (LO * b'\r' as u64));
+
+        if space != HI {
+            let end = !space & HI;
+            let before = (end & end.wrapping_neg()) - 1;
+            lines.add(newlines & before, space & before);
+
+            return (i * WORD + end.trailing_zeros() as usize / 8, lines);
+        }
+        lines.add(newlines, space);
+    }
+
+    let rest = chunks.remainder();
+    let start = haystack.len() - rest.len();
+    let len = rest
+        .iter()
+        .take_while(|&&b| matches!(b, b' ' | b'\n' | b'\t' | b'\r'))
+        .count();
+    lines.add_bytes(&rest[..len]);
+
+    (start + len, lines)
+}
+
+/// The lines of `bytes`.
+pub fn lines(bytes: &[u8]) -> Lines {
+    let mut lines = Lines::default();
+    let mut chunks = bytes.chunks_exact(WORD);
+
+    for chunk in chunks.by_ref() {
+        let word = read_word(chunk);
+        lines.add(exact_zero_bytes(word ^ (LO * b'\n' as u64)), starts(word));
+    }
+    lines.add_bytes(chunks.remainder());
+
+    lines
+}
+
+fn read_word(chunk: &[u8]) -> u64 {
+    let mut word = [0; WORD];
+    word.copy_from_slice(chunk);
+
+    u64::from_le_bytes(word)
+}
+
+/// Sets the high bit of the bytes of `word` which start a character,
+/// rather than being 0b10xxxxxx.
+fn starts(word: u64) -> u64 {
+    (!word | (word << 1)) & HI
+}
+
+fn swar_find2(a: u8, b: u8, haystack: &[u8], mut lines: Lines) -> (Option<usize>, Lines) {
+    let (splat_a, splat_b) = (LO * a as u64, LO * b as u64);
+    let mut chunks = haystack.chunks_exact(WORD);
+
+    for (i, chunk) in chunks.by_ref().enumerate() {
+        let word = read_word(chunk);
+        let newlines = exact_zero_bytes(word ^ (LO * b'\n' as u64));
+        let found = zero_bytes(word ^ splat_a) | zero_bytes(word ^ splat_b);
+
+        if found != 0 {
+            // The bits of the bytes below the first one found
+            let before = (found & found.wrapping_neg()) - 1;
+            lines.add(newlines & before, starts(word) & before);
+
+            return (Some(i * WORD + found.trailing_zeros() as usize / 8), lines);
+        }
+        lines.add(newlines, starts(word));
+    }
+
+    let rest = chunks.remainder();
+    let start = haystack.len() - rest.len();
+
+    match rest.iter().position(|&byte| byte == a || byte == b) {
+        Some(i) => {
+            lines.add_bytes(&rest[..i]);
+            (Some(start + i), lines)
+        }
+        None => {
+            lines.add_bytes(rest);
+            (None, lines)
+        }
+    }
+}
+
+/// Sets the high bit of the bytes of `word` which are zero. Bytes above
+/// the lowest zero one may be set wrongly, so only the lowest set bit
+/// can be relied on.
+fn zero_bytes(word: u64) -> u64 {
+    word.wrapping_sub(LO) & !word & HI
+}
+
+/// Like `zero_bytes`, but sets the high bit of exactly the bytes
+/// of `word` which are zero, for counting them.
+fn exact_zero_bytes(word: u64) -> u64 {
+    !(((word & !HI) + !HI) | word | !HI)
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod sse2 {
+    use std::arch::x86_64::*;
+
+    use super::Lines;
+
+    const LANES: usize = 16;
+
+    pub fn find2(a: u8, b: u8, haystack: &[u8]) -> (Option<usize>, Lines) {
+        let mut lines = Lines::default();
+        let mut chunks = haystack.chunks_exact(LANES);
+
+        for (i, chunk) in chunks.by_ref().enumerate() {
+            // SSE2 is part of x86-64, and the load is unaligned, so
+            // reading the 16 bytes of the chunk is sound
+            let (found, newlines, continuations) = unsafe {
+                let bytes = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+                let found = _mm_or_si128(
+                    _mm_cmpeq_epi8(bytes, _mm_set1_epi8(a as i8)),
+                    _mm_cmpeq_epi8(bytes, _mm_set1_epi8(b as i8)),
+                );
+                let newlines = _mm_cmpeq_epi8(bytes, _mm_set1_epi8(b'\n' as i8));
+                let continuations = _mm_cmpeq_epi8(
+                    _mm_and_si128(bytes, _mm_set1_epi8(0xC0_u8 as i8)),
+                    _mm_set1_epi8(0x80_u8 as i8),
+                );
+
+                (
+                    _mm_movemask_epi8(found) as u64,
+                    _mm_movemask_epi8(newlines) as u64,
+                    _mm_movemask_epi8(continuations) as u64,
+                )
+            };
+            let starts = !continuations & 0xFFFF;
+
+            if found != 0 {
+                let before = (found & found.wrapping_neg()) - 1;
+                lines.add(newlines & before, starts & before);
+
+                return (Some(i * LANES + found.trailing_zeros() as usize), lines);
+            }
+            lines.add(newlines, starts);
+        }
+
+        let rest = chunks.remainder();
+        let start = haystack.len() - rest.len();
+
+        match super::swar_find2(a, b, rest, lines) {
+            (Some(i), lines) => (Some(start + i), lines),
+            (None, lines) => (None, lines),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts the lines of `bytes` a byte at a time.
+    fn naive_lines(bytes: &[u8]) -> Lines {
+        let mut lines = Lines::default();
+        lines.add_bytes(bytes);
+
+        lines
+    }
+
+    #[test]
+    fn test_find() {
+        let text = "a long string, with \\\"escapes\\\",\nlines and more than 16 bytes\u{e9}\"";
+        let text = text.as_bytes();
+
+        for start in 0..text.len() {
+            let haystack = &text[start..];
+            let naive = haystack.iter().position(|&b| b == b'"' || b == b'\\');
+            let before = naive_lines(&haystack[..naive.unwrap_or(haystack.len())]);
+
+            assert_eq!(find2(b'"', b'\\', haystack), (naive, before));
+            assert_eq!(swar_find2(b'"', b'\\', haystack, Lines::default()), (naive, before));
+            assert_eq!(find(b'g', haystack).0, haystack.iter().position(|&b| b == b'g'));
+        }
+
+        assert_eq!(find2(0x80, 0xFF, b"\x7f\x01\xff").0, Some(2));
+        assert_eq!(find(0, b"\x01\x01\x01\x01\x01\x01\x01\x01\x00").0, Some(8));
+        assert_eq!(find(b'x', b""), (None, Lines::default()));
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let text = b" \t\r\n  \n\n    \t  \n   x \n";
+
+        for start in 0..text.len() {
+            let haystack = &text[start..];
+            let naive = haystack.iter().take_while(|b| b" \t\r\n".contains(b)).count();
+
+            assert_eq!(whitespace(haystack), (naive, naive_lines(&haystack[..naive])));
+        }
+
+        assert_eq!(whitespace(b"        ").0, 8);
+        assert_eq!(whitespace(b"\x00\x80").0, 0);
+    }
+
+    #[test]
+    fn test_lines() {
+        let text = "caf\u{e9}\n\u{1f600} line\n\na longer line, with \u{e9} and \u{1f600}\n!";
+
+        for start in 0..text.len() {
+            let bytes = &text.as_bytes()[start..];
+            let newlines = bytes.iter().filter(|&&b| b == b'\n').count();
+            let last = bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+            let chars = bytes[last..].iter().filter(|&&b| b & 0xC0 != 0x80).count();
+
+            assert_eq!(lines(bytes), Lines { newlines, chars });
+        }
+
+        assert_eq!(lines("a\u{e9}\u{1f600}".as_bytes()), Lines { newlines: 0, chars: 3 });
+        assert_eq!(lines(b"12345678\n"), Lines { newlines: 1, chars: 0 });
+    }
+}
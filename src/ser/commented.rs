@@ -0,0 +1,426 @@
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{
+    Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+    SerializeTuple, SerializeTupleStruct, SerializeTupleVariant, Serializer,
+};
+
+use super::skip_default::{SKIP_DEFAULT, SKIP_DEFAULT_DEFAULT};
+use super::Error;
+
+/// The name `Commented` is serialized with, which the RON serializer
+/// recognizes.
+pub const COMMENTED: &str = "$ron::Commented";
+
+/// A value with a comment, which the pretty serializer writes as
+/// `// comment` lines above the struct field or sequence element
+/// holding it.
+///
+/// ```
+/// use ron::ser::{to_string_pretty, Commented, PrettyConfig};
+///
+/// #[macro_use]
+/// extern crate serde;
+/// extern crate ron;
+///
+/// #[derive(Serialize)]
+/// struct Window {
+///     width: Commented<u32>,
+///     height: u32,
+/// }
+///
+/// fn main() {
+///     let window = Window {
+///         width: Commented::new("In pixels", 800),
+///         height: 600,
+///     };
+///     let config = PrettyConfig {
+///         new_line: "\n".to_owned(),
+///         ..PrettyConfig::default()
+///     };
+///
+///     assert_eq!(
+///         to_string_pretty(&window, config).unwrap(),
+///         "(\n    // In pixels\n    width: 800,\n    height: 600,\n)"
+///     );
+/// }
+/// ```
+///
+/// Comments are left out wherever a line comment can't be written,
+/// e.g. in compact output. Deserializing a `Commented` reads just the
+/// value and leaves the comment empty. Other formats see a tuple struct
+/// of the comment and the value.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Commented<T> {
+    pub comment: String,
+    pub value: T,
+}
+
+impl<T> Commented<T> {
+    pub fn new<S: Into<String>>(comment: S, value: T) -> Self {
+        Commented {
+            comment: comment.into(),
+            value,
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for Commented<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple_struct(COMMENTED, 2)?;
+        tuple.serialize_field(&self.comment)?;
+        tuple.serialize_field(&self.value)?;
+        tuple.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Commented<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(|value| Commented::new("", value))
+    }
+}
+
+/// Returns the comment of `value` if it's a `Commented`, without
+/// serializing the value itself, so that the comment can be written
+/// before the struct field or sequence element holding it.
+pub fn comment_of<T>(value: &T) -> Result<Option<String>, Error>
+where
+    T: ?Sized + Serialize,
+{
+    value.serialize(CommentProbe { text: false })
+}
+
+/// Returns the string `value` is serialized as, if it is one,
+/// like the comment field of a `Commented`.
+pub fn comment_text<T>(value: &T) -> Result<Option<String>, Error>
+where
+    T: ?Sized + Serialize,
+{
+    value.serialize(CommentProbe { text: true })
+}
+
+/// Looks at the outermost value only, which is either a `Commented`
+/// or not; with `text`, it's the comment of one, which is returned.
+struct CommentProbe {
+    text: bool,
+}
+
+/// The fields of a value which `CommentProbe` looks at,
+/// only keeping the comment of a `Commented`.
+struct CommentFields {
+    commented: bool,
+    comment: Option<String>,
+}
+
+impl CommentFields {
+    fn skip() -> Self {
+        CommentFields {
+            commented: false,
+            comment: None,
+        }
+    }
+}
+
+impl Serializer for CommentProbe {
+    type Ok = Option<String>;
+    type Error = Error;
+    type SerializeSeq = CommentFields;
+    type SerializeTuple = CommentFields;
+    type SerializeTupleStruct = CommentFields;
+    type SerializeTupleVariant = CommentFields;
+    type SerializeMap = CommentFields;
+    type SerializeStruct = CommentFields;
+    type SerializeStructVariant = CommentFields;
+
+    fn serialize_bool(self, _: bool) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_i8(self, _: i8) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_i16(self, _: i16) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_i32(self, _: i32) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_i64(self, _: i64) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_i128(self, _: i128) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_u8(self, _: u8) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_u16(self, _: u16) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_u32(self, _: u32) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_u64(self, _: u64) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_u128(self, _: u128) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_f32(self, _: f32) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_char(self, _: char) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Error> {
+        match self.text {
+            true => Ok(Some(v.to_owned())),
+            false => Ok(None),
+        }
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_some<T>(self, _: &T) -> Result<Self::Ok, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(None)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+    ) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        // `SkipDefault` is written as just its value
+        match name {
+            SKIP_DEFAULT | SKIP_DEFAULT_DEFAULT => value.serialize(self),
+            _ => Ok(None),
+        }
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Self::Ok, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(None)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<CommentFields, Error> {
+        Ok(CommentFields::skip())
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<CommentFields, Error> {
+        Ok(CommentFields::skip())
+    }
+
+    fn serialize_tuple_struct(self, name: &'static str, _: usize) -> Result<CommentFields, Error> {
+        Ok(CommentFields {
+            commented: name == COMMENTED,
+            comment: None,
+        })
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<CommentFields, Error> {
+        Ok(CommentFields::skip())
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<CommentFields, Error> {
+        Ok(CommentFields::skip())
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<CommentFields, Error> {
+        Ok(CommentFields::skip())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<CommentFields, Error> {
+        Ok(CommentFields::skip())
+    }
+}
+
+impl SerializeTupleStruct for CommentFields {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        // The comment is the first field
+        if self.commented {
+            self.commented = false;
+            self.comment = comment_text(value)?;
+        }
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<String>, Error> {
+        Ok(self.comment)
+    }
+}
+
+impl SerializeSeq for CommentFields {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, _: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+}
+
+impl SerializeTuple for CommentFields {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, _: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+}
+
+impl SerializeTupleVariant for CommentFields {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, _: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+}
+
+impl SerializeMap for CommentFields {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, _: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, _: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+}
+
+impl SerializeStruct for CommentFields {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, _: &'static str, _: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+}
+
+impl SerializeStructVariant for CommentFields {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, _: &'static str, _: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+}
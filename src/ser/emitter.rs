@@ -0,0 +1,327 @@
+use std::io::{self, Write};
+
+use serde::ser::Serialize;
+
+use super::{Error, Formatter, Layout, PrettyConfig, Result, Serializer, Token};
+
+/// Writes RON token by token, without going through `Serialize`.
+///
+/// This is meant for code generators and other tooling which know the
+/// structure they want to write; values are laid out according to the
+/// `PrettyConfig`, like the serializer would lay them out.
+///
+/// ```
+/// use ron::ser::{Emitter, PrettyConfig};
+///
+/// let config = PrettyConfig {
+///     new_line: "\n".to_owned(),
+///     ..PrettyConfig::default()
+/// };
+/// let mut emitter = Emitter::new(Vec::new(), Some(config));
+///
+/// emitter.begin_struct(Some("Window")).unwrap();
+/// emitter.field("title").unwrap();
+/// emitter.value("Main").unwrap();
+/// emitter.field("size").unwrap();
+/// emitter.begin_tuple(None).unwrap();
+/// emitter.value(&800).unwrap();
+/// emitter.value(&600).unwrap();
+/// emitter.end_tuple().unwrap();
+/// emitter.field("mode").unwrap();
+/// emitter.raw("Fullscreen").unwrap();
+/// emitter.end_struct().unwrap();
+///
+/// let output = emitter.finish().unwrap();
+///
+/// assert_eq!(
+///     String::from_utf8(output).unwrap(),
+///     "Window(\n    title: \"Main\",\n    size: (800, 600),\n    mode: Fullscreen,\n)"
+/// );
+/// ```
+///
+/// Using the emitter the wrong way, e.g. by ending a struct which
+/// hasn't been started or by writing a struct field without a name,
+/// returns an error.
+pub struct Emitter<W> {
+    ser: Serializer<W>,
+    frames: Vec<Frame>,
+}
+
+struct Frame {
+    kind: Kind,
+    layout: Layout,
+    first: bool,
+    /// Whether the elements are surrounded by spaces.
+    spaced: bool,
+    /// Whether a field name or map key has been written,
+    /// so the value comes next.
+    value_next: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Struct,
+    Tuple { separate: bool },
+    Seq,
+    Map,
+}
+
+impl<W: io::Write> Emitter<W> {
+    /// Creates a new `Emitter` which writes into `writer`,
+    /// in a pretty way if `config` is given.
+    pub fn new(writer: W, config: Option<PrettyConfig>) -> Self {
+        Emitter {
+            ser: Serializer::with_writer(writer, config),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Writes tokens through `formatter`, like `Serializer::set_formatter`.
+    pub fn set_formatter<F: Formatter + 'static>(&mut self, formatter: F) {
+        self.ser.set_formatter(formatter);
+    }
+
+    /// Starts a struct, prefixed with `name` if given,
+    /// which is also how struct-like enum variants are written.
+    pub fn begin_struct(&mut self, name: Option<&str>) -> Result<()> {
+        self.begin_value()?;
+        if let Some(name) = name {
+            self.ser.write_token(Token::Identifier, name)?;
+        }
+        self.ser.output.write_all(b"(")?;
+
+        self.begin(Kind::Struct)
+    }
+
+    /// Writes the name of the next field of the current struct.
+    pub fn field(&mut self, name: &str) -> Result<()> {
+        match self.frames.last() {
+            Some(frame) if frame.kind == Kind::Struct && !frame.value_next => {}
+            _ => return Err(misuse("a field outside of a struct")),
+        }
+
+        self.start_element()?;
+        self.ser.write_token(Token::Field, name)?;
+        self.key_separator()
+    }
+
+    pub fn end_struct(&mut self) -> Result<()> {
+        self.end(Kind::Struct, b")")
+    }
+
+    /// Starts a tuple, prefixed with `name` if given,
+    /// which is also how tuple structs and variants are written.
+    pub fn begin_tuple(&mut self, name: Option<&str>) -> Result<()> {
+        self.begin_value()?;
+        if let Some(name) = name {
+            self.ser.write_token(Token::Identifier, name)?;
+        }
+        self.ser.output.write_all(b"(")?;
+
+        let separate = self.ser.separate_tuple_members();
+        self.begin(Kind::Tuple { separate })
+    }
+
+    pub fn end_tuple(&mut self) -> Result<()> {
+        let separate = self.ser.separate_tuple_members();
+
+        self.end(Kind::Tuple { separate }, b")")
+    }
+
+    pub fn begin_seq(&mut self) -> Result<()> {
+        self.begin_value()?;
+        self.ser.output.write_all(b"[")?;
+
+        self.begin(Kind::Seq)
+    }
+
+    pub fn end_seq(&mut self) -> Result<()> {
+        self.end(Kind::Seq, b"]")
+    }
+
+    /// Starts a map, whose keys and values are written alternately.
+    pub fn begin_map(&mut self) -> Result<()> {
+        self.begin_value()?;
+        self.ser.output.write_all(b"{")?;
+
+        self.begin(Kind::Map)
+    }
+
+    pub fn end_map(&mut self) -> Result<()> {
+        self.end(Kind::Map, b"}")
+    }
+
+    /// Writes a complete value using the serializer.
+    pub fn value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.begin_value()?;
+        self.ser.serialize_value(value)?;
+
+        self.end_value()
+    }
+
+    /// Writes `ron` verbatim as the next value,
+    /// e.g. an enum variant like `Fullscreen`.
+    ///
+    /// It isn't checked whether `ron` is valid.
+    pub fn raw(&mut self, ron: &str) -> Result<()> {
+        self.begin_value()?;
+        self.ser.output.write_all(ron.as_bytes())?;
+
+        self.end_value()
+    }
+
+    /// Returns the writer, once all values have been ended.
+    pub fn finish(self) -> Result<W> {
+        if !self.frames.is_empty() {
+            return Err(misuse("unfinished values"));
+        }
+
+        Ok(self.ser.into_inner())
+    }
+
+    fn begin(&mut self, kind: Kind) -> Result<()> {
+        let layout = match kind {
+            Kind::Tuple { separate: false } => match self.ser.layout() {
+                Layout::Multiline => Layout::Inline,
+                layout => layout,
+            },
+            _ => {
+                self.ser.start_indent()?;
+
+                self.ser.layout()
+            }
+        };
+
+        let spaced = layout == Layout::Compact
+            && kind != Kind::Seq
+            && kind != Kind::Map
+            && self.ser.compact_spacing().inside_parens;
+
+        self.frames.push(Frame {
+            kind,
+            layout,
+            first: true,
+            spaced,
+            value_next: false,
+        });
+
+        Ok(())
+    }
+
+    fn end(&mut self, kind: Kind, close: &[u8]) -> Result<()> {
+        let frame = match self.frames.pop() {
+            Some(frame) if frame.kind == kind && !frame.value_next => frame,
+            Some(frame) => {
+                self.frames.push(frame);
+
+                return Err(misuse("an end which doesn't match its beginning"));
+            }
+            None => return Err(misuse("an end without a beginning")),
+        };
+
+        if frame.layout == Layout::Multiline && !frame.first {
+            if self.ser.trailing_comma() {
+                self.ser.output.write_all(b",")?;
+            }
+
+            self.ser.new_line()?;
+        }
+
+        if frame.spaced && !frame.first {
+            self.ser.output.write_all(b" ")?;
+        }
+        if kind != (Kind::Tuple { separate: false }) {
+            self.ser.end_indent()?;
+        }
+        self.ser.output.write_all(close)?;
+
+        self.end_value()
+    }
+
+    fn begin_value(&mut self) -> Result<()> {
+        match self.frames.last() {
+            Some(frame) if frame.kind == Kind::Struct && !frame.value_next => {
+                Err(misuse("a struct field without a name"))
+            }
+            Some(frame) if !frame.value_next => self.start_element(),
+            _ => Ok(()),
+        }
+    }
+
+    fn end_value(&mut self) -> Result<()> {
+        let (layout, key) = match self.frames.last_mut() {
+            Some(frame) => {
+                let key = frame.kind == Kind::Map && !frame.value_next;
+                frame.value_next = false;
+
+                (frame.layout, key)
+            }
+            None => return Ok(()),
+        };
+
+        if key {
+            self.key_separator()
+        } else {
+            if layout == Layout::Compact {
+                self.ser.output.write_all(b",")?;
+            }
+
+            Ok(())
+        }
+    }
+
+    fn start_element(&mut self) -> Result<()> {
+        let (layout, first, spaced) = match self.frames.last_mut() {
+            Some(frame) => (
+                frame.layout,
+                ::std::mem::replace(&mut frame.first, false),
+                frame.spaced,
+            ),
+            None => return Ok(()),
+        };
+
+        if !first {
+            match layout {
+                Layout::Multiline => {
+                    self.ser.output.write_all(b",")?;
+                    self.ser.new_line()?;
+                }
+                Layout::Inline => self.ser.output.write_all(b", ")?,
+                Layout::Compact => {
+                    if self.ser.compact_spacing().after_comma {
+                        self.ser.output.write_all(b" ")?;
+                    }
+                }
+            }
+        } else if spaced {
+            self.ser.output.write_all(b" ")?;
+        }
+
+        if layout == Layout::Multiline {
+            self.ser.indent()?;
+        }
+
+        Ok(())
+    }
+
+    fn key_separator(&mut self) -> Result<()> {
+        let after_colon = self.ser.compact_spacing().after_colon;
+
+        if let Some(frame) = self.frames.last_mut() {
+            self.ser.output.write_all(match frame.layout {
+                Layout::Compact if !after_colon => b":",
+                _ => b": ",
+            })?;
+            frame.value_next = true;
+        }
+
+        Ok(())
+    }
+}
+
+fn misuse(what: &str) -> Error {
+    Error::Message(format!("Emitter got {}", what))
+}
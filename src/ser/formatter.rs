@@ -0,0 +1,80 @@
+use std::io;
+
+/// The kinds of tokens a `Formatter` is given.
+///
+/// Punctuation and whitespace, like brackets, commas and indentation,
+/// are written as they are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Token {
+    /// The name of a struct or an enum variant, like `Some` or `None`.
+    Identifier,
+    /// The name of a struct field.
+    Field,
+    /// A quoted string, including its quotes and escape sequences.
+    Str,
+    /// A quoted character.
+    Char,
+    /// An integer or a float.
+    Number,
+    /// `true` or `false`.
+    Bool,
+    /// A comment, including its `//` or `/*` and `*/`.
+    Comment,
+}
+
+/// Decides how the tokens the serializer produces are written,
+/// while `PrettyConfig` decides where they go.
+///
+/// The default implementation writes every token unchanged.
+/// A formatter can wrap them in markup instead, e.g. to highlight
+/// the output as HTML:
+///
+/// ```
+/// use std::io::{self, Write};
+///
+/// use ron::ser::{Formatter, Serializer, Token};
+///
+/// #[macro_use]
+/// extern crate serde;
+/// extern crate ron;
+///
+/// struct Html;
+///
+/// impl Formatter for Html {
+///     fn write_token(&mut self, writer: &mut dyn Write, token: Token, text: &str) -> io::Result<()> {
+///         let text = text.replace('&', "&amp;").replace('<', "&lt;");
+///
+///         match token {
+///             Token::Field => write!(writer, "<b>{}</b>", text),
+///             Token::Str => write!(writer, "<i>{}</i>", text),
+///             _ => writer.write_all(text.as_bytes()),
+///         }
+///     }
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Tag {
+///     name: &'static str,
+/// }
+///
+/// fn main() {
+///     use serde::Serialize;
+///
+///     let mut s = Serializer::with_config(None);
+///     s.set_formatter(Html);
+///     Tag { name: "<br>" }.serialize(&mut s).unwrap();
+///
+///     assert_eq!(s.into_output_string(), "(<b>name</b>:<i>\"&lt;br>\"</i>,)");
+/// }
+/// ```
+///
+/// Markup counts towards `max_width` and the alignment of
+/// `align_struct_fields` like any other text.
+pub trait Formatter {
+    /// Writes `text`, which is a single `token`, into `writer`.
+    fn write_token(&mut self, writer: &mut dyn io::Write, token: Token, text: &str) -> io::Result<()> {
+        let _ = token;
+
+        writer.write_all(text.as_bytes())
+    }
+}
@@ -1,26 +1,48 @@
+use std::collections::HashMap;
 use std::error::Error as StdError;
-use std::fmt::{Display, Formatter, Write, Result as FmtResult};
+use std::fmt::{self, Display, Result as FmtResult};
+use std::io::{self, Write};
+use std::mem;
+use std::ops::Range;
 use std::result::Result as StdResult;
 
 use serde::ser::{self, Serialize};
 
+pub use self::commented::Commented;
+pub use self::emitter::Emitter;
+pub use self::formatter::{Formatter, Token};
+pub use self::output::FmtWriter;
+pub use self::redacted::{Redacted, REDACTED};
+pub use self::skip_default::SkipDefault;
+pub use shared::{Shared, SharedArc};
+
+use self::commented::{comment_of, comment_text, COMMENTED};
+use self::output::Output;
+use self::skip_default::{SKIP_DEFAULT, SKIP_DEFAULT_DEFAULT};
+use base64;
+use parse::needs_raw_identifier;
+use raw::RAW_VALUE;
+use shared::{take_address, SHARED};
+
+mod commented;
+mod emitter;
+mod formatter;
+mod output;
+mod redacted;
+mod skip_default;
 mod value;
 
 /// Serializes `value` and returns it as string.
 ///
 /// This function does not generate any newlines or nice formatting;
-/// if you want that, you can use `pretty::to_string` instead.
+/// if you want that, you can use `to_string_pretty` instead.
 pub fn to_string<T>(value: &T) -> Result<String>
 where
     T: Serialize,
 {
-    let mut s = Serializer {
-        output: String::new(),
-        pretty: None,
-        struct_names: false,
-    };
+    let mut s = Serializer::with_config(None);
     value.serialize(&mut s)?;
-    Ok(s.output)
+    Ok(s.into_output_string())
 }
 
 /// Serializes `value` in the recommended RON layout in a pretty way.
@@ -28,13 +50,101 @@ pub fn to_string_pretty<T>(value: &T, config: PrettyConfig) -> Result<String>
 where
     T: Serialize,
 {
-    let mut s = Serializer {
-        output: String::new(),
-        pretty: Some((config, Pretty { indent: 0, sequence_index: Vec::new() })),
-        struct_names: false,
+    let mut s = Serializer::with_config(Some(config));
+    s.serialize_document(value)?;
+    Ok(s.into_output_string())
+}
+
+/// Serializes `value` in a canonical form, which is the same
+/// for all equal values and suitable for hashing or signing.
+///
+/// The output is as compact as `to_string`'s, map entries are ordered
+/// by their serialized keys and struct fields by name, and floats always
+/// contain a decimal point, with `-0.0` written as `0.0`.
+/// Sequences are written in iteration order, so use ordered collections
+/// like `BTreeSet` rather than `HashSet`.
+pub fn to_string_canonical<T>(value: &T) -> Result<String>
+where
+    T: Serialize,
+{
+    let config = PrettyConfig {
+        depth_limit: 0,
+        sort_map_keys: true,
+        sort_struct_fields: true,
+        float_decimal_point: true,
+        ..PrettyConfig::default()
     };
+    let mut s = Serializer::with_config(Some(config));
+    s.canonical = true;
+    value.serialize(&mut s)?;
+    Ok(s.into_output_string())
+}
+
+/// Serializes `value` and returns the UTF-8 encoded bytes.
+pub fn to_vec<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize,
+{
+    let mut s = Serializer::with_config(None);
     value.serialize(&mut s)?;
-    Ok(s.output)
+    Ok(s.into_inner())
+}
+
+/// Serializes `value` in a pretty way and returns the UTF-8 encoded bytes.
+pub fn to_vec_pretty<T>(value: &T, config: PrettyConfig) -> Result<Vec<u8>>
+where
+    T: Serialize,
+{
+    let mut s = Serializer::with_config(Some(config));
+    s.serialize_document(value)?;
+    Ok(s.into_inner())
+}
+
+/// Serializes `value` into `writer`.
+///
+/// The output is written as it is produced, so no intermediate `String`
+/// is built; wrap `writer` in a `BufWriter` if it is unbuffered.
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: io::Write,
+    T: Serialize,
+{
+    let mut s = Serializer::with_writer(writer, None);
+    value.serialize(&mut s)
+}
+
+/// Serializes `value` into `writer` in a pretty way.
+///
+/// Like `to_writer`, this writes the output as it is produced.
+/// Only what `config` needs to look at before writing is held back:
+/// a single line when `max_width` is set, and whole maps or structs
+/// when their entries are sorted or aligned.
+pub fn to_writer_pretty<W, T>(writer: W, value: &T, config: PrettyConfig) -> Result<()>
+where
+    W: io::Write,
+    T: Serialize,
+{
+    let mut s = Serializer::with_writer(writer, Some(config));
+    s.serialize_document(value)
+}
+
+/// Serializes `value` into a `fmt::Write`, like a `String`
+/// or a `fmt::Formatter`.
+pub fn to_fmt_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: fmt::Write,
+    T: Serialize,
+{
+    to_writer(FmtWriter(writer), value)
+}
+
+/// Serializes `value` into a `fmt::Write` in a pretty way.
+pub fn to_fmt_writer_pretty<W, T>(writer: W, value: &T, config: PrettyConfig) -> Result<()>
+where
+    W: fmt::Write,
+    T: Serialize,
+{
+    to_writer_pretty(FmtWriter(writer), value, config)
 }
 
 /// Serialization result.
@@ -43,14 +153,28 @@ pub type Result<T> = StdResult<T, Error>;
 /// Serialization error.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Error {
+    /// An IO error occurred while writing the output.
+    IoError(String),
     /// A custom error emitted by a serialized value.
     Message(String),
+    /// The value is nested deeper than the recursion limit,
+    /// most likely because it contains a cycle.
+    CircularOrTooDeep,
+    /// A float is infinite or NaN, but writing those isn't enabled.
+    NonFiniteFloat,
+    /// A `Shared` value is in a map or struct whose entries are sorted,
+    /// which could move its references in front of its anchor.
+    SortedSharedValue,
 }
 
 impl Display for Error {
-    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> FmtResult {
         match *self {
+            Error::IoError(ref e) => write!(f, "IO error: {}", e),
             Error::Message(ref e) => write!(f, "Custom message: {}", e),
+            Error::CircularOrTooDeep => f.write_str("Value is circular or nested too deeply"),
+            Error::NonFiniteFloat => f.write_str("Float is infinite or NaN"),
+            Error::SortedSharedValue => f.write_str("Shared value in sorted entries"),
         }
     }
 }
@@ -64,36 +188,311 @@ impl ser::Error for Error {
 impl StdError for Error {
     fn description(&self) -> &str {
         match *self {
+            Error::IoError(ref e) => e,
             Error::Message(ref e) => e,
+            Error::CircularOrTooDeep => "Value is circular or nested too deeply",
+            Error::NonFiniteFloat => "Float is infinite or NaN",
+            Error::SortedSharedValue => "Shared value in sorted entries",
         }
     }
 }
 
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::IoError(e.to_string())
+    }
+}
+
 /// Pretty serializer state
 struct Pretty {
     indent: usize,
     sequence_index: Vec<usize>,
+    inline: bool,
+    /// Set by `always_inline`, unlike `inline` during trial renderings.
+    forced_inline: bool,
+    /// `config.depth_limit`, unless raised by `always_expand`.
+    depth_limit: usize,
+    /// The name of the struct field being serialized.
+    field: Option<&'static str>,
+    /// Whether scalars are replaced by `REDACTED`.
+    redact: bool,
+    wrote_none: bool,
+    wrote_default: bool,
+    wrote_number: bool,
+    /// The comment of the element or field which is written next.
+    comment: Option<String>,
+    /// The depth of the value held by `Some`s which are written without
+    /// `Some(...)`, and how many of them there are in a row.
+    implicit_somes: Option<(usize, usize)>,
+}
+
+impl Pretty {
+    fn save_layout(&self) -> Restore {
+        Restore {
+            inline: self.inline,
+            forced_inline: self.forced_inline,
+            depth_limit: self.depth_limit,
+        }
+    }
+
+    fn restore_layout(&mut self, restore: Restore) {
+        self.inline = restore.inline;
+        self.forced_inline = restore.forced_inline;
+        self.depth_limit = restore.depth_limit;
+    }
+}
+
+/// The fields of a `Shared`, which is written as `&0 value` or `*0`.
+#[derive(Clone, Copy, PartialEq)]
+enum SharedField {
+    /// The address, which has been handed over already.
+    Address { reference: bool },
+    Value,
+    /// The value has been written before and is left out.
+    Reference,
+}
+
+/// Layout state to restore once a value whose layout
+/// has been overridden is complete.
+#[derive(Clone, Copy)]
+struct Restore {
+    inline: bool,
+    forced_inline: bool,
+    depth_limit: usize,
+}
+
+/// How the members of a compound value are laid out.
+#[derive(Clone, Copy, PartialEq)]
+enum Layout {
+    /// One member per line, like `(\n    a: 1,\n)`.
+    Multiline,
+    /// All members on one line, like `(a: 1, b: 2)`.
+    Inline,
+    /// No whitespace at all, like `(a:1,b:2,)`.
+    Compact,
+}
+
+/// The base in which unsigned integers are written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Radix {
+    /// `65280`
+    Decimal,
+    /// `0xFF00`
+    Hexadecimal,
+    /// `0o177400`
+    Octal,
+    /// `0b1111111100000000`
+    Binary,
+}
+
+/// Which characters of strings and chars are escaped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Escape {
+    /// Only escape quotes and backslashes; everything else,
+    /// including line breaks, is written as is.
+    Minimal,
+    /// Also escape control characters, like `\n` or `\u{7}`;
+    /// all other characters are written as UTF-8.
+    Utf8,
+    /// Also escape all non-ASCII characters, like `\u{e4}`,
+    /// so that the output is plain ASCII.
+    Ascii,
+}
+
+/// Where spaces are written in compact output, like `(x: 1, y: 2)`
+/// rather than `(x:1,y:2,)`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Spacing {
+    /// Write a space after the `:` of struct fields and map entries.
+    pub after_colon: bool,
+    /// Write a space after the `,` between elements.
+    pub after_comma: bool,
+    /// Write a space after `(` and before `)` of non-empty
+    /// structs and tuples.
+    pub inside_parens: bool,
 }
 
 /// Pretty serializer configuration
+///
+/// New options may be added in the future, so prefer
+/// overriding single fields of the default configuration:
+///
+/// ```
+/// use ron::ser::PrettyConfig;
+///
+/// let config = PrettyConfig {
+///     depth_limit: 3,
+///     ..PrettyConfig::default()
+/// };
+/// ```
+///
+/// A configuration can also be read from RON, e.g. from a file
+/// describing the formatting style of a project; missing fields
+/// keep their default values:
+///
+/// ```
+/// use ron::ser::{Escape, PrettyConfig};
+///
+/// let config: PrettyConfig =
+///     ron::de::from_str("(depth_limit: 3, indentor: \"\\t\", escape: Ascii)").unwrap();
+///
+/// assert_eq!(config.depth_limit, 3);
+/// assert_eq!(config.indentor, "\t");
+/// assert_eq!(config.escape, Escape::Ascii);
+/// assert!(config.trailing_comma);
+/// ```
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PrettyConfig {
     /// Limit the pretty-ness up to the given depth.
+    ///
+    /// Containers nested deeper than this are written on a single line,
+    /// exactly like `to_string` would; `0` disables pretty printing.
     pub depth_limit: usize,
-    /// New line string
+    /// Spaces in values written on a single line beyond `depth_limit`
+    pub compact_spacing: Spacing,
+    /// New line string, `"\r\n"` on Windows and `"\n"` elsewhere by default
     pub new_line: String,
-    /// Indentation string
+    /// Indentation string, repeated once per nesting level (e.g. `"\t"`)
     pub indentor: String,
     /// Separate tuple members with indentation
+    ///
+    /// This applies to tuples, tuple structs and tuple variants;
+    /// if disabled, their members are kept on one line.
     pub separate_tuple_members: bool,
     /// Enumerate array items in comments
+    ///
+    /// Every line of a pretty sequence is preceded by a comment line
+    /// with the index of its element, like `// [12]`.
     pub enumerate_arrays: bool,
+    /// Write up to this many numbers on each line of a multi-line
+    /// sequence, like the rows of a matrix; `None` writes one per line.
+    pub numbers_per_line: Option<usize>,
+    /// Always write values on a single line if their struct or enum,
+    /// their variant or the struct field holding them has one
+    /// of these names, like `"Color"` or `"position"`.
+    pub always_inline: Vec<String>,
+    /// Always write values on multiple lines, regardless of `depth_limit`
+    /// and `max_width`, if their struct or enum, their variant or the
+    /// struct field holding them has one of these names.
+    ///
+    /// Tuples are expanded like with `separate_tuple_members`;
+    /// nested values are written as usual.
+    pub always_expand: Vec<String>,
+    /// Keep compound values on a single line if they fit within this
+    /// many columns, like `(x: 1, y: 2)`; `None` always expands them.
+    pub max_width: Option<usize>,
+    /// Write map entries ordered by their serialized keys,
+    /// so that the output doesn't depend on the map's iteration order.
+    pub sort_map_keys: bool,
+    /// Write struct fields in alphabetical order instead of
+    /// declaration order.
+    pub sort_struct_fields: bool,
+    /// Pad the fields of multi-line structs so that their values
+    /// line up, like `name:   "Bob"` above `health: 100`.
+    pub align_struct_fields: bool,
+    /// Separate the fields of the outermost struct with empty lines,
+    /// which groups the sections of big config files.
+    pub blank_lines_between_fields: bool,
+    /// Separate the entries of multi-line maps with more than this many
+    /// entries with empty lines; `None` never does.
+    pub blank_lines_in_maps: Option<usize>,
+    /// End the last member of multi-line values with a comma
+    /// as well, which keeps diffs small when members are added.
+    pub trailing_comma: bool,
+    /// Write floats with this many digits after the decimal point,
+    /// like `0.300` for a precision of 3; `None` writes the shortest
+    /// representation which reads back as the same value.
+    pub float_precision: Option<usize>,
+    /// Always write a decimal point, so that whole floats
+    /// are written as `1.0` rather than `1`.
+    pub float_decimal_point: bool,
+    /// Write infinite floats and NaN as `inf`, `-inf` and `NaN`,
+    /// which the deserializer reads back as floats.
+    ///
+    /// They aren't valid numbers otherwise, so serializing them fails
+    /// with `Error::NonFiniteFloat` unless this is set.
+    pub non_finite_floats: bool,
+    /// Write unsigned integers in this base; the prefixes
+    /// `0x`, `0o` and `0b` are understood by the deserializer.
+    pub unsigned_radix: Radix,
+    /// Which characters of strings and chars to escape
+    pub escape: Escape,
+    /// Write strings as raw strings like `r#"C:\temp"#` where that's
+    /// shorter than escaping them, e.g. for regexes and Windows paths.
+    ///
+    /// Strings with characters which `escape` requires to be escaped
+    /// are never written raw.
+    pub raw_strings: bool,
+    /// Write byte arrays as hex blocks like `x"00 7f ff"`, with this many
+    /// bytes per line; `None` writes them as arrays of integers.
+    ///
+    /// This only applies to values serialized as bytes,
+    /// like `serde_bytes::ByteBuf`, not to `Vec<u8>`.
+    pub hex_bytes: Option<usize>,
+    /// Write byte arrays as byte strings like `b"GIF89a\x01\x00"`, where
+    /// printable ASCII characters are written as they are; this takes
+    /// precedence over `hex_bytes`.
+    ///
+    /// Like `hex_bytes`, this only applies to values serialized as bytes.
+    pub byte_strings: bool,
+    /// Write byte arrays as base64 strings like `"R0lGODlh"`, which are
+    /// much shorter than byte strings for binary data like thumbnails;
+    /// this takes precedence over `byte_strings` and `hex_bytes`.
+    ///
+    /// The deserializer reads them back only if
+    /// `Deserializer::set_base64_bytes` is set, as they'd be read
+    /// as the UTF-8 of the string otherwise.
+    pub base64_bytes: bool,
+    /// Write `Some(value)` as just `value`, which requires the
+    /// `implicit_some` extension to be enabled when deserializing.
+    ///
+    /// Nested options which hold `None`, like `Some(None)`, are still
+    /// written with their `Some`s, as they'd be read as `None` otherwise.
+    pub implicit_some: bool,
+    /// Write newtype structs like `Wrapper(5)` as just `5`, which requires
+    /// the `unwrap_newtypes` extension to be enabled when deserializing.
+    pub unwrap_newtypes: bool,
+    /// Leave out struct fields which are `None` instead of writing
+    /// `field: None`; missing optional fields deserialize as `None`.
+    pub omit_none_fields: bool,
+    /// Leave out struct fields of type `SkipDefault` which hold
+    /// the default value, so that only changed settings are written.
+    pub skip_default_fields: bool,
+    /// Write `REDACTED` instead of the strings, numbers, booleans, chars
+    /// and bytes held by struct fields with one of these names, like
+    /// `"password"`, including those nested in their values. A `*` matches
+    /// any part of a name, so `"*_key"` covers both `api_key` and `ssh_key`.
+    ///
+    /// The keys of maps are kept, so that they stay distinct.
+    pub redacted_fields: Vec<String>,
+    /// Prefix structs with their names, like `Player(health: 100)`.
+    ///
+    /// The deserializer accepts structs with and without names.
+    pub struct_names: bool,
+    /// Prefix only the outermost struct with its name, e.g. to tell
+    /// the type of an asset file, and write nested structs without.
+    ///
+    /// This has no effect if `struct_names` is set.
+    pub root_struct_name: bool,
+    /// Start the output with an `#![enable(...)]` attribute listing the
+    /// extensions it relies on, like `implicit_some`, so that it can be
+    /// read without enabling them on the deserializer.
+    ///
+    /// Nothing is written if no extensions are needed.
+    pub extensions_header: bool,
+    /// Fail with `Error::CircularOrTooDeep` instead of overflowing the
+    /// stack once values are nested deeper than this, e.g. because a graph
+    /// of `Rc`s contains a cycle. `None`, the default, removes the limit.
+    pub recursion_limit: Option<usize>,
 }
 
 impl Default for PrettyConfig {
     fn default() -> Self {
         PrettyConfig {
             depth_limit: !0,
+            compact_spacing: Spacing::default(),
             #[cfg(not(target_os = "windows"))]
             new_line: "\n".to_string(),
             #[cfg(target_os = "windows")]
@@ -101,6 +500,34 @@ impl Default for PrettyConfig {
             indentor: "    ".to_string(),
             separate_tuple_members: false,
             enumerate_arrays: false,
+            numbers_per_line: None,
+            always_inline: Vec::new(),
+            always_expand: Vec::new(),
+            max_width: None,
+            sort_map_keys: false,
+            sort_struct_fields: false,
+            align_struct_fields: false,
+            blank_lines_between_fields: false,
+            blank_lines_in_maps: None,
+            trailing_comma: true,
+            float_precision: None,
+            float_decimal_point: false,
+            non_finite_floats: false,
+            unsigned_radix: Radix::Decimal,
+            escape: Escape::Utf8,
+            raw_strings: false,
+            hex_bytes: None,
+            byte_strings: false,
+            base64_bytes: false,
+            implicit_some: false,
+            unwrap_newtypes: false,
+            omit_none_fields: false,
+            skip_default_fields: false,
+            redacted_fields: Vec::new(),
+            struct_names: false,
+            root_struct_name: false,
+            extensions_header: false,
+            recursion_limit: None,
         }
     }
 }
@@ -108,368 +535,1618 @@ impl Default for PrettyConfig {
 /// The RON serializer.
 ///
 /// You can just use `to_string` for deserializing a value.
-/// If you want it pretty-printed, take a look at the `pretty` module.
-pub struct Serializer {
-    output: String,
+/// If you want it pretty-printed, take a look at `to_string_pretty`.
+pub struct Serializer<W> {
+    output: Output<W>,
     pretty: Option<(PrettyConfig, Pretty)>,
     struct_names: bool,
+    canonical: bool,
+    depth: usize,
+    recursion_limit: Option<usize>,
+    non_finite_floats: bool,
+    formatter: Option<Box<dyn Formatter>>,
+    /// The anchors of the `Shared` values written so far, by address.
+    anchors: HashMap<u64, usize>,
+    /// How many of the maps and structs which are written are sorted.
+    sorting: usize,
+    /// Whether the next string is the RON of a `RawValue`.
+    raw_value: bool,
 }
 
-impl Serializer {
-    /// Creates a new `Serializer`.
+impl Serializer<Vec<u8>> {
+    /// Creates a new `Serializer` which writes into an in-memory buffer.
     ///
-    /// Most of the time you can just use `to_string` or `to_string_pretty`.
+    /// If `struct_names` is set, structs are prefixed with their names;
+    /// `config.struct_names` is ignored here.
+    #[deprecated(note = "use `Serializer::with_config` and `set_struct_names` instead")]
     pub fn new(config: Option<PrettyConfig>, struct_names: bool) -> Self {
-        Serializer {
-            output: String::new(),
-            pretty: config.map(|conf| (conf, Pretty { indent: 0, sequence_index: Vec::new() })),
-            struct_names,
-        }
+        let mut s = Serializer::with_config(config);
+        s.set_struct_names(struct_names);
+        s
+    }
+
+    /// Creates a new `Serializer` which writes into an in-memory buffer.
+    ///
+    /// Most of the time you can just use `to_string` or `to_string_pretty`.
+    pub fn with_config(config: Option<PrettyConfig>) -> Self {
+        Serializer::with_writer(Vec::new(), config)
     }
 
     /// Consumes `self` and returns the built `String`.
     pub fn into_output_string(self) -> String {
-        self.output
+        String::from_utf8(self.output.into_inner()).expect("Bug: serializer produced invalid UTF-8")
     }
+}
 
-    fn is_pretty(&self) -> bool {
-        match self.pretty {
-            Some((ref config, ref pretty)) => pretty.indent < config.depth_limit,
-            None => false,
-        }
+impl<'a> Serializer<FmtWriter<&'a mut String>> {
+    /// Creates a new `Serializer` which appends to `output`.
+    ///
+    /// Clearing and reusing one `String` avoids allocating
+    /// a new one for every serialized value.
+    pub fn new_into(output: &'a mut String, config: Option<PrettyConfig>) -> Self {
+        Serializer::with_writer(FmtWriter(output), config)
     }
+}
 
-    fn separate_tuple_members(&self) -> bool {
-        self.pretty
+impl<W: io::Write> Serializer<W> {
+    /// Creates a new `Serializer` which writes into `writer`.
+    pub fn with_writer(writer: W, config: Option<PrettyConfig>) -> Self {
+        let struct_names = config
             .as_ref()
-            .map(|&(ref config, _)| config.separate_tuple_members)
-            .unwrap_or(false)
-    }
+            .map(|config| config.struct_names)
+            .unwrap_or(false);
+        let recursion_limit = config
+            .as_ref()
+            .and_then(|config| config.recursion_limit);
+        let non_finite_floats = config
+            .as_ref()
+            .map(|config| config.non_finite_floats)
+            .unwrap_or(false);
 
-    fn start_indent(&mut self) {
-        if let Some((ref config, ref mut pretty)) = self.pretty {
-            pretty.indent += 1;
-            if pretty.indent < config.depth_limit {
-                self.output += &config.new_line;
-            }
+        Serializer {
+            output: Output::new(writer),
+            pretty: config.map(|conf| {
+                let depth_limit = conf.depth_limit;
+                (
+                    conf,
+                    Pretty {
+                        indent: 0,
+                        sequence_index: Vec::new(),
+                        inline: false,
+                        forced_inline: false,
+                        depth_limit,
+                        field: None,
+                        redact: false,
+                        wrote_none: false,
+                        wrote_default: false,
+                        wrote_number: false,
+                        comment: None,
+                        implicit_somes: None,
+                    },
+                )
+            }),
+            struct_names,
+            canonical: false,
+            depth: 0,
+            recursion_limit,
+            non_finite_floats,
+            formatter: None,
+            anchors: HashMap::new(),
+            sorting: 0,
+            raw_value: false,
         }
     }
 
-    fn indent(&mut self) {
-        if let Some((ref config, ref pretty)) = self.pretty {
-            if pretty.indent < config.depth_limit {
-                self.output
-                    .extend((0..pretty.indent).map(|_| config.indentor.as_str()));
-            }
-        }
+    /// Writes tokens through `formatter` instead of as they are.
+    pub fn set_formatter<F: Formatter + 'static>(&mut self, formatter: F) {
+        self.formatter = Some(Box::new(formatter));
     }
 
-    fn end_indent(&mut self) {
-        if let Some((ref config, ref mut pretty)) = self.pretty {
-            if pretty.indent < config.depth_limit {
-                self.output
-                    .extend((1..pretty.indent).map(|_| config.indentor.as_str()));
+    /// Writes `text`, which is a single `token`, using the formatter.
+    ///
+    /// Names which aren't valid identifiers are written as raw ones,
+    /// like `r#1st`.
+    fn write_token(&mut self, token: Token, text: &str) -> Result<()> {
+        let raw;
+        let text = match token {
+            Token::Identifier | Token::Field if needs_raw_identifier(text) => {
+                raw = format!("r#{}", text);
+
+                &raw
             }
-            pretty.indent -= 1;
+            _ => text,
+        };
+
+        match self.formatter {
+            Some(ref mut formatter) => formatter.write_token(&mut self.output, token, text)?,
+            None => self.output.write_all(text.as_bytes())?,
         }
+
+        Ok(())
     }
 
-    fn serialize_escaped_str(&mut self, value: &str) {
-        self.output += "\"";
-        self.output.extend(value.chars().flat_map(|c| c.escape_debug()));
-        self.output += "\"";
+    /// Sets whether structs are prefixed with their names,
+    /// like `Player(health: 100)`.
+    ///
+    /// This defaults to `config.struct_names`, or `false`
+    /// without a config.
+    pub fn set_struct_names(&mut self, struct_names: bool) {
+        self.struct_names = struct_names;
     }
-}
 
-impl<'a> ser::Serializer for &'a mut Serializer {
-    type Ok = ();
-    type Error = Error;
+    /// Sets how deeply values may be nested before serialization fails
+    /// with `Error::CircularOrTooDeep`; `None` removes the limit.
+    ///
+    /// This defaults to `config.recursion_limit`, or `None`
+    /// without a config.
+    pub fn set_recursion_limit(&mut self, limit: Option<usize>) {
+        self.recursion_limit = limit;
+    }
 
-    type SerializeSeq = Self;
-    type SerializeTuple = Self;
-    type SerializeTupleStruct = Self;
-    type SerializeTupleVariant = Self;
-    type SerializeMap = Self;
-    type SerializeStruct = Self;
-    type SerializeStructVariant = Self;
+    /// Sets whether infinite floats and NaN are written as `inf`, `-inf`
+    /// and `NaN`, rather than failing with `Error::NonFiniteFloat`.
+    ///
+    /// This defaults to `config.non_finite_floats`, or `false`
+    /// without a config.
+    pub fn set_non_finite_floats(&mut self, enabled: bool) {
+        self.non_finite_floats = enabled;
+    }
 
-    fn serialize_bool(self, v: bool) -> Result<()> {
-        self.output += if v { "true" } else { "false" };
-        Ok(())
+    /// Consumes `self` and returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.output.into_inner()
     }
 
-    fn serialize_i8(self, v: i8) -> Result<()> {
-        self.serialize_i64(v as i64)
+    /// Enters a nested value, failing if it is nested too deeply.
+    fn enter(&mut self) -> Result<()> {
+        self.depth += 1;
+
+        match self.recursion_limit {
+            Some(limit) if self.depth > limit => Err(Error::CircularOrTooDeep),
+            _ => Ok(()),
+        }
     }
 
-    fn serialize_i16(self, v: i16) -> Result<()> {
-        self.serialize_i64(v as i64)
+    fn leave(&mut self) {
+        self.depth -= 1;
     }
 
-    fn serialize_i32(self, v: i32) -> Result<()> {
-        self.serialize_i64(v as i64)
+    fn layout(&self) -> Layout {
+        match self.pretty {
+            Some((_, ref pretty)) if pretty.indent < pretty.depth_limit => {
+                if pretty.inline {
+                    Layout::Inline
+                } else {
+                    Layout::Multiline
+                }
+            }
+            _ => Layout::Compact,
+        }
     }
 
-    fn serialize_i64(self, v: i64) -> Result<()> {
-        // TODO optimize
-        self.output += &v.to_string();
-        Ok(())
+    fn sort_map_keys(&self) -> bool {
+        self.pretty
+            .as_ref()
+            .map(|(config, _)| config.sort_map_keys)
+            .unwrap_or(false)
     }
 
-    fn serialize_u8(self, v: u8) -> Result<()> {
-        self.serialize_u64(v as u64)
+    fn sort_struct_fields(&self) -> bool {
+        self.pretty
+            .as_ref()
+            .map(|(config, _)| config.sort_struct_fields)
+            .unwrap_or(false)
     }
 
-    fn serialize_u16(self, v: u16) -> Result<()> {
-        self.serialize_u64(v as u64)
+    fn implicit_some(&self) -> bool {
+        self.pretty
+            .as_ref()
+            .map(|(config, _)| config.implicit_some)
+            .unwrap_or(false)
     }
 
-    fn serialize_u32(self, v: u32) -> Result<()> {
-        self.serialize_u64(v as u64)
+    /// How many `Some`s without `Some(...)` hold the value which is
+    /// written next, if it's their value rather than one nested in it.
+    fn implicit_somes(&self) -> usize {
+        match self.pretty {
+            Some((_, ref pretty)) => match pretty.implicit_somes {
+                Some((depth, somes)) if depth == self.depth => somes,
+                _ => 0,
+            },
+            None => 0,
+        }
     }
 
-    fn serialize_u64(self, v: u64) -> Result<()> {
-        self.output += &v.to_string();
-        Ok(())
+    /// Whether a struct is prefixed with its name,
+    /// checked before entering the struct.
+    fn writes_struct_name(&self) -> bool {
+        self.struct_names
+            || (self.depth == 0
+                && self.pretty
+                    .as_ref()
+                    .map(|(config, _)| config.root_struct_name)
+                    .unwrap_or(false))
     }
 
-    fn serialize_f32(self, v: f32) -> Result<()> {
-        self.output += &v.to_string();
-        Ok(())
+    fn compact_spacing(&self) -> Spacing {
+        self.pretty
+            .as_ref()
+            .map(|(config, _)| config.compact_spacing)
+            .unwrap_or_default()
     }
 
-    fn serialize_f64(self, v: f64) -> Result<()> {
-        self.output += &v.to_string();
-        Ok(())
+    fn unwrap_newtypes(&self) -> bool {
+        self.pretty
+            .as_ref()
+            .map(|(config, _)| config.unwrap_newtypes)
+            .unwrap_or(false)
     }
 
-    fn serialize_char(self, v: char) -> Result<()> {
-        self.output += "'";
-        if v == '\\' || v == '\'' {
-            self.output.push('\\');
-        }
-        self.output.push(v);
-        self.output += "'";
-        Ok(())
+    fn numbers_per_line(&self) -> Option<usize> {
+        self.pretty
+            .as_ref()
+            .and_then(|(config, _)| config.numbers_per_line)
     }
 
-    fn serialize_str(self, v: &str) -> Result<()> {
-        self.serialize_escaped_str(v);
+    fn skip_default_fields(&self) -> bool {
+        self.pretty
+            .as_ref()
+            .map(|(config, _)| config.skip_default_fields)
+            .unwrap_or(false)
+    }
 
-        Ok(())
+    fn align_struct_fields(&self) -> bool {
+        self.pretty
+            .as_ref()
+            .map(|(config, _)| config.align_struct_fields)
+            .unwrap_or(false)
     }
 
-    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
-        use serde::ser::SerializeSeq;
-        //TODO: shorter version? e.g. base64 encoding in a single line
-        let mut seq = self.serialize_seq(Some(v.len()))?;
-        for byte in v {
-            seq.serialize_element(byte)?;
-        }
-        seq.end()
+    fn omit_none_fields(&self) -> bool {
+        self.pretty
+            .as_ref()
+            .map(|(config, _)| config.omit_none_fields)
+            .unwrap_or(false)
     }
 
-    fn serialize_none(self) -> Result<()> {
-        self.output += "None";
+    fn trailing_comma(&self) -> bool {
+        self.pretty
+            .as_ref()
+            .map(|(config, _)| config.trailing_comma)
+            .unwrap_or(true)
+    }
 
-        Ok(())
+    /// Whether the fields of a struct which has just been entered
+    /// are separated with empty lines.
+    fn blank_lines_between_fields(&self) -> bool {
+        self.depth == 1
+            && self
+                .pretty
+                .as_ref()
+                .map(|(config, _)| config.blank_lines_between_fields)
+                .unwrap_or(false)
     }
 
-    fn serialize_some<T>(self, value: &T) -> Result<()>
-    where
-        T: ?Sized + Serialize,
-    {
-        self.output += "Some(";
-        value.serialize(&mut *self)?;
-        self.output += ")";
+    /// Whether the entries of a map with `len` entries
+    /// are separated with empty lines.
+    fn blank_lines_in_map(&self, len: Option<usize>) -> bool {
+        let threshold = self.pretty.as_ref().and_then(|(config, _)| config.blank_lines_in_maps);
 
-        Ok(())
+        match (threshold, len) {
+            (Some(threshold), Some(len)) => len > threshold,
+            _ => false,
+        }
     }
 
-    fn serialize_unit(self) -> Result<()> {
-        self.output += "()";
+    fn separate_tuple_members(&self) -> bool {
+        self.pretty
+            .as_ref()
+            .map(|(config, _)| config.separate_tuple_members)
+            .unwrap_or(false)
+    }
+
+    fn start_indent(&mut self) -> Result<()> {
+        if let Some((ref config, ref mut pretty)) = self.pretty {
+            pretty.indent += 1;
+            if pretty.indent < pretty.depth_limit && !pretty.inline {
+                self.output.write_all(config.new_line.as_bytes())?;
+            }
+        }
 
         Ok(())
     }
 
-    fn serialize_unit_struct(self, name: &'static str) -> Result<()> {
-        if self.struct_names {
-            self.output += name;
+    fn indent(&mut self) -> Result<()> {
+        if let Some((ref config, ref pretty)) = self.pretty {
+            if pretty.indent < pretty.depth_limit && !pretty.inline {
+                for _ in 0..pretty.indent {
+                    self.output.write_all(config.indentor.as_bytes())?;
+                }
+            }
+        }
 
-            Ok(())
-        } else {
-            self.serialize_unit()
+        Ok(())
+    }
+
+    fn end_indent(&mut self) -> Result<()> {
+        if let Some((ref config, ref mut pretty)) = self.pretty {
+            if pretty.indent < pretty.depth_limit && !pretty.inline {
+                for _ in 1..pretty.indent {
+                    self.output.write_all(config.indentor.as_bytes())?;
+                }
+            }
+            pretty.indent -= 1;
         }
+
+        Ok(())
     }
 
-    fn serialize_unit_variant(self, _: &'static str, _: u32, variant: &'static str) -> Result<()> {
-        self.output += variant;
+    fn new_line(&mut self) -> Result<()> {
+        if let Some((ref config, ref pretty)) = self.pretty {
+            if pretty.indent < pretty.depth_limit && !pretty.inline {
+                self.output.write_all(config.new_line.as_bytes())?;
+            }
+        }
 
         Ok(())
     }
 
-    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
+    /// Serializes `value` as a whole document,
+    /// starting with the extensions header if configured.
+    fn serialize_document<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        if self.struct_names {
-            self.output += name;
+        if let Some((ref config, _)) = self.pretty {
+            let mut extensions = Vec::new();
+            if config.unwrap_newtypes {
+                extensions.push("unwrap_newtypes");
+            }
+            if config.implicit_some {
+                extensions.push("implicit_some");
+            }
+
+            if config.extensions_header && !extensions.is_empty() {
+                write!(self.output, "#![enable({})]", extensions.join(", "))?;
+                self.output.write_all(config.new_line.as_bytes())?;
+                self.output.write_all(config.new_line.as_bytes())?;
+            }
         }
 
-        self.output += "(";
-        value.serialize(&mut *self)?;
-        self.output += ")";
-        Ok(())
+        self.serialize_value(value)
     }
 
-    fn serialize_newtype_variant<T>(
-        self,
-        _: &'static str,
-        _: u32,
-        variant: &'static str,
-        value: &T,
-    ) -> Result<()>
+    /// Serializes `value`, keeping it on one line if it fits into
+    /// `max_width`.
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        self.output += variant;
-        self.output += "(";
+        let max_width = match self.pretty {
+            Some((ref config, _)) if self.layout() == Layout::Multiline => config.max_width,
+            _ => None,
+        };
 
-        value.serialize(&mut *self)?;
+        if let Some(max_width) = max_width {
+            // Leave room for the trailing comma
+            let width = max_width.saturating_sub(self.output.column() + 1);
 
-        self.output += ")";
-        Ok(())
-    }
+            if let Some(inline) = self.capture_inline(value, width)? {
+                self.output.write_all(&inline)?;
 
-    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq> {
-        self.output += "[";
+                return Ok(());
+            }
+        }
+
+        value.serialize(self)
+    }
+
+    /// Checks whether `value` is serialized as `None`.
+    fn is_none<T>(&mut self, value: &T) -> Result<bool>
+    where
+        T: ?Sized + Serialize,
+    {
+        if let Some((_, ref mut pretty)) = self.pretty {
+            pretty.wrote_none = false;
+        }
+
+        // Anything longer than `None` can bail out early
+        let is_none = self.probe(value, 4)?.as_deref() == Some(b"None");
+
+        Ok(is_none && self.pretty.as_ref().is_some_and(|(_, p)| p.wrote_none))
+    }
+
+    /// Checks whether `value` is a `SkipDefault` holding the default value.
+    fn is_default<T>(&mut self, value: &T) -> Result<bool>
+    where
+        T: ?Sized + Serialize,
+    {
+        if let Some((_, ref mut pretty)) = self.pretty {
+            pretty.wrote_default = false;
+        }
+
+        // `SkipDefault` tells before writing anything
+        self.probe(value, 0)?;
+
+        Ok(self.pretty.as_ref().is_some_and(|(_, p)| p.wrote_default))
+    }
+
+    /// Applies `always_inline` and `always_expand` to a container called
+    /// one of `names`, or held by a field with a matching name.
+    ///
+    /// Returns the layout to restore once the container is complete.
+    fn override_layout(&mut self, names: &[&str]) -> Result<Option<Restore>> {
+        let (config, pretty) = match self.pretty {
+            Some((ref config, ref mut pretty)) => (config, pretty),
+            None => return Ok(None),
+        };
+
+        let field = pretty.field.take();
+        let matches = |rules: &[String]| {
+            rules
+                .iter()
+                .any(|rule| names.contains(&rule.as_str()) || field == Some(rule.as_str()))
+        };
+        let restore = pretty.save_layout();
+
+        if pretty.forced_inline {
+            // Everything inside stays on the line as well
+            Ok(None)
+        } else if matches(&config.always_inline) {
+            // Written like it fits within `max_width`, even beyond `depth_limit`
+            pretty.inline = true;
+            pretty.forced_inline = true;
+            pretty.depth_limit = !0;
+
+            Ok(Some(restore))
+        } else if matches(&config.always_expand) {
+            if pretty.inline {
+                // Fails attempts to fit the value on one line
+                return Err(self.output.overflow().into());
+            }
+            pretty.depth_limit = pretty.depth_limit.max(pretty.indent + 2);
+
+            Ok(Some(restore))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Starts a tuple, which is only written on multiple lines
+    /// if its members are separated or its layout is overridden.
+    fn start_tuple(&mut self, restore: Option<Restore>) -> Result<Compound<'_, W>> {
+        self.enter()?;
+        self.output.write_all(b"(")?;
+
+        let separate = self.separate_tuple_members() || restore.is_some();
+        let layout = if separate {
+            self.start_indent()?;
+
+            self.layout()
+        } else {
+            match self.layout() {
+                Layout::Multiline => Layout::Inline,
+                layout => layout,
+            }
+        };
+
+        let mut compound = Compound::new(self, layout).restore(restore).parens();
+        compound.separate = separate;
+        Ok(compound)
+    }
+
+    /// Checks whether `value` is written as a plain number.
+    fn is_number<T>(&mut self, value: &T) -> Result<bool>
+    where
+        T: ?Sized + Serialize,
+    {
+        if let Some((_, ref mut pretty)) = self.pretty {
+            pretty.wrote_number = false;
+        }
+
+        // Numbers are marked before anything is written
+        self.probe(value, 0)?;
+
+        Ok(self.pretty.as_ref().is_some_and(|(_, p)| p.wrote_number))
+    }
+
+    /// Writes `REDACTED` instead of a scalar held by a redacted field,
+    /// and returns whether it did.
+    fn redact(&mut self) -> Result<bool> {
+        match self.pretty {
+            Some((_, ref pretty)) if pretty.redact => {
+                self.serialize_escaped(REDACTED, '"')?;
+
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn mark_number(&mut self) {
+        if let Some((_, ref mut pretty)) = self.pretty {
+            pretty.wrote_number = true;
+        }
+    }
+
+    /// Looks for the comment of a `Commented` value,
+    /// which `Compound::start_element` writes afterwards.
+    fn probe_comment<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if let Some((_, ref mut pretty)) = self.pretty {
+            pretty.comment = comment_of(value)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_comment(&mut self) -> Result<()> {
+        let comment = match self.pretty {
+            Some((_, ref mut pretty)) => pretty.comment.take(),
+            None => None,
+        };
+
+        if let Some(comment) = comment {
+            for line in comment.lines() {
+                let line = format!("// {}", line);
+                self.write_token(Token::Comment, line.trim_end())?;
+                self.new_line()?;
+                self.indent()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the anchor of the `Shared` value which is serialized, or
+    /// a reference to it if it has been written before, and returns
+    /// whether it has been.
+    fn write_anchor(&mut self) -> Result<bool> {
+        let address = take_address()
+            .ok_or_else(|| Error::Message("Shared value without an address".to_owned()))?;
+        if self.sorting > 0 {
+            return Err(Error::SortedSharedValue);
+        }
+
+        let next = self.anchors.len();
+        let id = *self.anchors.entry(address).or_insert(next);
+        if id == next {
+            write!(self.output, "&{} ", id)?;
+
+            Ok(false)
+        } else {
+            write!(self.output, "*{}", id)?;
+
+            Ok(true)
+        }
+    }
+
+    /// Forgets the anchors written after the first `len`,
+    /// which have been discarded.
+    fn forget_anchors(&mut self, len: usize) {
+        if self.anchors.len() > len {
+            self.anchors.retain(|_, id| *id < len);
+        }
+    }
+
+    /// Like `capture_inline`, but without the formatter,
+    /// for looking at what `value` is serialized as.
+    fn probe<T>(&mut self, value: &T, width: usize) -> Result<Option<Vec<u8>>>
+    where
+        T: ?Sized + Serialize,
+    {
+        let formatter = self.formatter.take();
+        let anchors = self.anchors.len();
+        let captured = self.capture_inline(value, width);
+        self.formatter = formatter;
+        self.forget_anchors(anchors);
+
+        captured
+    }
+
+    /// Tries to serialize `value` on a single line within `width` columns
+    /// and returns the output if it fits; nothing is written.
+    ///
+    /// Errors other than the value not fitting are returned.
+    fn capture_inline<T>(&mut self, value: &T, width: usize) -> Result<Option<Vec<u8>>>
+    where
+        T: ?Sized + Serialize,
+    {
+        let (restore, field, indent, depth) = match self.pretty {
+            Some((_, ref mut pretty)) => {
+                let restore = pretty.save_layout();
+                pretty.inline = true;
+
+                (
+                    restore,
+                    (pretty.field, pretty.redact),
+                    pretty.indent,
+                    pretty.sequence_index.len(),
+                )
+            }
+            None => return Ok(None),
+        };
+
+        let (nesting, sorting) = (self.depth, self.sorting);
+        let anchors = self.anchors.len();
+        let captures = self.output.capture_depth();
+        self.output.start_capture(Some(width));
+        let res = value.serialize(&mut *self);
+        self.depth = nesting;
+        self.sorting = sorting;
+        if res.is_err() {
+            self.forget_anchors(anchors);
+        }
+        while self.output.capture_depth() > captures + 1 {
+            self.output.end_capture();
+        }
+        let overflowed = self.output.overflowed();
+        let captured = self.output.end_capture();
+
+        // A failed attempt may have bailed out in the middle of a value
+        if let Some((_, ref mut pretty)) = self.pretty {
+            pretty.restore_layout(restore);
+            pretty.field = field.0;
+            pretty.redact = field.1;
+            pretty.indent = indent;
+            pretty.sequence_index.truncate(depth);
+        }
+
+        match res {
+            Ok(()) => Ok(Some(captured)),
+            Err(_) if overflowed => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn serialize_float<F: Display>(&mut self, v: F, finite: bool) -> Result<()> {
+        if self.redact()? {
+            return Ok(());
+        }
+
+        if !finite && !self.non_finite_floats {
+            return Err(Error::NonFiniteFloat);
+        }
+
+        self.mark_number();
+
+        let (precision, decimal_point) = match self.pretty {
+            Some((ref config, _)) => (config.float_precision, config.float_decimal_point),
+            None => (None, false),
+        };
+
+        let mut s = match precision {
+            Some(precision) => format!("{:.*}", precision, v),
+            None => v.to_string(),
+        };
+        // `inf` and `NaN` have no decimal point to add, and `-0` needs
+        // one, as it would be read as the integer `0` otherwise
+        if (decimal_point || s == "-0") && finite && !s.contains('.') {
+            s.push_str(".0");
+        }
+        self.write_token(Token::Number, &s)?;
+
+        Ok(())
+    }
+
+    /// Writes `value` as a hex block, with `per_line` bytes per line
+    /// if it doesn't fit on one line.
+    fn serialize_hex_bytes(&mut self, value: &[u8], per_line: usize) -> Result<()> {
+        let layout = self.layout();
+
+        self.output.write_all(b"x\"")?;
+        if layout == Layout::Multiline && value.len() > per_line {
+            self.start_indent()?;
+            for line in value.chunks(per_line) {
+                self.indent()?;
+                self.write_hex(line, " ")?;
+                self.new_line()?;
+            }
+            self.end_indent()?;
+        } else {
+            self.write_hex(value, if layout == Layout::Compact { "" } else { " " })?;
+        }
+        self.output.write_all(b"\"")?;
+
+        Ok(())
+    }
+
+    /// Writes `value` as a byte string, escaping everything
+    /// but printable ASCII characters.
+    fn serialize_byte_string(&mut self, value: &[u8]) -> Result<()> {
+        use std::fmt::Write;
+
+        let mut s = String::with_capacity(value.len() + 3);
+        s.push_str("b\"");
+        for &byte in value {
+            match byte {
+                b'"' => s.push_str("\\\""),
+                b'\\' => s.push_str("\\\\"),
+                b'\n' => s.push_str("\\n"),
+                b'\r' => s.push_str("\\r"),
+                b'\t' => s.push_str("\\t"),
+                b'\0' => s.push_str("\\0"),
+                b' '..=b'~' => s.push(byte as char),
+                _ => {
+                    let _ = write!(s, "\\x{:02x}", byte);
+                }
+            }
+        }
+        s.push('"');
+        self.write_token(Token::Str, &s)?;
+
+        Ok(())
+    }
+
+    fn write_hex(&mut self, bytes: &[u8], separator: &str) -> Result<()> {
+        for (i, byte) in bytes.iter().enumerate() {
+            if i > 0 {
+                self.output.write_all(separator.as_bytes())?;
+            }
+            write!(self.output, "{:02x}", byte)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes `value` enclosed in `quote`, escaping it as configured.
+    fn serialize_escaped(&mut self, value: &str, quote: char) -> Result<()> {
+        let s = self.escaped(value, quote);
+        let token = if quote == '"' { Token::Str } else { Token::Char };
+        self.write_token(token, &s)?;
+
+        Ok(())
+    }
+
+    fn escape(&self) -> Escape {
+        self.pretty
+            .as_ref()
+            .map(|(config, _)| config.escape)
+            .unwrap_or(Escape::Utf8)
+    }
+
+    /// Returns `value` enclosed in `quote`, escaped as configured.
+    fn escaped(&self, value: &str, quote: char) -> String {
+        use std::fmt::Write;
+
+        let escape = self.escape();
+
+        let mut s = String::with_capacity(value.len() + 2);
+        s.push(quote);
+        for c in value.chars() {
+            match c {
+                '\\' => s.push_str("\\\\"),
+                c if c == quote => {
+                    s.push('\\');
+                    s.push(c);
+                }
+                c if escape == Escape::Minimal => s.push(c),
+                '\n' => s.push_str("\\n"),
+                '\r' => s.push_str("\\r"),
+                '\t' => s.push_str("\\t"),
+                '\0' => s.push_str("\\0"),
+                c if c.is_ascii_control() => {
+                    let _ = write!(s, "\\x{:02x}", c as u32);
+                }
+                c if c.is_control() || (escape == Escape::Ascii && !c.is_ascii()) => {
+                    let _ = write!(s, "\\u{{{:x}}}", c as u32);
+                }
+                c => s.push(c),
+            }
+        }
+        s.push(quote);
+
+        s
+    }
+
+    /// Returns `value` as a raw string, with as few `#` as possible,
+    /// unless it has characters which have to be escaped.
+    fn raw_string(&self, value: &str) -> Option<String> {
+        let escape = self.escape();
+        let must_escape = |c: char| c.is_control() || (escape == Escape::Ascii && !c.is_ascii());
+
+        if escape != Escape::Minimal && value.chars().any(must_escape) {
+            return None;
+        }
+
+        // Enough to not end the string at any `"` inside of it
+        let hashes = value
+            .split('"')
+            .skip(1)
+            .map(|rest| rest.bytes().take_while(|&b| b == b'#').count() + 1)
+            .max()
+            .unwrap_or(0);
+        let hashes = "#".repeat(hashes);
+
+        Some(format!("r{}\"{}\"{}", hashes, value, hashes))
+    }
+}
+
+impl<'a, W: io::Write> ser::Serializer for &'a mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Compound<'a, W>;
+    type SerializeTuple = Compound<'a, W>;
+    type SerializeTupleStruct = Compound<'a, W>;
+    type SerializeTupleVariant = Compound<'a, W>;
+    type SerializeMap = Compound<'a, W>;
+    type SerializeStruct = Compound<'a, W>;
+    type SerializeStructVariant = Compound<'a, W>;
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        if self.redact()? {
+            return Ok(());
+        }
+
+        self.write_token(Token::Bool, if v { "true" } else { "false" })?;
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        if self.redact()? {
+            return Ok(());
+        }
+
+        self.mark_number();
+        self.write_token(Token::Number, &v.to_string())?;
+        Ok(())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        if self.redact()? {
+            return Ok(());
+        }
+
+        self.mark_number();
+        self.write_token(Token::Number, &v.to_string())?;
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.serialize_u128(v as u128)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        if self.redact()? {
+            return Ok(());
+        }
+
+        self.mark_number();
+
+        let radix = self.pretty
+            .as_ref()
+            .map(|(config, _)| config.unsigned_radix)
+            .unwrap_or(Radix::Decimal);
+
+        let s = match radix {
+            Radix::Decimal => v.to_string(),
+            Radix::Hexadecimal => format!("0x{:X}", v),
+            Radix::Octal => format!("0o{:o}", v),
+            Radix::Binary => format!("0b{:b}", v),
+        };
+        self.write_token(Token::Number, &s)?;
+
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        // Drops the sign of `-0.0`
+        let v = if self.canonical && v == 0.0 { 0.0 } else { v };
+
+        self.serialize_float(v, v.is_finite())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        let v = if self.canonical && v == 0.0 { 0.0 } else { v };
+
+        self.serialize_float(v, v.is_finite())
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        if self.redact()? {
+            return Ok(());
+        }
+
+        let mut buf = [0; 4];
+
+        self.serialize_escaped(v.encode_utf8(&mut buf), '\'')
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        if self.raw_value {
+            self.raw_value = false;
+            if self.redact()? {
+                return Ok(());
+            }
+            self.output.write_all(v.as_bytes())?;
+
+            return Ok(());
+        }
+
+        if self.redact()? {
+            return Ok(());
+        }
+
+        let escaped = self.escaped(v, '"');
+
+        let raw_strings = self.pretty.as_ref().map(|(config, _)| config.raw_strings);
+        if raw_strings == Some(true) {
+            if let Some(raw) = self.raw_string(v) {
+                if raw.len() < escaped.len() {
+                    self.write_token(Token::Str, &raw)?;
+
+                    return Ok(());
+                }
+            }
+        }
+
+        self.write_token(Token::Str, &escaped)?;
+
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        use serde::ser::SerializeSeq;
+
+        if self.redact()? {
+            return Ok(());
+        }
+
+        let base64_bytes = self.pretty.as_ref().map(|(config, _)| config.base64_bytes);
+        if base64_bytes == Some(true) {
+            let s = format!("\"{}\"", base64::encode(v));
+
+            return self.write_token(Token::Str, &s);
+        }
+
+        let byte_strings = self.pretty.as_ref().map(|(config, _)| config.byte_strings);
+        if byte_strings == Some(true) {
+            return self.serialize_byte_string(v);
+        }
+
+        let hex_bytes = self.pretty.as_ref().and_then(|(config, _)| config.hex_bytes);
+        if let Some(per_line) = hex_bytes {
+            return self.serialize_hex_bytes(v, per_line.max(1));
+        }
+
+        let mut seq = self.serialize_seq(Some(v.len()))?;
+        for byte in v {
+            seq.serialize_element(byte)?;
+        }
+        seq.end()
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        // `Some(None)` would be read as `None` without its `Some`s
+        let somes = self.implicit_somes();
+        for _ in 0..somes {
+            self.write_token(Token::Identifier, "Some")?;
+            self.output.write_all(b"(")?;
+        }
 
-        self.start_indent();
+        self.write_token(Token::Identifier, "None")?;
+
+        for _ in 0..somes {
+            self.output.write_all(b")")?;
+        }
 
         if let Some((_, ref mut pretty)) = self.pretty {
-            pretty.sequence_index.push(0);
+            pretty.wrote_none = somes == 0;
         }
 
-        Ok(self)
+        Ok(())
     }
 
-    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple> {
-        self.output += "(";
+    fn serialize_some<T>(self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let somes = self.implicit_somes();
+        self.enter()?;
+
+        if self.implicit_some() {
+            let depth = self.depth;
+            let outer = match self.pretty {
+                Some((_, ref mut pretty)) => pretty.implicit_somes.replace((depth, somes + 1)),
+                None => None,
+            };
+            let res = value.serialize(&mut *self);
+            if let Some((_, ref mut pretty)) = self.pretty {
+                pretty.implicit_somes = outer;
+            }
+            res?;
+        } else {
+            self.write_token(Token::Identifier, "Some")?;
+            self.output.write_all(b"(")?;
+            value.serialize(&mut *self)?;
+            self.output.write_all(b")")?;
+        }
+
+        self.leave();
+        Ok(())
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        self.output.write_all(b"()")?;
+
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<()> {
+        if self.writes_struct_name() {
+            self.write_token(Token::Identifier, name)?;
+
+            Ok(())
+        } else {
+            self.serialize_unit()
+        }
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, _: u32, variant: &'static str) -> Result<()> {
+        self.write_token(Token::Identifier, variant)?;
+
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if name == SKIP_DEFAULT || name == SKIP_DEFAULT_DEFAULT {
+            if let Some((_, ref mut pretty)) = self.pretty {
+                pretty.wrote_default = name == SKIP_DEFAULT_DEFAULT;
+            }
+
+            return value.serialize(&mut *self);
+        }
+
+        if name == RAW_VALUE {
+            self.raw_value = true;
+            let result = value.serialize(&mut *self);
+            self.raw_value = false;
+
+            return result;
+        }
+
+        let struct_name = self.writes_struct_name();
+        self.enter()?;
+
+        if self.unwrap_newtypes() {
+            value.serialize(&mut *self)?;
+        } else {
+            if struct_name {
+                self.write_token(Token::Identifier, name)?;
+            }
+
+            self.output.write_all(b"(")?;
+            value.serialize(&mut *self)?;
+            self.output.write_all(b")")?;
+        }
+
+        self.leave();
+        Ok(())
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.enter()?;
+        self.write_token(Token::Identifier, variant)?;
+        self.output.write_all(b"(")?;
+
+        value.serialize(&mut *self)?;
 
-        if self.separate_tuple_members() {
-            self.start_indent();
+        self.output.write_all(b")")?;
+        self.leave();
+        Ok(())
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq> {
+        self.enter()?;
+        let restore = self.override_layout(&[])?;
+        self.output.write_all(b"[")?;
+
+        self.start_indent()?;
+
+        if let Some((_, ref mut pretty)) = self.pretty {
+            pretty.sequence_index.push(0);
         }
 
-        Ok(self)
+        let layout = self.layout();
+        Ok(Compound::new(self, layout).restore(restore))
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple> {
+        let restore = self.override_layout(&[])?;
+
+        self.start_tuple(restore)
     }
 
     fn serialize_tuple_struct(
         self,
         name: &'static str,
-        len: usize,
+        _: usize,
     ) -> Result<Self::SerializeTupleStruct> {
-        if self.struct_names {
-            self.output += name;
+        if name == COMMENTED {
+            let layout = self.layout();
+
+            return Ok(Compound::new(self, layout).commented());
+        }
+
+        if name == SHARED {
+            let reference = self.write_anchor()?;
+            let layout = self.layout();
+
+            return Ok(Compound::new(self, layout).shared(reference));
+        }
+
+        let restore = self.override_layout(&[name])?;
+        if self.writes_struct_name() {
+            self.write_token(Token::Identifier, name)?;
         }
 
-        self.serialize_tuple(len)
+        self.start_tuple(restore)
     }
 
     fn serialize_tuple_variant(
         self,
-        _: &'static str,
+        name: &'static str,
         _: u32,
         variant: &'static str,
         _: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        self.output += variant;
-        self.output += "(";
-
-        if self.separate_tuple_members() {
-            self.start_indent();
-        }
+        let restore = self.override_layout(&[name, variant])?;
+        self.write_token(Token::Identifier, variant)?;
 
-        Ok(self)
+        self.start_tuple(restore)
     }
 
-    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
-        self.output += "{";
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        self.enter()?;
+        let restore = self.override_layout(&[])?;
+        self.output.write_all(b"{")?;
 
-        self.start_indent();
+        self.start_indent()?;
 
-        Ok(self)
+        let layout = self.layout();
+        let sort = self.sort_map_keys();
+        let blank_lines = self.blank_lines_in_map(len);
+        Ok(Compound::new(self, layout)
+            .buffered(sort, false)
+            .blank_lines(blank_lines)
+            .restore(restore))
     }
 
     fn serialize_struct(self, name: &'static str, _: usize) -> Result<Self::SerializeStruct> {
-        if self.struct_names {
-            self.output += name;
+        let struct_name = self.writes_struct_name();
+        self.enter()?;
+        let restore = self.override_layout(&[name])?;
+        if struct_name {
+            self.write_token(Token::Identifier, name)?;
         }
-        self.output += "(";
+        self.output.write_all(b"(")?;
 
-        self.start_indent();
+        self.start_indent()?;
 
-        Ok(self)
+        let layout = self.layout();
+        let (sort, align) = (self.sort_struct_fields(), self.align_struct_fields());
+        let blank_lines = self.blank_lines_between_fields();
+        Ok(Compound::new(self, layout)
+            .buffered(sort, align)
+            .blank_lines(blank_lines)
+            .restore(restore)
+            .parens())
     }
 
     fn serialize_struct_variant(
         self,
-        _: &'static str,
+        name: &'static str,
         _: u32,
         variant: &'static str,
         _: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        self.output += variant;
-        self.output += "(";
+        self.enter()?;
+        let restore = self.override_layout(&[name, variant])?;
+        self.write_token(Token::Identifier, variant)?;
+        self.output.write_all(b"(")?;
+
+        self.start_indent()?;
+
+        let layout = self.layout();
+        let (sort, align) = (self.sort_struct_fields(), self.align_struct_fields());
+        let blank_lines = self.blank_lines_between_fields();
+        Ok(Compound::new(self, layout)
+            .buffered(sort, align)
+            .blank_lines(blank_lines)
+            .restore(restore)
+            .parens())
+    }
+}
 
-        self.start_indent();
+/// Serializer for the elements of sequences, tuples, maps and structs.
+///
+/// This is created by the `Serializer` and never has to be used directly.
+pub struct Compound<'a, W: 'a> {
+    ser: &'a mut Serializer<W>,
+    first: bool,
+    layout: Layout,
+    entries: Option<Entries>,
+    commented: bool,
+    /// How many numbers are on the current line of a sequence.
+    numbers_on_line: usize,
+    /// Whether the members of a tuple are on separate lines.
+    separate: bool,
+    /// Whether the elements are surrounded by spaces.
+    spaced: bool,
+    /// Whether multi-line elements are separated by empty lines.
+    blank_lines: bool,
+    /// The next field of a `Shared`.
+    shared: Option<SharedField>,
+    restore: Option<Restore>,
+}
 
-        Ok(self)
-    }
+/// Entries of a map or struct which are written once they're all
+/// complete, so that they can be ordered by their keys or aligned.
+struct Entries {
+    entries: Vec<Entry>,
+    sort: bool,
+    align: bool,
 }
 
-impl<'a> ser::SerializeSeq for &'a mut Serializer {
-    type Ok = ();
-    type Error = Error;
+/// A serialized map or struct entry, including its indentation.
+struct Entry {
+    buf: Vec<u8>,
+    key: Range<usize>,
+    value_start: usize,
+}
 
-    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+impl<'a, W: io::Write> Compound<'a, W> {
+    fn new(ser: &'a mut Serializer<W>, layout: Layout) -> Self {
+        Compound {
+            ser,
+            first: true,
+            layout,
+            entries: None,
+            commented: false,
+            numbers_on_line: 0,
+            separate: false,
+            spaced: false,
+            blank_lines: false,
+            shared: None,
+            restore: None,
+        }
+    }
+
+    fn shared(mut self, reference: bool) -> Self {
+        self.shared = Some(SharedField::Address { reference });
+
+        self
+    }
+
+    /// Serializes the value of a `Shared`, unless its anchor has been
+    /// written before; the address has been handed over instead.
+    fn serialize_shared<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        self.indent();
+        match self.shared {
+            Some(SharedField::Address { reference: false }) => {
+                self.shared = Some(SharedField::Value);
 
-        value.serialize(&mut **self)?;
-        self.output += ",";
+                Ok(())
+            }
+            Some(SharedField::Address { reference: true }) => {
+                self.shared = Some(SharedField::Reference);
 
-        if let Some((ref config, ref mut pretty)) = self.pretty {
-            if pretty.indent < config.depth_limit {
-                if config.enumerate_arrays {
-                    assert!(config.new_line.contains('\n'));
-                    let index = pretty.sequence_index.last_mut().unwrap();
-                    //TODO: when /**/ comments are supported, prepend the index
-                    // to an element instead of appending it.
-                    write!(self.output, "// [{}]", index).unwrap();
-                    *index += 1;
+                Ok(())
+            }
+            Some(SharedField::Value) => self.ser.serialize_value(value),
+            _ => Ok(()),
+        }
+    }
+
+    /// Surrounds the elements of compact structs and tuples
+    /// by spaces if configured.
+    fn parens(mut self) -> Self {
+        self.spaced = self.layout == Layout::Compact && self.ser.compact_spacing().inside_parens;
+
+        self
+    }
+
+    fn blank_lines(mut self, blank_lines: bool) -> Self {
+        self.blank_lines = blank_lines;
+
+        self
+    }
+
+    fn restore(mut self, restore: Option<Restore>) -> Self {
+        self.restore = restore;
+
+        self
+    }
+
+    /// Restores the depth and layout once the value is complete.
+    fn end_value(&mut self) {
+        self.ser.leave();
+
+        if let Some(restore) = self.restore.take() {
+            if let Some((_, ref mut pretty)) = self.ser.pretty {
+                pretty.restore_layout(restore);
+            }
+        }
+    }
+
+    fn commented(mut self) -> Self {
+        self.commented = true;
+
+        self
+    }
+
+    fn buffered(mut self, sort: bool, align: bool) -> Self {
+        // Values can only be aligned if they're on separate lines
+        let align = align && self.layout == Layout::Multiline;
+        if sort {
+            self.ser.sorting += 1;
+        }
+
+        if sort || align {
+            self.entries = Some(Entries {
+                entries: Vec::new(),
+                sort,
+                align,
+            });
+        }
+
+        self
+    }
+
+    fn start_element(&mut self) -> Result<()> {
+        // Buffered entries are separated once they are all complete
+        if !self.first && self.entries.is_none() {
+            self.separator()?;
+        }
+        // Buffered entries may be reordered, so the space before the
+        // first one is written with them
+        if self.first && self.spaced && self.entries.is_none() {
+            self.ser.output.write_all(b" ")?;
+        }
+        self.first = false;
+
+        if self.layout == Layout::Multiline {
+            self.ser.indent()?;
+            self.ser.write_comment()?;
+        }
+
+        Ok(())
+    }
+
+    fn probe_comment<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        match self.layout {
+            Layout::Multiline => self.ser.probe_comment(value),
+            _ => Ok(()),
+        }
+    }
+
+    /// Serializes the value of a `Commented`, whose comment has been
+    /// written by `start_element` already, if there's a line for it.
+    fn serialize_commented<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if !self.first {
+            return self.ser.serialize_value(value);
+        }
+        self.first = false;
+
+        let trial = match self.ser.pretty {
+            Some((_, ref pretty)) => pretty.inline && !pretty.forced_inline,
+            None => false,
+        };
+
+        // Line comments can't be kept on one line, so a value which is
+        // tried on one line is written on multiple ones instead
+        if trial && comment_text(value)?.is_some_and(|c| !c.is_empty()) {
+            return Err(self.ser.output.overflow().into());
+        }
+
+        Ok(())
+    }
+
+    fn separator(&mut self) -> Result<()> {
+        match self.layout {
+            Layout::Multiline => {
+                self.ser.output.write_all(b",")?;
+                if self.blank_lines {
+                    self.ser.new_line()?;
+                }
+                self.ser.new_line()
+            }
+            Layout::Inline => {
+                self.ser.output.write_all(b", ")?;
+
+                Ok(())
+            }
+            Layout::Compact => {
+                // The comma itself follows every element
+                if self.ser.compact_spacing().after_comma {
+                    self.ser.output.write_all(b" ")?;
                 }
-                self.output += &config.new_line;
+
+                Ok(())
+            }
+        }
+    }
+
+    fn start_key(&mut self) -> Result<()> {
+        if let Some(ref mut entries) = self.entries {
+            self.ser.output.start_capture(None);
+            entries.entries.push(Entry {
+                buf: Vec::new(),
+                key: 0..0,
+                value_start: 0,
+            });
+        }
+
+        self.start_element()?;
+
+        let position = self.ser.output.captured_len();
+        if let Some(entry) = self.current_entry() {
+            entry.key.start = position;
+        }
+
+        Ok(())
+    }
+
+    fn end_key(&mut self) {
+        let position = self.ser.output.captured_len();
+        if let Some(entry) = self.current_entry() {
+            entry.key.end = position;
+        }
+    }
+
+    fn end_entry(&mut self) -> Result<()> {
+        self.end_element()?;
+
+        if self.entries.is_some() {
+            let buf = self.ser.output.end_capture();
+            if let Some(entry) = self.current_entry() {
+                entry.buf = buf;
             }
         }
 
         Ok(())
     }
 
-    fn end(self) -> Result<()> {
-        self.end_indent();
+    fn current_entry(&mut self) -> Option<&mut Entry> {
+        self.entries.as_mut().and_then(|e| e.entries.last_mut())
+    }
 
-        if let Some((_, ref mut pretty)) = self.pretty {
-            pretty.sequence_index.pop();
+    fn write_entries(&mut self) -> Result<()> {
+        if let Some(mut entries) = self.entries.take() {
+            if entries.sort {
+                self.ser.sorting -= 1;
+                entries
+                    .entries
+                    .sort_by(|a, b| a.buf[a.key.clone()].cmp(&b.buf[b.key.clone()]));
+            }
+
+            let width = if entries.align {
+                entries.entries.iter().map(Entry::key_width).max()
+            } else {
+                None
+            };
+
+            for (i, entry) in entries.entries.iter().enumerate() {
+                if i > 0 {
+                    self.separator()?;
+                } else if self.spaced {
+                    self.ser.output.write_all(b" ")?;
+                }
+
+                match width {
+                    Some(width) => {
+                        let (key, value) = entry.buf.split_at(entry.value_start);
+                        self.ser.output.write_all(key)?;
+                        for _ in entry.key_width()..width {
+                            self.ser.output.write_all(b" ")?;
+                        }
+                        self.ser.output.write_all(value)?;
+                    }
+                    None => self.ser.output.write_all(&entry.buf)?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finishes the last element, to be called before closing the value.
+    fn end_elements(&mut self) -> Result<()> {
+        self.write_entries()?;
+
+        if self.layout == Layout::Multiline && !self.first {
+            if self.ser.trailing_comma() {
+                self.ser.output.write_all(b",")?;
+            }
+
+            self.ser.new_line()?;
+        }
+
+        if self.spaced && !self.first {
+            self.ser.output.write_all(b" ")?;
         }
 
-        self.output += "]";
         Ok(())
     }
+
+    fn key_separator(&mut self) -> Result<()> {
+        let colon: &[u8] = match self.layout {
+            Layout::Compact if !self.ser.compact_spacing().after_colon => b":",
+            _ => b": ",
+        };
+        self.ser.output.write_all(colon)?;
+
+        let position = self.ser.output.captured_len();
+        if let Some(entry) = self.current_entry() {
+            entry.value_start = position;
+        }
+
+        Ok(())
+    }
+
+    fn end_element(&mut self) -> Result<()> {
+        if self.layout == Layout::Compact {
+            self.ser.output.write_all(b",")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Entry {
+    fn key_width(&self) -> usize {
+        String::from_utf8_lossy(&self.buf[self.key.clone()]).chars().count()
+    }
 }
 
-impl<'a> ser::SerializeTuple for &'a mut Serializer {
+impl<'a, W: io::Write> ser::SerializeSeq for Compound<'a, W> {
     type Ok = ();
     type Error = Error;
 
@@ -477,42 +2154,93 @@ impl<'a> ser::SerializeTuple for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        if self.separate_tuple_members() {
-            self.indent();
+        let per_line = match self.layout {
+            Layout::Multiline => self.ser.numbers_per_line(),
+            _ => None,
+        };
+        let number = per_line.is_some() && self.ser.is_number(value)?;
+        let start_line = match per_line {
+            Some(per_line) if number => self.numbers_on_line % per_line.max(1) == 0,
+            _ => true,
+        };
+
+        if start_line {
+            self.probe_comment(value)?;
+            self.start_element()?;
+        } else {
+            self.ser.output.write_all(b", ")?;
+        }
+        self.numbers_on_line = if number { self.numbers_on_line + 1 } else { 0 };
+
+        let mut index = None;
+        if let Some((ref config, ref mut pretty)) = self.ser.pretty {
+            if self.layout == Layout::Multiline && config.enumerate_arrays {
+                let next = pretty.sequence_index.last_mut().unwrap();
+                if start_line {
+                    index = Some(*next);
+                }
+                *next += 1;
+            }
+        }
+        // The index goes on its own line before the element, like a comment
+        if let Some(index) = index {
+            self.ser.write_token(Token::Comment, &format!("// [{}]", index))?;
+            self.ser.new_line()?;
+            self.ser.indent()?;
         }
 
-        value.serialize(&mut **self)?;
-        self.output += ",";
+        self.ser.serialize_value(value)?;
 
-        if let Some((ref config, ref pretty)) = self.pretty {
-            if pretty.indent < config.depth_limit {
-                self.output += if self.separate_tuple_members() {
-                    &config.new_line
-                } else {
-                    " "
-                };
-            }
+        self.end_element()
+    }
+
+    fn end(mut self) -> Result<()> {
+        self.end_elements()?;
+
+        self.ser.end_indent()?;
+
+        if let Some((_, ref mut pretty)) = self.ser.pretty {
+            pretty.sequence_index.pop();
         }
 
+        self.ser.output.write_all(b"]")?;
+        self.end_value();
         Ok(())
     }
+}
 
-    fn end(self) -> Result<()> {
-        if self.separate_tuple_members() {
-            self.end_indent();
-        } else if self.is_pretty() {
-            self.output.pop();
-            self.output.pop();
+impl<'a, W: io::Write> ser::SerializeTuple for Compound<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.probe_comment(value)?;
+        self.start_element()?;
+
+        self.ser.serialize_value(value)?;
+
+        self.end_element()
+    }
+
+    fn end(mut self) -> Result<()> {
+        self.end_elements()?;
+
+        if self.separate {
+            self.ser.end_indent()?;
         }
 
-        self.output += ")";
+        self.ser.output.write_all(b")")?;
+        self.end_value();
 
         Ok(())
     }
 }
 
 // Same thing but for tuple structs.
-impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
+impl<'a, W: io::Write> ser::SerializeTupleStruct for Compound<'a, W> {
     type Ok = ();
     type Error = Error;
 
@@ -520,15 +2248,27 @@ impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
+        if self.commented {
+            return self.serialize_commented(value);
+        }
+
+        if self.shared.is_some() {
+            return self.serialize_shared(value);
+        }
+
         ser::SerializeTuple::serialize_element(self, value)
     }
 
     fn end(self) -> Result<()> {
+        if self.commented || self.shared.is_some() {
+            return Ok(());
+        }
+
         ser::SerializeTuple::end(self)
     }
 }
 
-impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+impl<'a, W: io::Write> ser::SerializeTupleVariant for Compound<'a, W> {
     type Ok = ();
     type Error = Error;
 
@@ -544,7 +2284,7 @@ impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeMap for &'a mut Serializer {
+impl<'a, W: io::Write> ser::SerializeMap for Compound<'a, W> {
     type Ok = ();
     type Error = Error;
 
@@ -552,42 +2292,47 @@ impl<'a> ser::SerializeMap for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        self.indent();
+        self.start_key()?;
+
+        // Redacted keys would all be the same
+        let redact = match self.ser.pretty {
+            Some((_, ref mut pretty)) => mem::replace(&mut pretty.redact, false),
+            None => false,
+        };
+        let res = key.serialize(&mut *self.ser);
+        if let Some((_, ref mut pretty)) = self.ser.pretty {
+            pretty.redact = redact;
+        }
+        res?;
 
-        key.serialize(&mut **self)
+        self.end_key();
+
+        Ok(())
     }
 
     fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        self.output += ":";
+        self.key_separator()?;
 
-        if self.is_pretty() {
-            self.output += " ";
-        }
-
-        value.serialize(&mut **self)?;
-        self.output += ",";
-
-        if let Some((ref config, ref pretty)) = self.pretty {
-            if pretty.indent < config.depth_limit {
-                self.output += &config.new_line;
-            }
-        }
+        self.ser.serialize_value(value)?;
 
-        Ok(())
+        self.end_entry()
     }
 
-    fn end(self) -> Result<()> {
-        self.end_indent();
+    fn end(mut self) -> Result<()> {
+        self.end_elements()?;
 
-        self.output += "}";
+        self.ser.end_indent()?;
+
+        self.ser.output.write_all(b"}")?;
+        self.end_value();
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeStruct for &'a mut Serializer {
+impl<'a, W: io::Write> ser::SerializeStruct for Compound<'a, W> {
     type Ok = ();
     type Error = Error;
 
@@ -595,36 +2340,55 @@ impl<'a> ser::SerializeStruct for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        self.indent();
-
-        self.output += key;
-        self.output += ":";
+        if self.ser.omit_none_fields() && self.ser.is_none(value)? {
+            return Ok(());
+        }
 
-        if self.is_pretty() {
-            self.output += " ";
+        if self.ser.skip_default_fields() && self.ser.is_default(value)? {
+            return Ok(());
         }
 
-        value.serialize(&mut **self)?;
-        self.output += ",";
+        self.probe_comment(value)?;
+        self.start_key()?;
 
-        if let Some((ref config, ref pretty)) = self.pretty {
-            if pretty.indent < config.depth_limit {
-                self.output += &config.new_line;
+        self.ser.write_token(Token::Field, key)?;
+        self.end_key();
+        self.key_separator()?;
+
+        let redact = match self.ser.pretty {
+            Some((ref config, ref mut pretty)) => {
+                let redact = pretty.redact;
+                pretty.field = Some(key);
+                pretty.redact |= config
+                    .redacted_fields
+                    .iter()
+                    .any(|pattern| matches_pattern(pattern.as_bytes(), key.as_bytes()));
+
+                redact
             }
+            None => false,
+        };
+        self.ser.serialize_value(value)?;
+        if let Some((_, ref mut pretty)) = self.ser.pretty {
+            pretty.field = None;
+            pretty.redact = redact;
         }
 
-        Ok(())
+        self.end_entry()
     }
 
-    fn end(self) -> Result<()> {
-        self.end_indent();
+    fn end(mut self) -> Result<()> {
+        self.end_elements()?;
+
+        self.ser.end_indent()?;
 
-        self.output += ")";
+        self.ser.output.write_all(b")")?;
+        self.end_value();
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
+impl<'a, W: io::Write> ser::SerializeStructVariant for Compound<'a, W> {
     type Ok = ();
     type Error = Error;
 
@@ -640,6 +2404,18 @@ impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
     }
 }
 
+/// Matches a field name against a pattern, in which `*`
+/// stands for any number of characters.
+fn matches_pattern(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        Some((b'*', rest)) => (0..=name.len()).any(|i| matches_pattern(rest, &name[i..])),
+        Some((c, rest)) => name
+            .split_first()
+            .is_some_and(|(n, name)| c == n && matches_pattern(rest, name)),
+        None => name.is_empty(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -741,4 +2517,1343 @@ mod tests {
     fn test_escape() {
         assert_eq!(to_string(&r#""Quoted""#).unwrap(), r#""\"Quoted\"""#);
     }
+
+    #[test]
+    fn test_writer() {
+        let mut buf = Vec::new();
+        to_writer(&mut buf, &MyStruct { x: 4.0, y: 7.0 }).unwrap();
+
+        assert_eq!(buf, b"(x:4,y:7,)");
+    }
+
+    #[test]
+    fn test_writer_io_error() {
+        struct Full;
+
+        impl io::Write for Full {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk full"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        assert_eq!(
+            to_writer(Full, &MyEnum::A),
+            Err(Error::IoError("disk full".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_writer_streaming() {
+        // Large writes would mean that the output was buffered
+        struct Small(usize);
+
+        impl io::Write for Small {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                assert!(buf.len() <= 32, "wrote {} bytes at once", buf.len());
+                self.0 += buf.len();
+
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let value: Vec<_> = (0..1000).map(|i| MyStruct { x: i as f32, y: 0.5 }).collect();
+
+        let mut writer = Small(0);
+        to_writer(&mut writer, &value).unwrap();
+        assert!(writer.0 > 10_000);
+
+        let config = PrettyConfig {
+            max_width: Some(30),
+            ..PrettyConfig::default()
+        };
+        let mut writer = Small(0);
+        to_writer_pretty(&mut writer, &value, config).unwrap();
+        assert!(writer.0 > 10_000);
+    }
+
+    #[test]
+    fn test_pretty() {
+        use std::collections::BTreeMap;
+
+        #[derive(Serialize)]
+        struct Level {
+            name: &'static str,
+            size: (u32, u32),
+            spawns: Vec<MyStruct>,
+            lookup: BTreeMap<u8, bool>,
+        }
+
+        let level = Level {
+            name: "Start",
+            size: (10, 20),
+            spawns: vec![MyStruct { x: 1.0, y: 2.0 }],
+            lookup: vec![(1, true), (2, false)].into_iter().collect(),
+        };
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&level, config).unwrap(),
+            "(
+    name: \"Start\",
+    size: (10, 20),
+    spawns: [
+        (
+            x: 1,
+            y: 2,
+        ),
+    ],
+    lookup: {
+        1: true,
+        2: false,
+    },
+)"
+        );
+    }
+
+    #[test]
+    fn test_writer_pretty() {
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            ..PrettyConfig::default()
+        };
+        let mut buf = Vec::new();
+        to_writer_pretty(&mut buf, &[1, 2], config).unwrap();
+
+        assert_eq!(buf, b"(1, 2)");
+    }
+
+    #[test]
+    fn test_indentor() {
+        use std::collections::BTreeMap;
+
+        let mut map = BTreeMap::new();
+        map.insert("a", vec![(MyEnum::D { a: 1, b: 2 }, (0, 1))]);
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            indentor: "\t".to_owned(),
+            separate_tuple_members: true,
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&map, config).unwrap(),
+            "{
+\t\"a\": [
+\t\t(
+\t\t\tD(
+\t\t\t\ta: 1,
+\t\t\t\tb: 2,
+\t\t\t),
+\t\t\t(
+\t\t\t\t0,
+\t\t\t\t1,
+\t\t\t),
+\t\t),
+\t],
+}"
+        );
+    }
+
+    #[test]
+    fn test_new_line() {
+        let config = PrettyConfig {
+            new_line: "\r\n".to_owned(),
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&vec![MyStruct { x: 1.0, y: 2.0 }], config).unwrap(),
+            "[\r\n    (\r\n        x: 1,\r\n        y: 2,\r\n    ),\r\n]"
+        );
+    }
+
+    #[test]
+    fn test_separate_tuple_members() {
+        #[derive(Serialize)]
+        struct Transform((f32, f32), MyEnum);
+
+        let value = Transform((1.0, 2.0), MyEnum::C(true, 0.5));
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config.clone()).unwrap(),
+            "((1, 2), C(true, 0.5))"
+        );
+
+        let config = PrettyConfig {
+            separate_tuple_members: true,
+            ..config
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config).unwrap(),
+            "(
+    (
+        1,
+        2,
+    ),
+    C(
+        true,
+        0.5,
+    ),
+)"
+        );
+    }
+
+    #[test]
+    fn test_enumerate_arrays() {
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            enumerate_arrays: true,
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&vec![vec![1, 2], vec![]], config).unwrap(),
+            "[
+    // [0]
+    [
+        // [0]
+        1,
+        // [1]
+        2,
+    ],
+    // [1]
+    [
+    ],
+]"
+        );
+    }
+
+    #[test]
+    fn test_max_width_errors() {
+        use std::cell::Cell;
+
+        /// Fails after writing its first element, counting its attempts.
+        struct Failing<'a>(&'a Cell<usize>);
+
+        impl<'a> Serialize for Failing<'a> {
+            fn serialize<S: ser::Serializer>(&self, serializer: S) -> StdResult<S::Ok, S::Error> {
+                use serde::ser::{Error, SerializeSeq};
+
+                self.0.set(self.0.get() + 1);
+                let mut seq = serializer.serialize_seq(None)?;
+                seq.serialize_element(&1)?;
+
+                Err(S::Error::custom("broken"))
+            }
+        }
+
+        let attempts = Cell::new(0);
+        let config = PrettyConfig {
+            max_width: Some(80),
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&[Failing(&attempts)], config.clone()),
+            Err(Error::Message("broken".to_owned()))
+        );
+        assert_eq!(attempts.get(), 1);
+
+        let config = PrettyConfig {
+            recursion_limit: Some(2),
+            ..config
+        };
+
+        assert_eq!(
+            to_string_pretty(&[[[1]]], config),
+            Err(Error::CircularOrTooDeep)
+        );
+    }
+
+    #[test]
+    fn test_max_width() {
+        use std::collections::BTreeMap;
+
+        #[derive(Serialize)]
+        struct Entity {
+            pos: MyStruct,
+            tags: Vec<&'static str>,
+            empty: BTreeMap<u8, u8>,
+        }
+
+        let entities = vec![
+            Entity {
+                pos: MyStruct { x: 1.0, y: 2.0 },
+                tags: vec!["player"],
+                empty: BTreeMap::new(),
+            },
+            Entity {
+                pos: MyStruct { x: 3.0, y: 4.0 },
+                tags: vec!["a rather long tag", "and another long one"],
+                empty: BTreeMap::new(),
+            },
+        ];
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            enumerate_arrays: true,
+            max_width: Some(40),
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&entities, config).unwrap(),
+            "[
+    // [0]
+    (
+        pos: (x: 1, y: 2),
+        tags: [\"player\"],
+        empty: {},
+    ),
+    // [1]
+    (
+        pos: (x: 3, y: 4),
+        tags: [
+            // [0]
+            \"a rather long tag\",
+            // [1]
+            \"and another long one\",
+        ],
+        empty: {},
+    ),
+]"
+        );
+
+        let config = PrettyConfig {
+            max_width: Some(80),
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&MyEnum::D { a: 1, b: 2 }, config).unwrap(),
+            "D(a: 1, b: 2)"
+        );
+    }
+
+    #[test]
+    fn test_sort_map_keys() {
+        use std::collections::HashMap;
+
+        let map: HashMap<_, _> = (0..20u8).map(|i| (format!("key{:02}", i), i)).collect();
+        let expected = (0..20u8).fold(String::from("{"), |s, i| {
+            s + &format!("\"key{:02}\":{},", i, i)
+        }) + "}";
+        let config = PrettyConfig {
+            depth_limit: 0,
+            sort_map_keys: true,
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(to_string_pretty(&map, config).unwrap(), expected);
+
+        let mut map = HashMap::new();
+        map.insert('b', vec![MyEnum::A]);
+        map.insert('a', vec![]);
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            sort_map_keys: true,
+            max_width: Some(20),
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&map, config.clone()).unwrap(),
+            "{'a': [], 'b': [A]}"
+        );
+        assert_eq!(
+            to_string_pretty(&vec![map], config).unwrap(),
+            "[
+    {
+        'a': [],
+        'b': [A],
+    },
+]"
+        );
+    }
+
+    #[test]
+    fn test_sort_struct_fields() {
+        #[derive(Serialize)]
+        struct Unsorted {
+            c: u8,
+            a: MyStruct,
+            b: (),
+        }
+
+        let value = Unsorted {
+            c: 3,
+            a: MyStruct { x: 1.0, y: 2.0 },
+            b: (),
+        };
+        let config = PrettyConfig {
+            max_width: Some(80),
+            sort_struct_fields: true,
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config).unwrap(),
+            "(a: (x: 1, y: 2), b: (), c: 3)"
+        );
+    }
+
+    #[test]
+    fn test_struct_names() {
+        #[derive(Serialize)]
+        struct Player {
+            health: u32,
+            pos: MyStruct,
+            unit: EmptyStruct1,
+        }
+
+        let player = Player {
+            health: 100,
+            pos: MyStruct { x: 1.0, y: 2.0 },
+            unit: EmptyStruct1,
+        };
+
+        let mut s = Serializer::with_config(None);
+        s.set_struct_names(true);
+        player.serialize(&mut s).unwrap();
+        assert_eq!(
+            s.into_output_string(),
+            "Player(health:100,pos:MyStruct(x:1,y:2,),unit:EmptyStruct1,)"
+        );
+
+        #[allow(deprecated)]
+        let mut s = Serializer::new(None, true);
+        player.serialize(&mut s).unwrap();
+        assert_eq!(
+            s.into_output_string(),
+            "Player(health:100,pos:MyStruct(x:1,y:2,),unit:EmptyStruct1,)"
+        );
+
+        let config = PrettyConfig {
+            max_width: Some(80),
+            struct_names: true,
+            ..PrettyConfig::default()
+        };
+        assert_eq!(
+            to_string_pretty(&player, config.clone()).unwrap(),
+            "Player(health: 100, pos: MyStruct(x: 1, y: 2), unit: EmptyStruct1)"
+        );
+
+        let config = PrettyConfig {
+            struct_names: false,
+            root_struct_name: true,
+            ..config
+        };
+        assert_eq!(
+            to_string_pretty(&player, config.clone()).unwrap(),
+            "Player(health: 100, pos: (x: 1, y: 2), unit: ())"
+        );
+        assert_eq!(
+            to_string_pretty(&vec![EmptyStruct1], config.clone()).unwrap(),
+            "[()]"
+        );
+        assert_eq!(
+            to_string_pretty(&EmptyStruct1, config).unwrap(),
+            "EmptyStruct1"
+        );
+    }
+
+    #[test]
+    fn test_trailing_comma() {
+        use std::collections::BTreeMap;
+
+        let mut map = BTreeMap::new();
+        map.insert(1, MyEnum::C(true, 0.5));
+        map.insert(2, MyEnum::D { a: 1, b: 2 });
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            separate_tuple_members: true,
+            trailing_comma: false,
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&(map, Vec::<u8>::new()), config).unwrap(),
+            "(
+    {
+        1: C(
+            true,
+            0.5
+        ),
+        2: D(
+            a: 1,
+            b: 2
+        )
+    },
+    [
+    ]
+)"
+        );
+    }
+
+    #[test]
+    fn test_implicit_some() {
+        #[derive(Serialize)]
+        struct Optional {
+            a: Option<u8>,
+            b: Option<MyEnum>,
+            c: Option<MyStruct>,
+            d: Option<Option<()>>,
+        }
+
+        let value = Optional {
+            a: Some(1),
+            b: None,
+            c: Some(MyStruct { x: 1.0, y: 2.0 }),
+            d: Some(None),
+        };
+        let config = PrettyConfig {
+            max_width: Some(80),
+            implicit_some: true,
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config.clone()).unwrap(),
+            "(a: 1, b: None, c: (x: 1, y: 2), d: Some(None))"
+        );
+        assert_eq!(
+            to_string_pretty(&vec![Some(Some(None::<u8>)), Some(Some(Some(1)))], config.clone())
+                .unwrap(),
+            "[Some(Some(None)), 1]"
+        );
+
+        let config = PrettyConfig {
+            implicit_some: false,
+            omit_none_fields: true,
+            ..config
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config.clone()).unwrap(),
+            "(a: Some(1), c: Some((x: 1, y: 2)), d: Some(None))"
+        );
+
+        let config = PrettyConfig {
+            max_width: None,
+            new_line: "\n".to_owned(),
+            ..config
+        };
+
+        assert_eq!(to_string_pretty(&[MyEnum::A], config).unwrap(), "(A)");
+    }
+
+    #[test]
+    fn test_float_format() {
+        let value = (0.1 + 0.2, 1.0f32, 2.5f64, -3.0f64);
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config.clone()).unwrap(),
+            "(0.30000000000000004, 1, 2.5, -3)"
+        );
+
+        let config = PrettyConfig {
+            float_decimal_point: true,
+            ..config
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config.clone()).unwrap(),
+            "(0.30000000000000004, 1.0, 2.5, -3.0)"
+        );
+
+        let config = PrettyConfig {
+            float_precision: Some(3),
+            ..config
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config.clone()).unwrap(),
+            "(0.300, 1.000, 2.500, -3.000)"
+        );
+
+        let config = PrettyConfig {
+            float_precision: Some(0),
+            ..config
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config).unwrap(),
+            "(0.0, 1.0, 2.0, -3.0)"
+        );
+    }
+
+    #[test]
+    fn test_unsigned_radix() {
+        let value = (65280u16, 0u8, 5u64, -5i32);
+        let config = |unsigned_radix| PrettyConfig {
+            unsigned_radix,
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config(Radix::Decimal)).unwrap(),
+            "(65280, 0, 5, -5)"
+        );
+        assert_eq!(
+            to_string_pretty(&value, config(Radix::Hexadecimal)).unwrap(),
+            "(0xFF00, 0x0, 0x5, -5)"
+        );
+        assert_eq!(
+            to_string_pretty(&value, config(Radix::Octal)).unwrap(),
+            "(0o177400, 0o0, 0o5, -5)"
+        );
+        assert_eq!(
+            to_string_pretty(&value, config(Radix::Binary)).unwrap(),
+            "(0b1111111100000000, 0b0, 0b101, -5)"
+        );
+    }
+
+    #[test]
+    fn test_escape_strategies() {
+        let value = ("\"Grüße\"\n\t\\\u{7}", '\'', 'ä');
+        let config = |escape| PrettyConfig {
+            escape,
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config(Escape::Minimal)).unwrap(),
+            "(\"\\\"Grüße\\\"\n\t\\\\\u{7}\", '\\'', 'ä')"
+        );
+        assert_eq!(
+            to_string_pretty(&value, config(Escape::Utf8)).unwrap(),
+            "(\"\\\"Grüße\\\"\\n\\t\\\\\\x07\", '\\'', 'ä')"
+        );
+        assert_eq!(
+            to_string_pretty(&value, config(Escape::Ascii)).unwrap(),
+            "(\"\\\"Gr\\u{fc}\\u{df}e\\\"\\n\\t\\\\\\x07\", '\\'', '\\u{e4}')"
+        );
+    }
+
+    #[test]
+    fn test_raw_strings() {
+        let config = PrettyConfig {
+            raw_strings: true,
+            ..PrettyConfig::default()
+        };
+        let ser = |value: &str| to_string_pretty(&value, config.clone()).unwrap();
+
+        assert_eq!(ser("plain"), "\"plain\"");
+        assert_eq!(ser(r"C:\temp\new"), r#"r"C:\temp\new""#);
+        assert_eq!(ser(r#"^"\d+\.\d+"$"#), r###"r#"^"\d+\.\d+"$"#"###);
+        assert_eq!(ser(r##""#\d\d\d\d\d"##), r####"r##""#\d\d\d\d\d"##"####);
+        // Only shorter strings are written raw
+        assert_eq!(ser(r#"say "hi""#), r#""say \"hi\"""#);
+        // Escapes which are needed anyway rule out the raw form
+        assert_eq!(ser("\\\\\n"), r#""\\\\\n""#);
+
+        let values = [r"C:\temp", r#"^"\d+\.\d+"$"#, r##""#\d\d\d\d\d"##];
+
+        for value in &values {
+            assert_eq!(::de::from_str(&ser(value)), Ok(value.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_commented() {
+        #[derive(Serialize)]
+        struct Config {
+            size: Commented<(u32, u32)>,
+            levels: Vec<Commented<MyStruct>>,
+            name: Commented<&'static str>,
+        }
+
+        let value = Config {
+            size: Commented::new("Window size\n\nIn pixels", (800, 600)),
+            levels: vec![
+                Commented::new("Start", MyStruct { x: 0.0, y: 0.0 }),
+                Commented::new("", MyStruct { x: 1.0, y: 2.0 }),
+            ],
+            name: Commented::new("Shown in the title", "Game"),
+        };
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            max_width: Some(80),
+            sort_struct_fields: true,
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&vec![Commented::new("", 1)], config.clone()).unwrap(),
+            "[1]"
+        );
+        assert_eq!(
+            to_string_pretty(&value, config).unwrap(),
+            "(
+    levels: [
+        // Start
+        (x: 0, y: 0),
+        (x: 1, y: 2),
+    ],
+    // Shown in the title
+    name: \"Game\",
+    // Window size
+    //
+    // In pixels
+    size: (800, 600),
+)"
+        );
+        assert_eq!(
+            to_string(&value).unwrap(),
+            "(size:(800,600,),levels:[(x:0,y:0,),(x:1,y:2,),],name:\"Game\",)"
+        );
+
+        // Comments are left out of values which are always inline
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            always_inline: vec!["levels".to_owned()],
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config).unwrap(),
+            "(
+    // Window size
+    //
+    // In pixels
+    size: (800, 600),
+    levels: [(x: 0, y: 0), (x: 1, y: 2)],
+    // Shown in the title
+    name: \"Game\",
+)"
+        );
+    }
+
+    #[test]
+    fn test_commented_serializes_once() {
+        use std::cell::Cell;
+
+        struct Counted<'a>(&'a Cell<usize>);
+
+        impl<'a> Serialize for Counted<'a> {
+            fn serialize<S: ser::Serializer>(&self, serializer: S) -> StdResult<S::Ok, S::Error> {
+                self.0.set(self.0.get() + 1);
+
+                serializer.serialize_u8(1)
+            }
+        }
+
+        let count = Cell::new(0);
+        let value = vec![
+            Commented::new("One", Counted(&count)),
+            Commented::new("", Counted(&count)),
+        ];
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config).unwrap(),
+            "[\n    // One\n    1,\n    1,\n]"
+        );
+        assert_eq!(count.get(), 2);
+    }
+
+    #[test]
+    fn test_commented_128_bit() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Big {
+            id: u128,
+            n: i128,
+            ids: Vec<u128>,
+            pair: (u128, i128),
+        }
+
+        let value = Big {
+            id: u128::MAX,
+            n: -5,
+            ids: vec![u128::MAX, 0],
+            pair: (u128::MAX, i128::MIN),
+        };
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            ..PrettyConfig::default()
+        };
+
+        // Fields and elements are probed for comments before they're written
+        let serial = to_string_pretty(&value, config.clone()).unwrap();
+
+        assert_eq!(::de::from_str(&serial), Ok(value));
+        assert_eq!(
+            to_string_pretty(&vec![Commented::new("Max", u128::MAX)], config).unwrap(),
+            "[\n    // Max\n    340282366920938463463374607431768211455,\n]"
+        );
+    }
+
+    #[test]
+    fn test_to_vec() {
+        let value = MyStruct { x: 4.0, y: 7.0 };
+
+        assert_eq!(to_vec(&value).unwrap(), b"(x:4,y:7,)");
+        assert_eq!(
+            to_vec_pretty(&value, PrettyConfig::default()).unwrap(),
+            to_string_pretty(&value, PrettyConfig::default())
+                .unwrap()
+                .into_bytes()
+        );
+    }
+
+    #[test]
+    fn test_fmt_writer() {
+        use std::fmt::Write;
+
+        let mut s = String::from("value = ");
+        to_fmt_writer(&mut s, &("♠", MyEnum::A)).unwrap();
+        s.push(';');
+
+        assert_eq!(s, "value = (\"♠\",A,);");
+
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            ..PrettyConfig::default()
+        };
+        s.clear();
+        to_fmt_writer_pretty(&mut s, &vec![1, 2], config).unwrap();
+
+        assert_eq!(s, "[\n    1,\n    2,\n]");
+
+        struct Fail;
+
+        impl Write for Fail {
+            fn write_str(&mut self, _: &str) -> fmt::Result {
+                Err(fmt::Error)
+            }
+        }
+
+        match to_fmt_writer(Fail, &1) {
+            Err(Error::IoError(_)) => {}
+            other => panic!("expected IoError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_canonical() {
+        use std::collections::HashMap;
+
+        #[derive(Serialize)]
+        struct Unsorted {
+            b: HashMap<&'static str, f64>,
+            a: Vec<Commented<f32>>,
+        }
+
+        let value = Unsorted {
+            b: vec![("z", -0.0), ("y", 1.0), ("x", 0.25)].into_iter().collect(),
+            a: vec![Commented::new("Ignored", 3.0)],
+        };
+
+        assert_eq!(
+            to_string_canonical(&value).unwrap(),
+            "(a:[3.0,],b:{\"x\":0.25,\"y\":1.0,\"z\":0.0,},)"
+        );
+    }
+
+    #[test]
+    fn test_new_into() {
+        let mut buf = String::with_capacity(64);
+
+        for i in 0..3 {
+            buf.clear();
+            let mut s = Serializer::new_into(&mut buf, None);
+            s.set_struct_names(true);
+            MyStruct { x: i as f32, y: 1.0 }.serialize(&mut s).unwrap();
+
+            assert_eq!(buf, format!("MyStruct(x:{},y:1,)", i));
+            assert_eq!(buf.capacity(), 64);
+        }
+    }
+
+    #[test]
+    fn test_skip_default_fields() {
+        #[derive(Serialize)]
+        struct Settings {
+            name: &'static str,
+            scale: SkipDefault<f32>,
+            tags: SkipDefault<Vec<&'static str>>,
+            nested: SkipDefault<Option<SkipDefault<u8>>>,
+        }
+
+        let value = Settings {
+            name: "default",
+            scale: SkipDefault(0.0),
+            tags: SkipDefault(vec![]),
+            nested: SkipDefault(Some(SkipDefault(0))),
+        };
+        let config = PrettyConfig {
+            max_width: Some(80),
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config.clone()).unwrap(),
+            "(name: \"default\", scale: 0, tags: [], nested: Some(0))"
+        );
+        assert_eq!(
+            to_string(&value).unwrap(),
+            "(name:\"default\",scale:0,tags:[],nested:Some(0),)"
+        );
+
+        let config = PrettyConfig {
+            skip_default_fields: true,
+            ..config
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config).unwrap(),
+            "(name: \"default\", nested: Some(0))"
+        );
+    }
+
+    #[test]
+    fn test_align_struct_fields() {
+        #[derive(Serialize)]
+        struct Player {
+            name: &'static str,
+            health: u32,
+            position: MyStruct,
+            id: Commented<u8>,
+        }
+
+        let value = Player {
+            name: "Bob",
+            health: 100,
+            position: MyStruct { x: 1.0, y: 2.0 },
+            id: Commented::new("Unique", 7),
+        };
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            align_struct_fields: true,
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config.clone()).unwrap(),
+            "(
+    name:     \"Bob\",
+    health:   100,
+    position: (
+        x: 1,
+        y: 2,
+    ),
+    // Unique
+    id:       7,
+)"
+        );
+
+        let config = PrettyConfig {
+            max_width: Some(40),
+            sort_struct_fields: true,
+            ..config
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config).unwrap(),
+            "(
+    health:   100,
+    // Unique
+    id:       7,
+    name:     \"Bob\",
+    position: (x: 1, y: 2),
+)"
+        );
+    }
+
+    #[test]
+    fn test_numbers_per_line() {
+        #[derive(Serialize)]
+        struct Mesh {
+            transform: Vec<f32>,
+            indices: Vec<u16>,
+            names: Vec<&'static str>,
+        }
+
+        let value = Mesh {
+            transform: vec![
+                1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 5.0, 0.5, -2.0, 1.0,
+            ],
+            indices: vec![0, 1, 2, 2, 3],
+            names: vec!["a", "b"],
+        };
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            numbers_per_line: Some(4),
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config.clone()).unwrap(),
+            "(
+    transform: [
+        1, 0, 0, 0,
+        0, 1, 0, 0,
+        0, 0, 1, 0,
+        5, 0.5, -2, 1,
+    ],
+    indices: [
+        0, 1, 2, 2,
+        3,
+    ],
+    names: [
+        \"a\",
+        \"b\",
+    ],
+)"
+        );
+
+        let config = PrettyConfig {
+            numbers_per_line: Some(3),
+            enumerate_arrays: true,
+            ..config
+        };
+
+        assert_eq!(
+            to_string_pretty(&value.indices, config).unwrap(),
+            "[
+    // [0]
+    0, 1, 2,
+    // [3]
+    2, 3,
+]"
+        );
+    }
+
+    #[test]
+    fn test_layout_overrides() {
+        #[derive(Serialize)]
+        struct Color(u8, u8, u8);
+
+        #[derive(Serialize)]
+        struct Entity {
+            color: Color,
+            position: MyStruct,
+            path: Vec<(u8, u8)>,
+        }
+
+        let value = Entity {
+            color: Color(255, 0, 0),
+            position: MyStruct { x: 1.0, y: 2.0 },
+            path: vec![(0, 0), (1, 2)],
+        };
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            depth_limit: 2,
+            separate_tuple_members: true,
+            always_inline: vec!["Color".to_owned()],
+            always_expand: vec!["position".to_owned()],
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config.clone()).unwrap(),
+            "(
+    color: (255, 0, 0),
+    position: (
+        x: 1,
+        y: 2,
+    ),
+    path: [(0,0,),(1,2,),],
+)"
+        );
+
+        let config = PrettyConfig {
+            depth_limit: !0,
+            separate_tuple_members: false,
+            max_width: Some(80),
+            always_expand: vec!["path".to_owned()],
+            ..config
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config).unwrap(),
+            "(
+    color: (255, 0, 0),
+    position: (x: 1, y: 2),
+    path: [
+        (0, 0),
+        (1, 2),
+    ],
+)"
+        );
+    }
+
+    #[test]
+    fn test_redacted_fields() {
+        use std::collections::BTreeMap;
+
+        #[derive(Serialize)]
+        struct Credentials {
+            user: &'static str,
+            api_key: &'static str,
+            tokens: Vec<&'static str>,
+            password: Redacted<String>,
+            pin_secret: (u16, f32, char, bool, Option<i8>),
+            salt_secret: serde_bytes::ByteBuf,
+            accounts_secret: BTreeMap<&'static str, u64>,
+        }
+
+        let value = Credentials {
+            user: "admin",
+            api_key: "1234",
+            tokens: vec!["a", "b"],
+            password: Redacted("hunter2".to_owned()),
+            pin_secret: (1234, 0.5, 'x', true, None),
+            salt_secret: serde_bytes::ByteBuf::from(vec![1, 2]),
+            accounts_secret: vec![("a", 1), ("b", 2)].into_iter().collect(),
+        };
+        let config = PrettyConfig {
+            max_width: Some(300),
+            redacted_fields: vec![
+                "*_key".to_owned(),
+                "tokens".to_owned(),
+                "*_secret".to_owned(),
+            ],
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config).unwrap(),
+            "(user: \"admin\", api_key: \"<redacted>\", \
+             tokens: [\"<redacted>\", \"<redacted>\"], password: \"<redacted>\", \
+             pin_secret: (\"<redacted>\", \"<redacted>\", \"<redacted>\", \"<redacted>\", None), \
+             salt_secret: \"<redacted>\", \
+             accounts_secret: {\"a\": \"<redacted>\", \"b\": \"<redacted>\"})"
+        );
+
+        assert!(matches_pattern(b"*", b""));
+        assert!(matches_pattern(b"pass*", b"password"));
+        assert!(matches_pattern(b"*secret*", b"client_secret_id"));
+        assert!(!matches_pattern(b"*_key", b"keys"));
+        assert!(!matches_pattern(b"token", b"tokens"));
+    }
+
+    #[test]
+    fn test_formatter() {
+        struct Brackets;
+
+        impl Formatter for Brackets {
+            fn write_token(
+                &mut self,
+                writer: &mut dyn io::Write,
+                token: Token,
+                text: &str,
+            ) -> io::Result<()> {
+                write!(writer, "<{:?} {}>", token, text)
+            }
+        }
+
+        #[derive(Serialize)]
+        struct Tokens {
+            a: Option<u8>,
+            b: Commented<Vec<char>>,
+            c: Option<MyEnum>,
+            d: f32,
+        }
+
+        let value = Tokens {
+            a: None,
+            b: Commented::new("Chars", vec!['x']),
+            c: Some(MyEnum::B(true)),
+            d: 0.5,
+        };
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            omit_none_fields: true,
+            ..PrettyConfig::default()
+        };
+        let mut s = Serializer::with_config(Some(config));
+        s.set_formatter(Brackets);
+        s.serialize_value(&value).unwrap();
+
+        assert_eq!(
+            s.into_output_string(),
+            "(
+    <Comment // Chars>
+    <Field b>: [
+        <Char 'x'>,
+    ],
+    <Field c>: <Identifier Some>(<Identifier B>(<Bool true>)),
+    <Field d>: <Number 0.5>,
+)"
+        );
+    }
+
+    #[test]
+    fn test_compact_spacing() {
+        let value = (
+            MyStruct { x: 1.0, y: 2.0 },
+            vec![MyEnum::A, MyEnum::C(true, 0.5)],
+            EmptyStruct2 {},
+        );
+        let config = PrettyConfig {
+            depth_limit: 0,
+            compact_spacing: Spacing {
+                after_colon: true,
+                ..Spacing::default()
+            },
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config.clone()).unwrap(),
+            "((x: 1,y: 2,),[A,C(true,0.5,),],(),)"
+        );
+
+        let config = PrettyConfig {
+            compact_spacing: Spacing {
+                after_comma: true,
+                inside_parens: true,
+                ..config.compact_spacing
+            },
+            ..config
+        };
+
+        assert_eq!(
+            to_string_pretty(&value, config.clone()).unwrap(),
+            "( ( x: 1, y: 2, ), [A, C( true, 0.5, ),], (), )"
+        );
+
+        // The spaces stay in place when the fields are reordered
+        #[derive(Serialize)]
+        struct Reversed {
+            b: u8,
+            a: u8,
+        }
+
+        let config = PrettyConfig {
+            sort_struct_fields: true,
+            compact_spacing: Spacing {
+                after_colon: false,
+                ..config.compact_spacing
+            },
+            ..config
+        };
+
+        assert_eq!(
+            to_string_pretty(&Reversed { b: 1, a: 2 }, config).unwrap(),
+            "( a:2, b:1, )"
+        );
+    }
+
+    #[test]
+    fn test_config_roundtrip() {
+        let config = PrettyConfig {
+            new_line: "\n".to_owned(),
+            depth_limit: 4,
+            compact_spacing: Spacing {
+                after_colon: true,
+                ..Spacing::default()
+            },
+            unsigned_radix: Radix::Hexadecimal,
+            always_inline: vec!["Color".to_owned()],
+            hex_bytes: Some(16),
+            ..PrettyConfig::default()
+        };
+        let serial = to_string_pretty(&config, config.clone()).unwrap();
+        let loaded: PrettyConfig = ::de::from_str(&serial).unwrap();
+
+        assert_eq!(to_string_pretty(&loaded, config.clone()).unwrap(), serial);
+
+        let loaded: PrettyConfig = ::de::from_str("(compact_spacing: (after_comma: true))").unwrap();
+
+        assert!(loaded.compact_spacing.after_comma);
+        assert_eq!(loaded.depth_limit, !0);
+    }
+
+    #[test]
+    fn test_blank_lines() {
+        use std::collections::BTreeMap;
+
+        #[derive(Serialize)]
+        struct Config {
+            window: MyStruct,
+            keys: BTreeMap<u8, u8>,
+        }
+
+        let config = Config {
+            window: MyStruct { x: 1.0, y: 2.0 },
+            keys: (1..4).map(|i| (i, i)).collect(),
+        };
+        let pretty = PrettyConfig {
+            new_line: "\n".to_owned(),
+            blank_lines_between_fields: true,
+            ..PrettyConfig::default()
+        };
+
+        assert_eq!(
+            to_string_pretty(&config, pretty.clone()).unwrap(),
+            "(
+    window: (
+        x: 1,
+        y: 2,
+    ),
+
+    keys: {
+        1: 1,
+        2: 2,
+        3: 3,
+    },
+)"
+        );
+
+        let pretty = PrettyConfig {
+            blank_lines_between_fields: false,
+            blank_lines_in_maps: Some(2),
+            ..pretty
+        };
+
+        assert_eq!(
+            to_string_pretty(&config, pretty.clone()).unwrap(),
+            "(
+    window: (
+        x: 1,
+        y: 2,
+    ),
+    keys: {
+        1: 1,
+
+        2: 2,
+
+        3: 3,
+    },
+)"
+        );
+
+        let pretty = PrettyConfig {
+            blank_lines_in_maps: Some(3),
+            ..pretty
+        };
+
+        assert_eq!(
+            to_string_pretty(&config.keys, pretty).unwrap(),
+            "{
+    1: 1,
+    2: 2,
+    3: 3,
+}"
+        );
+    }
 }
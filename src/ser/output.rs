@@ -0,0 +1,162 @@
+use std::fmt;
+use std::io;
+use std::str;
+
+/// Wraps the serializer's writer, keeping track of the current column.
+///
+/// While a capture is active, nothing reaches the writer;
+/// the output is buffered instead, so the serializer can try
+/// whether a value fits on the rest of the line or reorder
+/// what it has written.
+pub struct Output<W> {
+    writer: W,
+    column: usize,
+    captures: Vec<Capture>,
+}
+
+struct Capture {
+    buf: Vec<u8>,
+    width: Option<usize>,
+    /// Whether something didn't fit into `width`.
+    overflowed: bool,
+    column: usize,
+}
+
+impl<W> Output<W> {
+    pub fn new(writer: W) -> Self {
+        Output {
+            writer,
+            column: 0,
+            captures: Vec::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// The number of characters written since the last line break.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Starts buffering the output.
+    ///
+    /// If `width` is given, all writes fail once the buffered line would
+    /// be longer than `width` characters or contain a line break.
+    /// Captures can be nested.
+    pub fn start_capture(&mut self, width: Option<usize>) {
+        self.captures.push(Capture {
+            buf: Vec::new(),
+            width,
+            overflowed: false,
+            column: self.column,
+        });
+    }
+
+    /// Fails the innermost capture with a width, like a write which
+    /// doesn't fit would, for what can't be written on one line at all.
+    pub fn overflow(&mut self) -> io::Error {
+        if let Some(capture) = self.captures.iter_mut().rev().find(|c| c.width.is_some()) {
+            capture.overflowed = true;
+        }
+
+        too_wide()
+    }
+
+    /// Whether the innermost capture has failed because something
+    /// didn't fit into its width, rather than because of another error.
+    pub fn overflowed(&self) -> bool {
+        self.captures.last().is_some_and(|c| c.overflowed)
+    }
+
+    /// The number of captures currently active.
+    pub fn capture_depth(&self) -> usize {
+        self.captures.len()
+    }
+
+    /// The number of bytes buffered by the innermost capture.
+    pub fn captured_len(&self) -> usize {
+        self.captures.last().map(|c| c.buf.len()).unwrap_or(0)
+    }
+
+    /// Stops the innermost capture and returns what it has buffered.
+    pub fn end_capture(&mut self) -> Vec<u8> {
+        match self.captures.pop() {
+            Some(capture) => {
+                self.column = capture.column;
+
+                capture.buf
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+impl<W: io::Write> io::Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = match self.captures.last_mut() {
+            Some(capture) => {
+                if let Some(ref mut width) = capture.width {
+                    let chars = count_chars(buf);
+
+                    if chars > *width || buf.contains(&b'\n') {
+                        capture.overflowed = true;
+
+                        return Err(too_wide());
+                    }
+
+                    *width -= chars;
+                }
+
+                capture.buf.extend_from_slice(buf);
+
+                buf.len()
+            }
+            None => self.writer.write(buf)?,
+        };
+        let buf = &buf[..written];
+
+        match buf.iter().rposition(|&b| b == b'\n') {
+            Some(i) => self.column = count_chars(&buf[i + 1..]),
+            None => self.column += count_chars(buf),
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+fn too_wide() -> io::Error {
+    io::Error::other("value does not fit on the line")
+}
+
+fn count_chars(buf: &[u8]) -> usize {
+    // Count everything but UTF-8 continuation bytes
+    buf.iter().filter(|&&b| b & 0xC0 != 0x80).count()
+}
+
+/// Adapts a `fmt::Write`, like a `String`, to `io::Write`,
+/// so that the serializer can write into it.
+///
+/// The serializer only ever writes complete UTF-8 sequences,
+/// so every buffer can be passed on as a `str`.
+pub struct FmtWriter<W>(pub W);
+
+impl<W: fmt::Write> io::Write for FmtWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let s = str::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.0
+            .write_str(s)
+            .map_err(|_| io::Error::other("formatter error"))?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+/// The placeholder written instead of redacted values.
+pub const REDACTED: &str = "<redacted>";
+
+/// A secret, like a password or an API key, which is serialized
+/// as the `REDACTED` placeholder string instead of its value.
+///
+/// ```
+/// use ron::ser::{to_string, Redacted};
+///
+/// #[macro_use]
+/// extern crate serde;
+/// extern crate ron;
+///
+/// #[derive(Serialize)]
+/// struct Login {
+///     user: String,
+///     password: Redacted<String>,
+/// }
+///
+/// fn main() {
+///     let login = Login {
+///         user: "admin".to_owned(),
+///         password: Redacted("hunter2".to_owned()),
+///     };
+///
+///     assert_eq!(
+///         to_string(&login).unwrap(),
+///         "(user:\"admin\",password:\"<redacted>\",)"
+///     );
+/// }
+/// ```
+///
+/// This applies to all serializers, and `Debug` hides the value as well.
+/// Deserializing a `Redacted` reads the value as usual.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct Redacted<T>(pub T);
+
+impl<T> Debug for Redacted<T> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_tuple("Redacted").field(&REDACTED).finish()
+    }
+}
+
+impl<T> Serialize for Redacted<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Redacted<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(Redacted)
+    }
+}
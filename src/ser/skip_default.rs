@@ -0,0 +1,93 @@
+use std::ops::{Deref, DerefMut};
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+/// The name `SkipDefault` is serialized with.
+pub const SKIP_DEFAULT: &str = "$ron::SkipDefault";
+/// The name `SkipDefault` is serialized with if it holds the default value.
+pub const SKIP_DEFAULT_DEFAULT: &str = "$ron::SkipDefault::Default";
+
+/// A struct field which the pretty serializer leaves out if it holds
+/// the default value and `skip_default_fields` is set.
+///
+/// The field should be marked with `#[serde(default)]`, so that
+/// it can be read back when it's missing.
+///
+/// ```
+/// use ron::ser::{to_string_pretty, PrettyConfig, SkipDefault};
+///
+/// #[macro_use]
+/// extern crate serde;
+/// extern crate ron;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Audio {
+///     #[serde(default)]
+///     volume: SkipDefault<u8>,
+///     #[serde(default)]
+///     muted: SkipDefault<bool>,
+/// }
+///
+/// fn main() {
+///     let audio = Audio {
+///         volume: SkipDefault(80),
+///         muted: SkipDefault(false),
+///     };
+///     let config = PrettyConfig {
+///         skip_default_fields: true,
+///         max_width: Some(80),
+///         ..PrettyConfig::default()
+///     };
+///
+///     assert_eq!(to_string_pretty(&audio, config).unwrap(), "(volume: 80)");
+/// }
+/// ```
+///
+/// Other serializers write the value as a newtype struct.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SkipDefault<T>(pub T);
+
+impl<T> Deref for SkipDefault<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for SkipDefault<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> From<T> for SkipDefault<T> {
+    fn from(value: T) -> Self {
+        SkipDefault(value)
+    }
+}
+
+impl<T: Default + PartialEq + Serialize> Serialize for SkipDefault<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let name = if self.0 == T::default() {
+            SKIP_DEFAULT_DEFAULT
+        } else {
+            SKIP_DEFAULT
+        };
+
+        serializer.serialize_newtype_struct(name, &self.0)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for SkipDefault<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(SkipDefault)
+    }
+}
@@ -1,6 +1,6 @@
 use serde::ser::{Serialize, Serializer};
 
-use value::Value;
+use value::{UnknownFields, Value};
 
 impl Serialize for Value {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -11,7 +11,7 @@ impl Serialize for Value {
             Value::Bool(b) => serializer.serialize_bool(b),
             Value::Char(c) => serializer.serialize_char(c),
             Value::Map(ref m) => Serialize::serialize(m, serializer),
-            Value::Number(ref n) => serializer.serialize_f64(n.get()),
+            Value::Number(ref n) => serialize_number(n.get(), serializer),
             Value::Option(Some(ref o)) => serializer.serialize_some(o.as_ref()),
             Value::Option(None) => serializer.serialize_none(),
             Value::String(ref s) => serializer.serialize_str(s),
@@ -20,3 +20,25 @@ impl Serialize for Value {
         }
     }
 }
+
+impl Serialize for UnknownFields {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+/// Whole numbers are written as integers, so that they can be read
+/// back into integer types whatever the float formatting is.
+/// `-0.0` stays a float, as an integer has no sign to keep.
+fn serialize_number<S: Serializer>(n: f64, serializer: S) -> Result<S::Ok, S::Error> {
+    const MAX_EXACT: f64 = (1u64 << 53) as f64;
+
+    if n.fract() == 0.0 && n.abs() <= MAX_EXACT && !(n == 0.0 && n.is_sign_negative()) {
+        serializer.serialize_i64(n as i64)
+    } else {
+        serializer.serialize_f64(n)
+    }
+}
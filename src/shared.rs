@@ -0,0 +1,270 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeTupleStruct, Serializer};
+
+/// The name `Shared` is serialized with, which the RON serializer
+/// and deserializer recognize.
+pub const SHARED: &str = "$ron::Shared";
+
+thread_local! {
+    /// The pointers to the values defined by the anchors read so far,
+    /// if a RON deserializer is running.
+    static ANCHORS: RefCell<Option<HashMap<u64, Box<dyn Any>>>> = const { RefCell::new(None) };
+    /// The address a shared value hands over to the RON serializer.
+    static ADDRESS: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// A value behind an `Rc`, which is written only once by the
+/// RON serializer, no matter how often it is shared.
+///
+/// The first occurrence is labeled with an anchor, like `&0 (x: 1)`,
+/// and every other occurrence refers back to it, like `*0`.
+/// Deserializing the output with `Options::with_shared_values`
+/// shares the value again.
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// use ron::de::Options;
+/// use ron::ser::{to_string, Shared};
+///
+/// let mesh = Shared::new(vec![1, 2, 3]);
+/// let meshes = vec![mesh.clone(), mesh, Shared::new(vec![4])];
+/// let serial = to_string(&meshes).unwrap();
+///
+/// assert_eq!(serial, "[&0 [1,2,3,],*0,&1 [4,],]");
+///
+/// let options = Options::new().with_shared_values(true);
+/// let meshes: Vec<Shared<Vec<u8>>> = options.from_str(&serial).unwrap();
+///
+/// assert!(Rc::ptr_eq(&meshes[0].0, &meshes[1].0));
+/// assert!(!Rc::ptr_eq(&meshes[0].0, &meshes[2].0));
+/// ```
+///
+/// An anchor must be written before the references to it, which sorted
+/// map keys or struct fields could change, so the serializer fails with
+/// a `Shared` value inside a map or struct which is sorted.
+/// Other formats see a tuple struct of the value's address and the value.
+#[derive(Debug, Default, PartialEq)]
+pub struct Shared<T>(pub Rc<T>);
+
+/// Like `Shared`, but behind an `Arc`, for values which are
+/// shared between threads.
+///
+/// A reference can't be read into a `SharedArc` if its anchor
+/// was read into a `Shared`, and vice versa.
+#[derive(Debug, Default, PartialEq)]
+pub struct SharedArc<T>(pub Arc<T>);
+
+impl<T> Shared<T> {
+    pub fn new(value: T) -> Self {
+        Shared(Rc::new(value))
+    }
+}
+
+impl<T> SharedArc<T> {
+    pub fn new(value: T) -> Self {
+        SharedArc(Arc::new(value))
+    }
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Shared(self.0.clone())
+    }
+}
+
+impl<T> Clone for SharedArc<T> {
+    fn clone(&self) -> Self {
+        SharedArc(self.0.clone())
+    }
+}
+
+impl<T> Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> Deref for SharedArc<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> From<Rc<T>> for Shared<T> {
+    fn from(rc: Rc<T>) -> Self {
+        Shared(rc)
+    }
+}
+
+impl<T> From<Arc<T>> for SharedArc<T> {
+    fn from(arc: Arc<T>) -> Self {
+        SharedArc(arc)
+    }
+}
+
+impl<T: Serialize> Serialize for Shared<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_shared(Rc::as_ptr(&self.0) as usize, &*self.0, serializer)
+    }
+}
+
+impl<T: Serialize> Serialize for SharedArc<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_shared(Arc::as_ptr(&self.0) as usize, &*self.0, serializer)
+    }
+}
+
+/// Serializes `value`, which is shared at `address`.
+fn serialize_shared<T, S>(address: usize, value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    let address = address as u64;
+    // The RON serializer takes the address as it is, rather than
+    // serializing the first field, which it may format or redact
+    ADDRESS.with(|handed_over| handed_over.set(Some(address)));
+
+    let mut tuple = serializer.serialize_tuple_struct(SHARED, 2)?;
+    tuple.serialize_field(&address)?;
+    tuple.serialize_field(value)?;
+    tuple.end()
+}
+
+/// Takes the address the shared value which is serialized
+/// has handed over.
+pub fn take_address() -> Option<u64> {
+    ADDRESS.with(|handed_over| handed_over.take())
+}
+
+impl<'de, T: Deserialize<'de> + 'static> Deserialize<'de> for Shared<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_tuple_struct(SHARED, 2, SharedVisitor::<T, Rc<T>>(PhantomData))
+            .map(Shared)
+    }
+}
+
+impl<'de, T: Deserialize<'de> + 'static> Deserialize<'de> for SharedArc<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_tuple_struct(SHARED, 2, SharedVisitor::<T, Arc<T>>(PhantomData))
+            .map(SharedArc)
+    }
+}
+
+/// A pointer to a shared value, which `SharedVisitor` creates.
+trait Pointer<T>: Clone + 'static {
+    fn new(value: T) -> Self;
+}
+
+impl<T: 'static> Pointer<T> for Rc<T> {
+    fn new(value: T) -> Self {
+        Rc::new(value)
+    }
+}
+
+impl<T: 'static> Pointer<T> for Arc<T> {
+    fn new(value: T) -> Self {
+        Arc::new(value)
+    }
+}
+
+struct SharedVisitor<T, P>(PhantomData<(T, P)>);
+
+impl<'de, T: Deserialize<'de>, P: Pointer<T>> Visitor<'de> for SharedVisitor<T, P> {
+    type Value = P;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a shared value")
+    }
+
+    /// Reads a value without an anchor.
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(P::new)
+    }
+
+    /// Reads an anchor and its value, or a reference without a value.
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let id: u64 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+
+        match seq.next_element::<T>()? {
+            Some(value) => {
+                let pointer = P::new(value);
+                ANCHORS.with(|anchors| {
+                    if let Some(ref mut anchors) = *anchors.borrow_mut() {
+                        anchors.insert(id, Box::new(pointer.clone()));
+                    }
+                });
+
+                Ok(pointer)
+            }
+            None => ANCHORS.with(|anchors| {
+                let anchors = anchors.borrow();
+                let pointer = anchors
+                    .as_ref()
+                    .and_then(|anchors| anchors.get(&id))
+                    .ok_or_else(|| de::Error::custom(format!("Unknown anchor `*{}`", id)))?;
+
+                pointer.downcast_ref::<P>().cloned().ok_or_else(|| {
+                    de::Error::custom(format!("Anchor `*{}` refers to another type", id))
+                })
+            }),
+        }
+    }
+}
+
+/// Collects the anchors read while it's alive, for a single document.
+pub struct AnchorScope {
+    outer: Option<HashMap<u64, Box<dyn Any>>>,
+}
+
+impl AnchorScope {
+    pub fn new() -> Self {
+        let outer = ANCHORS.with(|anchors| anchors.borrow_mut().replace(HashMap::new()));
+
+        AnchorScope { outer }
+    }
+}
+
+impl Drop for AnchorScope {
+    fn drop(&mut self) {
+        let outer = self.outer.take();
+
+        ANCHORS.with(|anchors| *anchors.borrow_mut() = outer);
+    }
+}
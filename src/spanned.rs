@@ -0,0 +1,117 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, Range};
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+/// The name `Spanned` is deserialized with, which the RON
+/// deserializer recognizes.
+pub const SPANNED: &str = "$ron::Spanned";
+pub const START: &str = "$ron::start";
+pub const VALUE: &str = "$ron::value";
+pub const END: &str = "$ron::end";
+
+const FIELDS: &[&str] = &[START, VALUE, END];
+
+/// A value together with the range of bytes it was read from,
+/// so that problems found after deserializing it can point at it.
+///
+/// ```
+/// use ron::Spanned;
+///
+/// #[macro_use]
+/// extern crate serde;
+/// extern crate ron;
+///
+/// #[derive(Deserialize)]
+/// struct Server {
+///     port: Spanned<u32>,
+/// }
+///
+/// fn main() {
+///     let input = "(port: 99999)";
+///     let server: Server = ron::de::from_str(input).unwrap();
+///
+///     assert_eq!(*server.port, 99999);
+///     assert_eq!(&input[server.port.span.clone()], "99999");
+/// }
+/// ```
+///
+/// The span includes neither the whitespace nor the comments around
+/// the value. `Spanned` is serialized like the value on its own.
+/// Other formats see a struct of the value and its start and end.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Spanned<T> {
+    /// The byte offsets of the value in the input.
+    pub span: Range<usize>,
+    pub value: T,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(value: T, span: Range<usize>) -> Self {
+        Spanned { span, value }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Serialize> Serialize for Spanned<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.value.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Spanned<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(SPANNED, FIELDS, SpannedVisitor(PhantomData))
+    }
+}
+
+struct SpannedVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for SpannedVisitor<T> {
+    type Value = Spanned<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a spanned value")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let (mut start, mut value, mut end) = (None, None, None);
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                START => start = Some(map.next_value()?),
+                VALUE => value = Some(map.next_value()?),
+                END => end = Some(map.next_value()?),
+                _ => return Err(de::Error::unknown_field(&key, FIELDS)),
+            }
+        }
+
+        let start = start.ok_or_else(|| de::Error::missing_field(START))?;
+        let value = value.ok_or_else(|| de::Error::missing_field(VALUE))?;
+        let end = end.ok_or_else(|| de::Error::missing_field(END))?;
+
+        Ok(Spanned::new(value, start..end))
+    }
+}
@@ -3,24 +3,23 @@
 use std::cmp::{Eq, Ordering};
 use std::collections::BTreeMap;
 use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
 
 use serde::de::{DeserializeSeed, Deserializer, Error as SerdeErr, MapAccess, SeqAccess, Visitor};
 
 use de::{Error as RonError, Result};
 
-/// A wrapper for `f64` which guarantees that the inner value
-/// is finite and thus implements `Eq`, `Hash` and `Ord`.
-#[derive(Copy, Clone, Debug, PartialOrd, PartialEq)]
+pub use raw::RawValue;
+
+/// A wrapper for `f64` which implements `Eq`, `Hash` and `Ord`.
+///
+/// To make that possible, all NaNs are equal to each other
+/// and greater than every other number, including infinity.
+#[derive(Copy, Clone, Debug)]
 pub struct Number(f64);
 
 impl Number {
-    /// Panics if `v` is not a real number
-    /// (infinity, NaN, ..).
     pub fn new(v: f64) -> Self {
-        if !v.is_finite() {
-            panic!("Tried to create Number with a NaN / infinity");
-        }
-
         Number(v)
     }
 
@@ -30,6 +29,12 @@ impl Number {
     }
 }
 
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
 impl Eq for Number {}
 
 impl Hash for Number {
@@ -38,9 +43,18 @@ impl Hash for Number {
     }
 }
 
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Ord for Number {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).expect("Bug: Contract violation")
+        match self.0.partial_cmp(&other.0) {
+            Some(ordering) => ordering,
+            None => self.0.is_nan().cmp(&other.0.is_nan()),
+        }
     }
 }
 
@@ -56,6 +70,50 @@ pub enum Value {
     Unit,
 }
 
+/// The fields of a struct which it doesn't declare, collected by
+/// a field marked with `#[serde(flatten)]`, e.g. to warn about typos
+/// or to keep settings of newer versions.
+///
+/// ```
+/// use ron::value::UnknownFields;
+///
+/// #[macro_use]
+/// extern crate serde;
+/// extern crate ron;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     volume: u8,
+///     #[serde(flatten)]
+///     unknown: UnknownFields,
+/// }
+///
+/// fn main() {
+///     let config: Config = ron::de::from_str("(volume: 80, colour: \"red\")").unwrap();
+///
+///     assert_eq!(config.unknown.keys().collect::<Vec<_>>(), ["colour"]);
+/// }
+/// ```
+///
+/// The values are read like `Value`, so a unit enum variant
+/// like `Red` ends up as `Value::String`.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct UnknownFields(pub BTreeMap<String, Value>);
+
+impl Deref for UnknownFields {
+    type Target = BTreeMap<String, Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for UnknownFields {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
 /// Deserializer implementation for RON `Value`.
 /// This does not support enums (because `Value` doesn't store them).
 impl<'de> Deserializer<'de> for Value {
@@ -270,4 +328,86 @@ mod tests {
     fn unit() {
         assert_same::<()>("()");
     }
+
+    #[test]
+    fn serialize() {
+        use de;
+        use ser::{to_string, to_string_pretty, PrettyConfig};
+
+        let mut value: Value = de::from_str(
+            "Room(
+                size: [20, 5.5],
+                name: \"The Room\",
+                door: Some('d'),
+                enemies: { \"Enemy1\": [3, -1.25e3] },
+                light: None,
+                visited: false,
+            )",
+        ).unwrap();
+
+        if let Value::Map(ref mut fields) = value {
+            fields.insert(Value::String("visited".to_owned()), Value::Bool(true));
+        }
+
+        let compact = to_string(&value).unwrap();
+        assert_eq!(
+            compact,
+            "{\"door\":Some('d'),\"enemies\":{\"Enemy1\":[3,-1250,],},\"light\":None,\
+             \"name\":\"The Room\",\"size\":[20,5.5,],\"visited\":true,}"
+        );
+        assert_eq!(de::from_str::<Value>(&compact), Ok(value.clone()));
+
+        let config = PrettyConfig {
+            float_decimal_point: true,
+            ..PrettyConfig::default()
+        };
+        let pretty = to_string_pretty(&value, config).unwrap();
+        assert_eq!(de::from_str::<Value>(&pretty), Ok(value));
+
+        // Whole numbers can still be read as integers
+        let value = Value::Seq(vec![Value::Number(Number::new(3.0))]);
+        assert_eq!(
+            de::from_str::<Vec<u8>>(&to_string(&value).unwrap()),
+            Ok(vec![3])
+        );
+
+        // The sign of `-0.0` is kept
+        let zero = to_string(&Value::Number(Number::new(-0.0))).unwrap();
+        assert_eq!(zero, "-0.0");
+        match de::from_str::<Value>(&zero) {
+            Ok(Value::Number(n)) => assert!(n.get().is_sign_negative()),
+            other => panic!("{:?}", other),
+        }
+        match de::from_str::<Value>("-0") {
+            Ok(Value::Number(n)) => assert!(n.get().is_sign_negative()),
+            other => panic!("{:?}", other),
+        }
+        assert!(de::from_str::<f32>(&to_string(&-0.0f32).unwrap())
+            .unwrap()
+            .is_sign_negative());
+    }
+
+    #[test]
+    fn test_non_finite_numbers() {
+        const INFINITY: f64 = f64::INFINITY;
+        const NAN: f64 = f64::NAN;
+        const NEG_INFINITY: f64 = f64::NEG_INFINITY;
+
+        let numbers = [NAN, INFINITY, 1.0, -0.0, NEG_INFINITY, -NAN];
+        let mut sorted: Vec<Number> = numbers.iter().cloned().map(Number::new).collect();
+        sorted.sort();
+        let sorted: Vec<f64> = sorted.iter().map(Number::get).collect();
+
+        assert_eq!(sorted[..4], [NEG_INFINITY, -0.0, 1.0, INFINITY]);
+        assert!(sorted[4].is_nan() && sorted[5].is_nan());
+
+        assert_eq!(Number::new(NAN), Number::new(-NAN));
+        assert_eq!(Number::new(0.0), Number::new(-0.0));
+        assert!(Number::new(INFINITY) < Number::new(NAN));
+
+        let mut map = BTreeMap::new();
+        map.insert(Value::Number(Number::new(NAN)), Value::Unit);
+
+        assert!(map.contains_key(&Value::Number(Number::new(NAN))));
+    }
 }
@@ -0,0 +1,127 @@
+#![cfg(feature = "async")]
+
+extern crate ron;
+#[macro_use]
+extern crate serde;
+extern crate tokio;
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use ron::de::{from_async_reader, Error, Limits, Options, ParseError};
+use tokio::io::{AsyncRead, ReadBuf};
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Config {
+    name: String,
+    replicas: u8,
+}
+
+/// Hands out one byte at a time, and is only ready every other time.
+struct Trickle<'a> {
+    input: &'a [u8],
+    ready: bool,
+}
+
+impl<'a> AsyncRead for Trickle<'a> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<io::Result<()>> {
+        self.ready = !self.ready;
+        if !self.ready {
+            cx.waker().wake_by_ref();
+
+            return Poll::Pending;
+        }
+
+        if let Some((&byte, rest)) = self.input.split_first() {
+            buf.put_slice(&[byte]);
+            self.input = rest;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+fn noop_waker() -> Waker {
+    Arc::new(NoopWaker).into()
+}
+
+/// Polls `future` until it's ready, which the readers here always become.
+fn block_on<F: Future + Unpin>(mut future: F) -> F::Output {
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        if let Poll::Ready(output) = Pin::new(&mut future).poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn test_from_async_reader() {
+    let input = b"(name: \"api\", replicas: 3)";
+    let expected = Config {
+        name: "api".to_owned(),
+        replicas: 3,
+    };
+
+    assert_eq!(block_on(from_async_reader(&input[..])), Ok(expected));
+
+    let reader = Trickle {
+        input,
+        ready: false,
+    };
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    let mut future = from_async_reader::<_, Config>(reader);
+
+    assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+    assert_eq!(block_on(future).unwrap().replicas, 3);
+
+    assert_eq!(
+        block_on(from_async_reader::<_, Config>(&b"(name: 1)"[..])).map_err(|e| e.position()),
+        Err(Some(ron::de::Position {
+            col: 8,
+            line: 1,
+            offset: 7,
+        }))
+    );
+}
+
+#[test]
+fn test_async_input_limit() {
+    let options = Options::new().with_limits(Limits {
+        max_input_len: Some(8),
+        ..Limits::default()
+    });
+    let reader = Trickle {
+        input: b"[1, 2, 3, 4, 5, 6]",
+        ready: false,
+    };
+
+    match block_on(options.from_async_reader::<_, Vec<u8>>(reader)) {
+        Err(Error::Parser(ParseError::ExceededInputLimit, _)) => {}
+        other => panic!("{:?}", other),
+    }
+    assert_eq!(
+        block_on(options.from_async_reader(&b"[1, 2]"[..])),
+        Ok(vec![1u8, 2])
+    );
+
+    let mut input = &b"[1, 2, 3, 4, 5, 6]"[..];
+    assert!(block_on(options.from_async_reader::<_, Vec<u8>>(&mut input)).is_err());
+    assert_eq!(input, b" 4, 5, 6]");
+}
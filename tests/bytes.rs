@@ -0,0 +1,200 @@
+extern crate ron;
+#[macro_use]
+extern crate serde;
+extern crate serde_bytes;
+
+use ron::de::from_str;
+use ron::ser::{to_string, to_string_pretty, PrettyConfig};
+use serde_bytes::ByteBuf;
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+struct Blob {
+    name: String,
+    #[serde(with = "serde_bytes")]
+    data: Vec<u8>,
+}
+
+fn hex_config(per_line: usize) -> PrettyConfig {
+    PrettyConfig {
+        new_line: "\n".to_owned(),
+        hex_bytes: Some(per_line),
+        ..PrettyConfig::default()
+    }
+}
+
+#[test]
+fn test_hex_block() {
+    let value = Blob {
+        name: "blob".to_owned(),
+        data: (0..20).collect(),
+    };
+
+    assert_eq!(
+        to_string_pretty(&value, hex_config(8)).unwrap(),
+        "(
+    name: \"blob\",
+    data: x\"
+        00 01 02 03 04 05 06 07
+        08 09 0a 0b 0c 0d 0e 0f
+        10 11 12 13
+    \",
+)"
+    );
+    assert_eq!(
+        to_string_pretty(&ByteBuf::from(vec![0, 127, 255]), hex_config(8)).unwrap(),
+        "x\"00 7f ff\""
+    );
+}
+
+#[test]
+fn test_hex_block_roundtrip() {
+    let value = Blob {
+        name: "blob".to_owned(),
+        data: (0..=255).collect(),
+    };
+
+    for &per_line in &[1, 16, 300] {
+        let serial = to_string_pretty(&value, hex_config(per_line)).unwrap();
+
+        assert_eq!(from_str(&serial), Ok(value.clone()));
+    }
+
+    let config = PrettyConfig {
+        depth_limit: 1,
+        ..hex_config(16)
+    };
+    let serial = to_string_pretty(&value, config).unwrap();
+
+    assert!(serial.contains("x\"000102"));
+    assert_eq!(from_str(&serial), Ok(value.clone()));
+
+    // Without `hex_bytes`, bytes are still written as arrays
+    let serial = to_string(&value).unwrap();
+
+    assert!(serial.contains("[0,1,2,"));
+    assert_eq!(from_str(&serial), Ok(value));
+}
+
+#[test]
+fn test_hex_block_as_seq() {
+    assert_eq!(from_str("x\"00 01 ff\""), Ok(vec![0u8, 1, 255]));
+    assert_eq!(from_str("x\"0001 ff\""), Ok(vec![0u32, 1, 255]));
+    assert_eq!(
+        from_str::<ron::value::Value>("x\"\""),
+        Ok(ron::value::Value::Seq(vec![]))
+    );
+    assert!(from_str::<Vec<u8>>("x\"0\"").is_err());
+}
+
+#[test]
+fn test_hex_block_without_comments() {
+    assert_eq!(from_str("x\"00\n\tff \""), Ok(vec![0u8, 255]));
+    assert!(from_str::<Vec<u8>>("x\"00 // ff\n01\"").is_err());
+    assert!(from_str::<Vec<u8>>("x\"00 /* ff */ 01\"").is_err());
+}
+
+#[test]
+fn test_borrowed_bytes() {
+    use serde_bytes::Bytes;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Packet<'a> {
+        #[serde(borrow)]
+        payload: &'a Bytes,
+    }
+
+    let input = "(payload: \"GET /\")";
+    let packet: Packet = from_str(input).unwrap();
+
+    assert_eq!(packet.payload, Bytes::new(b"GET /"));
+    assert_eq!(packet.payload.as_ptr(), input[11..].as_ptr());
+
+    // Escapes have to be decoded into a buffer
+    assert!(from_str::<Packet>("(payload: \"\\n\")").is_err());
+    assert_eq!(
+        from_str::<ByteBuf>("\"\\n\""),
+        Ok(ByteBuf::from(b"\n".to_vec()))
+    );
+    assert_eq!(
+        from_str::<ByteBuf>("[1, 2]"),
+        Ok(ByteBuf::from(vec![1, 2]))
+    );
+}
+
+#[test]
+fn test_byte_string() {
+    use serde_bytes::Bytes;
+
+    let config = PrettyConfig {
+        byte_strings: true,
+        ..hex_config(8)
+    };
+    let value = Blob {
+        name: "gif".to_owned(),
+        data: b"GIF89a\x01\x00\"\\\n\xff".to_vec(),
+    };
+    let serial = to_string_pretty(&value, config.clone()).unwrap();
+
+    assert_eq!(
+        serial,
+        "(\n    name: \"gif\",\n    data: b\"GIF89a\\x01\\0\\\"\\\\\\n\\xff\",\n)"
+    );
+    assert_eq!(from_str(&serial), Ok(value));
+
+    // Byte strings without escapes are borrowed
+    let input = "b\"GIF89a\"";
+    let bytes: &Bytes = from_str(input).unwrap();
+
+    assert_eq!(bytes, Bytes::new(b"GIF89a"));
+    assert_eq!(bytes.as_ptr(), input[2..].as_ptr());
+
+    assert_eq!(from_str("b\"a\\x00\""), Ok(vec![b'a', 0]));
+    assert_eq!(
+        from_str::<ron::value::Value>("b\"\""),
+        Ok(ron::value::Value::Seq(vec![]))
+    );
+    assert!(from_str::<ByteBuf>("b\"\\q\"").is_err());
+    assert!(from_str::<ByteBuf>("b\"open").is_err());
+}
+
+#[test]
+fn test_base64() {
+    use ron::de::{Error, Options, ParseError};
+
+    let config = PrettyConfig {
+        base64_bytes: true,
+        byte_strings: true,
+        ..hex_config(8)
+    };
+    let value = Blob {
+        name: "thumbnail".to_owned(),
+        data: b"\x89PNG\r\n\x1a\n\x00".to_vec(),
+    };
+    let serial = to_string_pretty(&value, config).unwrap();
+
+    assert_eq!(
+        serial,
+        "(\n    name: \"thumbnail\",\n    data: \"iVBORw0KGgoA\",\n)"
+    );
+
+    let options = Options::new().with_base64_bytes(true);
+
+    assert_eq!(options.from_str(&serial), Ok(value.clone()));
+    assert_eq!(
+        options.from_str("\"iVBO Rw0K\n GgoA\""),
+        Ok(ByteBuf::from(value.data.clone()))
+    );
+    // `Vec<u8>` is read from base64 as well
+    assert_eq!(options.from_str("\"AP8=\""), Ok(vec![0u8, 255]));
+    // Other notations still work
+    assert_eq!(options.from_str("x\"00 ff\""), Ok(vec![0u8, 255]));
+    assert_eq!(options.from_str("[0, 255]"), Ok(ByteBuf::from(vec![0, 255])));
+
+    match options.from_str::<ByteBuf>("  \"AP8!\"") {
+        Err(Error::Parser(ParseError::ExpectedBase64, position)) => assert_eq!(position.col, 3),
+        other => panic!("{:?}", other),
+    }
+
+    // Without the option, the string is read as it is
+    assert_eq!(from_str("\"AP8=\""), Ok(ByteBuf::from(b"AP8=".to_vec())));
+}
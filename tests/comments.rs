@@ -1,4 +1,6 @@
 extern crate ron;
+#[macro_use]
+extern crate serde;
 
 use ron::de::Error as RonErr;
 use ron::de::ParseError;
@@ -37,5 +39,136 @@ fn test_unclosed() {
 /* Unfortunately, this comment won't get closed :(
 \"THE VALUE (which is invalid)\"
 "
-    ), Err(RonErr::Parser(ParseError::UnclosedBlockComment, Position { col: 1, line: 9 })));
+    ), Err(RonErr::Parser(ParseError::UnclosedBlockComment, Position { col: 1, line: 9, offset: 334 })));
+}
+
+#[test]
+fn test_line_comments() {
+    use std::collections::HashMap;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Shape {
+        Circle { radius: f32 },
+        Square(f32),
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Scene {
+        name: String,
+        shapes: Vec<Shape>,
+        tags: HashMap<String, u8>,
+        parent: Option<(u8, u8)>,
+    }
+
+    let scene: Scene = ron::de::from_str(
+        "// The scene
+Scene // named
+( // fields
+    name // before the colon
+    : // after the colon
+    \"main\" // before the comma
+    , // after the comma
+    shapes: [ // first
+        Circle // variant
+        ( // inside
+            radius: 1.5, // radius
+        ), // done
+        Square(2.0) // no trailing comma
+        // last
+    ],
+    tags: { // map
+        \"a\" // key
+        : 1, // value
+    },
+    parent: Some // option
+    ( // inside
+        (1, // tuple
+        2) // value
+    ),
+) // after the final value
+// and at the very end",
+    ).unwrap();
+
+    assert_eq!(
+        scene,
+        Scene {
+            name: "main".to_owned(),
+            shapes: vec![Shape::Circle { radius: 1.5 }, Shape::Square(2.0)],
+            tags: vec![("a".to_owned(), 1)].into_iter().collect(),
+            parent: Some((1, 2)),
+        }
+    );
+    assert_eq!(ron::de::from_str("5 // the end"), Ok(5));
+    assert_eq!(ron::de::from_str("[1, // one\n2]"), Ok(vec![1, 2]));
+}
+
+#[test]
+fn test_block_comments() {
+    assert_eq!(ron::de::from_str("[1, /* 2, */ 3]"), Ok(vec![1, 3]));
+    assert_eq!(ron::de::from_str("/**/ 4 /***/"), Ok(4));
+    assert_eq!(ron::de::from_str("(1 /* a */, /* b */ 2)"), Ok((1, 2)));
+
+    // Commenting out a section which has comments of its own
+    assert_eq!(
+        ron::de::from_str(
+            "[
+    1,
+/*
+    2, /* the second */
+    3, // the third
+*/
+    4,
+]"
+        ),
+        Ok(vec![1, 4])
+    );
+
+    assert_eq!(
+        ron::de::from_str::<u8>("/* /* */ 5"),
+        Err(RonErr::Parser(
+            ParseError::UnclosedBlockComment,
+            Position {
+                col: 11,
+                line: 1,
+                offset: 10,
+            }
+        ))
+    );
+    assert_eq!(
+        ron::de::from_str::<u8>("5 */"),
+        Err(RonErr::Parser(
+            ParseError::TrailingCharacters,
+            Position {
+                col: 3,
+                line: 1,
+                offset: 2,
+            }
+        ))
+    );
+}
+
+#[test]
+fn test_shebang() {
+    assert_eq!(
+        ron::de::from_str("#!/usr/bin/env launcher --config\n[1, 2]"),
+        Ok(vec![1, 2])
+    );
+    assert_eq!(
+        ron::de::from_str("#! /bin/launch\n#![enable(implicit_some)]\n1"),
+        Ok(Some(1))
+    );
+    assert_eq!(ron::de::from_str("#! [enable(implicit_some)] 5"), Ok(Some(5)));
+
+    // Only the first line may be one
+    assert_eq!(
+        ron::de::from_str::<u8>("\n#!/bin/launch\n5"),
+        Err(RonErr::Parser(
+            ParseError::UnexpectedByte('b'),
+            Position {
+                col: 5,
+                line: 2,
+                offset: 5,
+            }
+        ))
+    );
 }
@@ -61,3 +61,60 @@ fn depth_limit() {
 
     assert_eq!(s, Ok(EXPECTED.to_string()));
 }
+
+#[derive(Serialize)]
+struct Vec3 {
+    x: i32,
+    y: i32,
+    z: i32,
+}
+
+#[derive(Serialize)]
+struct Transform {
+    position: Vec3,
+    scale: Vec3,
+}
+
+#[test]
+fn depth_limit_inline_leaves() {
+    let data = vec![Transform {
+        position: Vec3 { x: 1, y: 2, z: 3 },
+        scale: Vec3 { x: 1, y: 1, z: 1 },
+    }];
+
+    let pretty = ron::ser::PrettyConfig {
+        depth_limit: 3,
+        new_line: "\n".to_owned(),
+        ..Default::default()
+    };
+    let s = ron::ser::to_string_pretty(&data, pretty);
+
+    assert_eq!(
+        s,
+        Ok("[
+    (
+        position: (x:1,y:2,z:3,),
+        scale: (x:1,y:1,z:1,),
+    ),
+]"
+            .to_string())
+    );
+}
+
+#[test]
+fn depth_limit_zero() {
+    let data = Transform {
+        position: Vec3 { x: 1, y: 2, z: 3 },
+        scale: Vec3 { x: 1, y: 1, z: 1 },
+    };
+
+    let pretty = ron::ser::PrettyConfig {
+        depth_limit: 0,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        ron::ser::to_string_pretty(&data, pretty),
+        ron::ser::to_string(&data)
+    );
+}
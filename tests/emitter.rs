@@ -0,0 +1,121 @@
+extern crate ron;
+#[macro_use]
+extern crate serde;
+
+use std::collections::BTreeMap;
+
+use ron::ser::{to_string, to_string_pretty, Emitter, PrettyConfig, Spacing};
+
+#[derive(Serialize)]
+struct Level {
+    name: String,
+    size: (u32, u32),
+    spawns: Vec<Spawn>,
+    tags: BTreeMap<String, i32>,
+}
+
+#[derive(Serialize)]
+enum Spawn {
+    Player { x: f32 },
+    Enemy(u8, u8),
+}
+
+fn level() -> Level {
+    Level {
+        name: "intro".to_owned(),
+        size: (20, 10),
+        spawns: vec![Spawn::Player { x: 1.5 }, Spawn::Enemy(3, 4)],
+        tags: vec![("a".to_owned(), 1), ("b".to_owned(), -1)]
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn emit(config: Option<PrettyConfig>) -> String {
+    let mut e = Emitter::new(Vec::new(), config);
+
+    e.begin_struct(None).unwrap();
+    e.field("name").unwrap();
+    e.value("intro").unwrap();
+    e.field("size").unwrap();
+    e.begin_tuple(None).unwrap();
+    e.value(&20).unwrap();
+    e.value(&10).unwrap();
+    e.end_tuple().unwrap();
+    e.field("spawns").unwrap();
+    e.begin_seq().unwrap();
+    e.begin_struct(Some("Player")).unwrap();
+    e.field("x").unwrap();
+    e.value(&1.5).unwrap();
+    e.end_struct().unwrap();
+    e.begin_tuple(Some("Enemy")).unwrap();
+    e.raw("3").unwrap();
+    e.value(&4).unwrap();
+    e.end_tuple().unwrap();
+    e.end_seq().unwrap();
+    e.field("tags").unwrap();
+    e.begin_map().unwrap();
+    e.value("a").unwrap();
+    e.value(&1).unwrap();
+    e.value("b").unwrap();
+    e.value(&-1).unwrap();
+    e.end_map().unwrap();
+    e.end_struct().unwrap();
+
+    String::from_utf8(e.finish().unwrap()).unwrap()
+}
+
+#[test]
+fn test_matches_serializer() {
+    assert_eq!(emit(None), to_string(&level()).unwrap());
+
+    let configs = vec![
+        PrettyConfig::default(),
+        PrettyConfig {
+            depth_limit: 2,
+            ..PrettyConfig::default()
+        },
+        PrettyConfig {
+            separate_tuple_members: true,
+            trailing_comma: false,
+            ..PrettyConfig::default()
+        },
+        PrettyConfig {
+            depth_limit: 1,
+            compact_spacing: Spacing {
+                after_colon: true,
+                after_comma: true,
+                inside_parens: true,
+            },
+            ..PrettyConfig::default()
+        },
+    ];
+
+    for config in configs {
+        assert_eq!(
+            emit(Some(config.clone())),
+            to_string_pretty(&level(), config).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_misuse() {
+    let mut e = Emitter::new(Vec::new(), None);
+
+    assert!(e.field("x").is_err());
+    assert!(e.end_seq().is_err());
+
+    e.begin_struct(None).unwrap();
+    assert!(e.value(&1).is_err());
+    assert!(e.end_map().is_err());
+
+    e.field("x").unwrap();
+    assert!(e.field("y").is_err());
+    assert!(e.end_struct().is_err());
+
+    e.value(&1).unwrap();
+    e.field("y").unwrap();
+    e.begin_seq().unwrap();
+    assert!(e.finish().is_err());
+}
@@ -0,0 +1,116 @@
+extern crate ron;
+#[macro_use]
+extern crate serde;
+
+use std::fmt::Debug;
+
+use ron::de::from_str;
+use ron::ser::to_string;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+fn roundtrip<T>(value: T, expected: &str)
+where
+    T: Debug + PartialEq + Serialize + DeserializeOwned,
+{
+    let serial = to_string(&value).unwrap();
+
+    assert_eq!(serial, expected);
+    assert_eq!(from_str::<T>(&serial), Ok(value));
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Inner {
+    a: u8,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum External {
+    Unit,
+    Newtype(Inner),
+    Tuple(u8, bool),
+    Struct { b: String },
+}
+
+#[test]
+fn externally_tagged() {
+    roundtrip(External::Unit, "Unit");
+    roundtrip(External::Newtype(Inner { a: 1 }), "Newtype((a:1,))");
+    roundtrip(External::Tuple(1, true), "Tuple(1,true,)");
+    roundtrip(External::Struct { b: "x".to_owned() }, "Struct(b:\"x\",)");
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum Internal {
+    Unit,
+    Newtype(Inner),
+    Struct { b: String },
+}
+
+#[test]
+fn internally_tagged() {
+    roundtrip(Internal::Unit, "(type:\"Unit\",)");
+    roundtrip(Internal::Newtype(Inner { a: 1 }), "(type:\"Newtype\",a:1,)");
+    roundtrip(Internal::Struct { b: "x".to_owned() }, "(type:\"Struct\",b:\"x\",)");
+
+    assert_eq!(
+        from_str("(b: \"x\", type: \"Struct\")"),
+        Ok(Internal::Struct { b: "x".to_owned() })
+    );
+    assert!(from_str::<Internal>("(type: \"Other\")").is_err());
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "t", content = "c")]
+enum Adjacent {
+    Unit,
+    Newtype(Inner),
+    Tuple(u8, bool),
+    Struct { b: String },
+}
+
+#[test]
+fn adjacently_tagged() {
+    roundtrip(Adjacent::Unit, "(t:Unit,)");
+    roundtrip(Adjacent::Newtype(Inner { a: 1 }), "(t:Newtype,c:(a:1,),)");
+    roundtrip(Adjacent::Tuple(1, true), "(t:Tuple,c:(1,true,),)");
+    roundtrip(Adjacent::Struct { b: "x".to_owned() }, "(t:Struct,c:(b:\"x\",),)");
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Untagged {
+    Struct { b: String },
+    Newtype(Inner),
+    Tuple(u8, bool),
+    Unit,
+}
+
+#[test]
+fn untagged() {
+    roundtrip(Untagged::Unit, "()");
+    roundtrip(Untagged::Newtype(Inner { a: 1 }), "(a:1,)");
+    roundtrip(Untagged::Tuple(1, true), "(1,true,)");
+    roundtrip(Untagged::Struct { b: "x".to_owned() }, "(b:\"x\",)");
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+enum Numbers {
+    Point { x: i8, y: u64, z: f32 },
+}
+
+#[test]
+fn buffered_numbers() {
+    // Integers stay integers while the tag is looked for
+    assert_eq!(
+        from_str("(x: -3, y: 18446744073709551615, z: 1, kind: \"Point\")"),
+        Ok(Numbers::Point {
+            x: -3,
+            y: u64::MAX,
+            z: 1.0,
+        })
+    );
+    assert!(from_str::<Numbers>("(kind: \"Point\", x: 1.5, y: 0, z: 0)").is_err());
+}
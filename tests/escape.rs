@@ -10,10 +10,14 @@ use serde::{Deserialize, Serialize};
 
 #[test]
 fn test_escape_basic() {
-    assert_eq!(to_string(&"\x07").unwrap(), "\"\\u{7}\"");
+    assert_eq!(to_string(&"\x07").unwrap(), "\"\\x07\"");
+    assert_eq!(to_string(&"\0\x7f\u{80}").unwrap(), "\"\\0\\x7f\\u{80}\"");
+    assert_eq!(to_string(&'\x1b').unwrap(), "'\\x1b'");
 
     assert_eq!(from_str::<String>("\"\\x07\"").unwrap(), "\x07");
     assert_eq!(from_str::<String>("\"\\u{7}\"").unwrap(), "\x07");
+    assert_eq!(from_str::<String>("\"\\x41\\0\\x7F\"").unwrap(), "A\0\x7f");
+    assert_eq!(from_str::<char>("'\\0'").unwrap(), '\0');
 }
 
 fn check_same<T>(t: T)
@@ -34,12 +38,12 @@ fn test_ascii_10() {
 
 #[test]
 fn test_ascii_chars() {
-    (1..128).into_iter().flat_map(from_u32).for_each(check_same)
+    (1..128).flat_map(from_u32).for_each(check_same)
 }
 
 #[test]
 fn test_ascii_string() {
-    let s: String = (1..128).into_iter().flat_map(from_u32).collect();
+    let s: String = (1..128).flat_map(from_u32).collect();
 
     check_same(s);
 }
@@ -71,3 +75,113 @@ fn test_chars() {
 fn test_nul_in_string() {
     check_same("Hello\0World!".to_owned());
 }
+
+#[test]
+fn test_escape_strategies() {
+    use ron::ser::{to_string_pretty, Escape, PrettyConfig};
+
+    let s: String = (1..0x300)
+        .flat_map(from_u32)
+        .chain("♠ß\u{715}".chars())
+        .collect();
+    let value = (s, '\n', 'ü', '\'', '"');
+
+    for &escape in &[Escape::Minimal, Escape::Utf8, Escape::Ascii] {
+        let config = PrettyConfig {
+            escape,
+            ..PrettyConfig::default()
+        };
+        let serial = to_string_pretty(&value, config).unwrap();
+
+        assert_eq!(serial.is_ascii(), escape == Escape::Ascii);
+        assert_eq!(from_str(&serial), Ok(value.clone()));
+    }
+}
+
+#[test]
+fn test_ascii_unicode_escapes() {
+    use ron::ser::{to_string_pretty, Escape, PrettyConfig};
+
+    let config = PrettyConfig {
+        escape: Escape::Ascii,
+        ..PrettyConfig::default()
+    };
+
+    assert_eq!(
+        to_string_pretty(&"Grüße ♠ \u{1f980}", config.clone()).unwrap(),
+        "\"Gr\\u{fc}\\u{df}e \\u{2660} \\u{1f980}\""
+    );
+    assert_eq!(to_string_pretty(&'é', config).unwrap(), "'\\u{e9}'");
+
+    assert_eq!(from_str("'\\u{1f980}'"), Ok('\u{1f980}'));
+    assert_eq!(from_str("\"\\u{00e9}\\u{C9}\""), Ok("éÉ".to_owned()));
+    assert!(from_str::<char>("'\\u{110000}'").is_err());
+    assert!(from_str::<char>("'\\u{}'").is_err());
+}
+
+#[test]
+fn test_invalid_unicode_escapes() {
+    use ron::de::{Error, ParseError};
+
+    let why = |input: &str| match from_str::<String>(input) {
+        Err(Error::Parser(ParseError::InvalidEscape(why), _)) => why,
+        other => panic!("{:?}", other),
+    };
+
+    // JSON-style escapes aren't supported, only Rust-style ones
+    assert_eq!(why("\"\\u00e9\""), "Missing {");
+    assert_eq!(why("\"\\u{1234567}\""), "No } at the end");
+    assert_eq!(why("\"\\u{d800}\""), "Lone surrogate");
+    assert_eq!(why("\"\\u{}\""), "Expected 1-6 digits, got 0 digits");
+    assert_eq!(why("\"\\x80\""), "Not an ASCII character");
+    assert_eq!(why("\"\\x4\""), "Non-hex digit found");
+
+    assert_eq!(
+        from_str("\"\\u{1F600} \\u{10ffff}\""),
+        Ok("\u{1f600} \u{10ffff}".to_owned())
+    );
+}
+
+#[test]
+fn test_lone_surrogates() {
+    use ron::de::{Error, Options, ParseError, Position};
+
+    fn lone<T>(col: usize, offset: usize) -> Result<T, Error> {
+        Err(Error::Parser(
+            ParseError::InvalidEscape("Lone surrogate"),
+            Position {
+                col,
+                line: 1,
+                offset,
+            },
+        ))
+    }
+
+    let json = Options::new().with_json(true);
+
+    assert_eq!(json.from_str::<String>("\"ab\\ud83d\""), lone(4, 3));
+    assert_eq!(json.from_str::<String>("\"\\ude00\\ud83d\""), lone(2, 1));
+    assert_eq!(json.from_str::<String>("\"\\ud83d\\u0041\""), lone(2, 1));
+    assert_eq!(json.from_str::<char>("'\\ud83d'"), lone(2, 1));
+    assert_eq!(from_str::<String>("\"\\u{dfff}\""), lone(2, 1));
+
+    let salvage = json.clone().with_replace_lone_surrogates(true);
+
+    assert_eq!(
+        salvage.from_str("\"\\ud83d\\ud83d\\ude00 \\ude00!\""),
+        Ok("\u{fffd}\u{1f600} \u{fffd}!".to_owned())
+    );
+    assert_eq!(salvage.from_str("\"\\ud83d\\u0041\""), Ok("\u{fffd}A".to_owned()));
+    assert_eq!(salvage.from_str("'\\u{d800}'"), Ok('\u{fffd}'));
+    assert_eq!(
+        salvage.from_str::<String>("\"\\u{110000}\""),
+        Err(Error::Parser(
+            ParseError::InvalidEscape("Not a valid char"),
+            Position {
+                col: 12,
+                line: 1,
+                offset: 11,
+            },
+        ))
+    );
+}
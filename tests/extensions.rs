@@ -50,7 +50,7 @@ const CONFIG_U_NT: &str = "
 
 #[test]
 fn unwrap_newtypes() {
-    let d: Struct = ron::de::from_str(&CONFIG_U_NT).expect("Failed to deserialize");
+    let d: Struct = ron::de::from_str(CONFIG_U_NT).expect("Failed to deserialize");
 
     println!("unwrap_newtypes: {:#?}", d);
 }
@@ -78,7 +78,149 @@ const CONFIG_I_S: &str = "
 
 #[test]
 fn implicit_some() {
-    let d: Struct = ron::de::from_str(&CONFIG_I_S).expect("Failed to deserialize");
+    let d: Struct = ron::de::from_str(CONFIG_I_S).expect("Failed to deserialize");
 
     println!("implicit_some: {:#?}", d);
 }
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Window {
+    title: Option<String>,
+    size: Option<(u32, u32)>,
+}
+
+#[test]
+fn extensions_header() {
+    use ron::ser::{to_string_pretty, PrettyConfig};
+
+    let value = Window {
+        title: Some("Main".to_owned()),
+        size: None,
+    };
+    let config = PrettyConfig {
+        new_line: "\n".to_owned(),
+        implicit_some: true,
+        extensions_header: true,
+        ..PrettyConfig::default()
+    };
+    let serial = to_string_pretty(&value, config.clone()).unwrap();
+
+    assert_eq!(
+        serial,
+        "#![enable(implicit_some)]
+
+(
+    title: \"Main\",
+    size: None,
+)"
+    );
+    assert_eq!(ron::de::from_str(&serial), Ok(value));
+
+    let config = PrettyConfig {
+        implicit_some: false,
+        ..config
+    };
+
+    assert_eq!(to_string_pretty(&(), config).unwrap(), "()");
+}
+
+#[test]
+fn implicit_some_nested_options() {
+    use ron::ser::{to_string_pretty, PrettyConfig};
+
+    let value: Vec<Option<Option<Option<u8>>>> = vec![
+        None,
+        Some(None),
+        Some(Some(None)),
+        Some(Some(Some(1))),
+    ];
+    let config = PrettyConfig {
+        depth_limit: 0,
+        new_line: "\n".to_owned(),
+        implicit_some: true,
+        extensions_header: true,
+        ..PrettyConfig::default()
+    };
+    let serial = to_string_pretty(&value, config).unwrap();
+
+    assert_eq!(
+        serial,
+        "#![enable(implicit_some)]\n\n[None,Some(None),Some(Some(None)),1,]"
+    );
+    assert_eq!(ron::de::from_str(&serial), Ok(value));
+}
+
+#[test]
+fn unwrap_newtypes_roundtrip() {
+    use ron::ser::{to_string_pretty, PrettyConfig};
+
+    let value = Struct {
+        tuple: ((), NewType(0.5), TupleStruct(UnitStruct, -5)),
+        vec: vec![None, Some(UnitStruct)],
+        map: vec![(Key(7), Enum::Bool(true))].into_iter().collect(),
+    };
+    let config = PrettyConfig {
+        new_line: "\n".to_owned(),
+        unwrap_newtypes: true,
+        extensions_header: true,
+        ..PrettyConfig::default()
+    };
+    let serial = to_string_pretty(&value, config).unwrap();
+
+    assert_eq!(
+        serial,
+        "#![enable(unwrap_newtypes)]
+
+(
+    tuple: ((), 0.5, ((), -5)),
+    vec: [
+        None,
+        Some(()),
+    ],
+    map: {
+        7: Bool(true),
+    },
+)"
+    );
+    assert_eq!(ron::de::from_str(&serial), Ok(value));
+}
+
+#[test]
+fn default_extensions() {
+    use ron::de::{Deserializer, Options};
+    use ron::extensions::Extensions;
+
+    let input = "(
+    tuple: ((), 0.5, ((), -5)),
+    vec: [None, ()],
+    map: {},
+)";
+
+    assert!(ron::de::from_str::<Struct>(input).is_err());
+
+    let options = Options::new()
+        .with_default_extensions(Extensions::UNWRAP_NEWTYPES | Extensions::IMPLICIT_SOME);
+    let value: Struct = options.from_str(input).unwrap();
+
+    assert_eq!(value.vec, vec![None, Some(UnitStruct)]);
+
+    // Extensions enabled by the input are added to the default ones
+    let options = Options::new().with_default_extensions(Extensions::UNWRAP_NEWTYPES);
+
+    assert_eq!(
+        options.from_str(&format!("#![enable(implicit_some)] {}", input)),
+        Ok(value)
+    );
+
+    let de = options.deserializer(b"#![enable(implicit_some)] 5").unwrap();
+
+    assert_eq!(de.extensions(), Extensions::all());
+    assert_eq!(
+        Deserializer::from_str("5").unwrap().extensions(),
+        Extensions::empty()
+    );
+    assert_eq!(
+        Extensions::from_ident(b"implicit_some"),
+        Some(Extensions::IMPLICIT_SOME)
+    );
+}
@@ -0,0 +1,94 @@
+extern crate ron;
+#[macro_use]
+extern crate serde;
+
+use std::collections::BTreeMap;
+
+use ron::de::from_str;
+use ron::ser::to_string;
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+enum Mode {
+    Windowed,
+    Fullscreen,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Window {
+    width: u32,
+    height: u32,
+    mode: Mode,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Config {
+    title: String,
+    #[serde(flatten)]
+    window: Window,
+    #[serde(flatten)]
+    extra: BTreeMap<String, Option<u8>>,
+}
+
+fn config() -> Config {
+    let mut extra = BTreeMap::new();
+    extra.insert("vsync".to_owned(), Some(1));
+
+    Config {
+        title: "Main".to_owned(),
+        window: Window {
+            width: 800,
+            height: 600,
+            mode: Mode::Fullscreen,
+        },
+        extra,
+    }
+}
+
+#[test]
+fn flattened_struct() {
+    let input = "Config(
+        title: \"Main\",
+        width: 800,
+        mode: Fullscreen,
+        vsync: Some(1),
+        height: 600,
+    )";
+
+    assert_eq!(from_str(input), Ok(config()));
+}
+
+#[test]
+fn roundtrip_flattened_struct() {
+    let serial = to_string(&config()).unwrap();
+
+    assert_eq!(
+        serial,
+        "{\"title\":\"Main\",\"width\":800,\"height\":600,\"mode\":Fullscreen,\"vsync\":Some(1),}"
+    );
+    assert_eq!(from_str(&serial), Ok(config()));
+}
+
+#[test]
+fn flattened_unknown_fields() {
+    use ron::value::{Number, UnknownFields, Value};
+
+    #[derive(Debug, Deserialize)]
+    struct Settings {
+        title: String,
+        #[serde(flatten)]
+        unknown: UnknownFields,
+    }
+
+    let settings: Settings = from_str("(title: \"Main\", mode: Fullscreen, size: (800, 600))")
+        .unwrap();
+
+    assert_eq!(settings.title, "Main");
+    assert_eq!(settings.unknown["mode"], Value::String("Fullscreen".to_owned()));
+    assert_eq!(
+        settings.unknown["size"],
+        Value::Seq(vec![
+            Value::Number(Number::new(800.0)),
+            Value::Number(Number::new(600.0)),
+        ])
+    );
+}
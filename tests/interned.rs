@@ -0,0 +1,77 @@
+extern crate ron;
+#[macro_use]
+extern crate serde;
+extern crate serde_json;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use ron::de::{Deserializer, Interned, Options, StreamDeserializer};
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Sprite {
+    texture: Interned,
+    normals: Option<Interned>,
+}
+
+#[test]
+fn test_shared_buffers() {
+    let input = r#"{
+        "grass": (texture: "textures/grass.png", normals: Some("textures/flat.png")),
+        "hill": (texture: "textures/gr\u{61}ss.png", normals: Some("textures/flat.png")),
+        "rock": (texture: "textures/rock.png", normals: None),
+    }"#;
+    let options = Options::new().with_intern_strings(true);
+    let sprites: HashMap<Interned, Sprite> = options.from_str(input).unwrap();
+
+    let grass = &sprites["grass"];
+    let hill = &sprites["hill"];
+
+    assert_eq!(&*hill.texture, "textures/grass.png");
+    assert!(Arc::ptr_eq(&grass.texture.0, &hill.texture.0));
+    assert!(Arc::ptr_eq(
+        &grass.normals.as_ref().unwrap().0,
+        &hill.normals.as_ref().unwrap().0
+    ));
+    assert!(!Arc::ptr_eq(&grass.texture.0, &sprites["rock"].texture.0));
+
+    // Without interning, every string has a buffer of its own
+    let sprites: HashMap<Interned, Sprite> = ron::de::from_str(input).unwrap();
+
+    assert_eq!(sprites["hill"].texture, sprites["grass"].texture);
+    assert!(!Arc::ptr_eq(&sprites["hill"].texture.0, &sprites["grass"].texture.0));
+}
+
+#[test]
+fn test_stream() {
+    let mut de = Deserializer::from_str(r#""a" "b" "a""#).unwrap();
+    de.set_intern_strings(true);
+
+    let strings: Vec<Interned> = StreamDeserializer::new(de)
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert!(Arc::ptr_eq(&strings[0].0, &strings[2].0));
+}
+
+#[test]
+fn test_other_formats() {
+    let sprite = Sprite {
+        texture: "grass.png".into(),
+        normals: None,
+    };
+
+    assert_eq!(
+        ron::ser::to_string(&sprite).unwrap(),
+        "(texture:\"grass.png\",normals:None,)"
+    );
+
+    let json = serde_json::to_string(&sprite).unwrap();
+    let sprite: Sprite = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(&*sprite.texture, "grass.png");
+
+    let options = Options::new().with_intern_strings(true);
+
+    assert!(options.from_str::<Interned>("5").is_err());
+}
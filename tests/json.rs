@@ -0,0 +1,130 @@
+extern crate ron;
+#[macro_use]
+extern crate serde;
+extern crate serde_json;
+
+use std::collections::HashMap;
+
+use ron::de::{Error, Options, ParseError, Position};
+use ron::value::Value;
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Unit;
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Meters(f32);
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+enum Shape {
+    Empty,
+    Circle(f32),
+    Rect(f32, f32),
+    Polygon { points: Vec<(f32, f32)> },
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Config {
+    name: String,
+    enabled: bool,
+    parent: Option<String>,
+    scale: Option<u8>,
+    size: Meters,
+    origin: (i32, i32),
+    marker: Unit,
+    shapes: Vec<Shape>,
+    tags: HashMap<String, u8>,
+}
+
+fn json() -> Options {
+    Options::new().with_json(true)
+}
+
+#[test]
+fn test_serde_json_output() {
+    let config = Config {
+        name: "Grüße \"world\"\n".to_owned(),
+        enabled: true,
+        parent: None,
+        scale: Some(2),
+        size: Meters(1.5),
+        origin: (-3, 4),
+        marker: Unit,
+        shapes: vec![
+            Shape::Empty,
+            Shape::Circle(1.0),
+            Shape::Rect(2.0, 3.0),
+            Shape::Polygon {
+                points: vec![(0.0, 0.0), (1.0, 0.5)],
+            },
+        ],
+        tags: vec![("a".to_owned(), 1)].into_iter().collect(),
+    };
+
+    let compact = serde_json::to_string(&config).unwrap();
+    let pretty = serde_json::to_string_pretty(&config).unwrap();
+
+    assert_eq!(json().from_str(&compact), Ok(config));
+    assert_eq!(
+        json().from_str::<Config>(&pretty).unwrap(),
+        json().from_str::<Config>(&compact).unwrap()
+    );
+}
+
+#[test]
+fn test_ron_still_works() {
+    assert_eq!(json().from_str("Some(5)"), Ok(Some(5)));
+    assert_eq!(json().from_str("None"), Ok(None::<u8>));
+    assert_eq!(json().from_str("Meters(2)"), Ok(Meters(2.0)));
+    assert_eq!(json().from_str("Rect(1, 2)"), Ok(Shape::Rect(1.0, 2.0)));
+    assert_eq!(json().from_str("(1, 2)"), Ok((1, 2)));
+    assert_eq!(json().from_str("()"), Ok(Unit));
+}
+
+#[test]
+fn test_json_forms() {
+    assert_eq!(json().from_str("null"), Ok(None::<u8>));
+    assert_eq!(json().from_str("null"), Ok(()));
+    assert_eq!(json().from_str("[1, 2]"), Ok((1, 2)));
+    assert_eq!(json().from_str("{\"Circle\": 2}"), Ok(Shape::Circle(2.0)));
+    assert_eq!(json().from_str("{\"Empty\": null}"), Ok(Shape::Empty));
+    assert_eq!(json().from_str("\"Empty\""), Ok(Shape::Empty));
+    assert_eq!(
+        json().from_str("\"\\u00e9\\ud83d\\ude00 \\/\\b\\f\""),
+        Ok("\u{e9}\u{1f600} /\u{8}\u{c}".to_owned())
+    );
+    assert_eq!(
+        json().from_str::<Value>("{\"a\": [null, true]}"),
+        ron::de::from_str("{\"a\": [None, true]}")
+    );
+}
+
+#[test]
+fn test_json_is_opt_in() {
+    assert!(ron::de::from_str::<Option<u8>>("null").is_err());
+    assert!(ron::de::from_str::<(u8, u8)>("[1, 2]").is_err());
+    assert!(ron::de::from_str::<String>("\"\\u00e9\"").is_err());
+}
+
+fn err<T>(kind: ParseError, col: usize, offset: usize) -> Result<T, Error> {
+    Err(Error::Parser(kind, Position { col, line: 1, offset }))
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(
+        json().from_str::<String>("\"\\ud83d\""),
+        err(ParseError::InvalidEscape("Lone surrogate"), 2, 1)
+    );
+    assert_eq!(
+        json().from_str::<String>("\"\\ude00\""),
+        err(ParseError::InvalidEscape("Lone surrogate"), 2, 1)
+    );
+    assert_eq!(
+        json().from_str::<Shape>("{\"Circle\" 2}"),
+        err(ParseError::ExpectedMapColon(Some("2".to_owned())), 11, 10)
+    );
+    assert_eq!(
+        json().from_str::<Shape>("{\"Circle\": 2, \"Empty\": null}"),
+        err(ParseError::ExpectedMapEnd(Some("\"Empty\"".to_owned())), 15, 14)
+    );
+}
@@ -1,4 +1,5 @@
 extern crate ron;
+extern crate serde;
 
 #[test]
 fn test_hex() {
@@ -20,3 +21,256 @@ fn test_oct() {
     assert_eq!(ron::de::from_str("0o051"), Ok(0o051));
     assert_eq!(ron::de::from_str("0o150700"), Ok(0o150700));
 }
+
+#[test]
+fn test_128_bit() {
+    use ron::ser::{to_string, to_string_pretty, PrettyConfig, Radix};
+
+    let nonce = u128::MAX;
+    let serial = to_string(&nonce).unwrap();
+
+    assert_eq!(serial, "340282366920938463463374607431768211455");
+    assert_eq!(ron::de::from_str(&serial), Ok(nonce));
+
+    let counter = i128::MIN;
+    let serial = to_string(&counter).unwrap();
+
+    assert_eq!(serial, "-170141183460469231731687303715884105728");
+    assert_eq!(ron::de::from_str(&serial), Ok(counter));
+    assert_eq!(ron::de::from_str("-9223372036854775808"), Ok(i64::MIN));
+
+    let config = PrettyConfig {
+        unsigned_radix: Radix::Hexadecimal,
+        ..PrettyConfig::default()
+    };
+    let serial = to_string_pretty(&(1u128 << 100), config).unwrap();
+
+    assert_eq!(serial, "0x10000000000000000000000000");
+    assert_eq!(ron::de::from_str(&serial), Ok(1u128 << 100));
+
+    // Struct fields, sequence elements and tuple members, on multiple lines
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Big {
+        nonce: u128,
+        counter: i128,
+    }
+
+    let config = PrettyConfig {
+        new_line: "\n".to_owned(),
+        ..PrettyConfig::default()
+    };
+    let big = Big {
+        nonce: u128::MAX,
+        counter: i128::MIN,
+    };
+    let serial = to_string_pretty(&big, config.clone()).unwrap();
+
+    assert_eq!(
+        serial,
+        "(\n    nonce: 340282366920938463463374607431768211455,\n    \
+         counter: -170141183460469231731687303715884105728,\n)"
+    );
+    assert_eq!(ron::de::from_str(&serial), Ok(big));
+
+    let nonces = vec![u128::MAX, 0];
+    let serial = to_string_pretty(&nonces, config.clone()).unwrap();
+
+    assert_eq!(ron::de::from_str(&serial), Ok(nonces));
+
+    let pair = (u128::MAX, i128::MIN);
+    let serial = to_string_pretty(&pair, config).unwrap();
+
+    assert_eq!(ron::de::from_str(&serial), Ok(pair));
+
+    // One more than the maximum
+    assert!(ron::de::from_str::<u128>("340282366920938463463374607431768211456").is_err());
+    assert!(ron::de::from_str::<i128>("170141183460469231731687303715884105728").is_err());
+}
+
+#[test]
+fn test_non_finite_floats() {
+    use ron::ser::{to_string, to_string_pretty, Error, PrettyConfig, Serializer};
+    use ron::value::Value;
+    use serde::Serialize;
+
+    assert_eq!(to_string(&f64::INFINITY), Err(Error::NonFiniteFloat));
+
+    let config = PrettyConfig {
+        non_finite_floats: true,
+        ..PrettyConfig::default()
+    };
+    let floats = (f64::INFINITY, f32::NEG_INFINITY, f64::NAN);
+    let serial = to_string_pretty(&floats, config).unwrap();
+
+    assert_eq!(serial, "(inf, -inf, NaN)");
+
+    let (inf, neg_inf, nan): (f64, f32, f64) = ron::de::from_str(&serial).unwrap();
+
+    assert_eq!(inf, f64::INFINITY);
+    assert_eq!(neg_inf, f32::NEG_INFINITY);
+    assert!(nan.is_nan());
+    assert_eq!(ron::de::from_str("+inf"), Ok(f64::INFINITY));
+
+    let mut s = Serializer::with_config(None);
+    s.set_non_finite_floats(true);
+    f32::NAN.serialize(&mut s).unwrap();
+
+    assert_eq!(s.into_output_string(), "NaN");
+
+    let value: Value = ron::de::from_str("[1, inf, -inf, NaN]").unwrap();
+
+    match value {
+        Value::Seq(ref values) => match values[..] {
+            [_, Value::Number(inf), Value::Number(neg_inf), Value::Number(nan)] => {
+                assert_eq!(inf.get(), f64::INFINITY);
+                assert_eq!(neg_inf.get(), f64::NEG_INFINITY);
+                assert!(nan.get().is_nan());
+            }
+            _ => panic!("{:?}", values),
+        },
+        _ => panic!("{:?}", value),
+    }
+    assert_eq!(ron::de::from_str("[1, inf, -inf, NaN]"), Ok(value));
+}
+
+#[test]
+fn test_out_of_range() {
+    use ron::de::{from_str, Error, ParseError, Position};
+
+    fn out_of_range(literal: &str, ty: &'static str) -> ParseError {
+        ParseError::IntegerOutOfRange {
+            literal: literal.to_owned(),
+            ty,
+        }
+    }
+
+    assert_eq!(
+        from_str::<Vec<u8>>("[255, 300]"),
+        Err(Error::Parser(
+            out_of_range("300", "u8"),
+            Position {
+                col: 7,
+                line: 1,
+                offset: 6,
+            }
+        ))
+    );
+
+    let error = from_str::<u8>("-1").unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "1:1: Integer `-1` doesn't fit into `u8`, which ranges from 0 to 255"
+    );
+
+    let kind = |res: Result<i64, Error>| match res {
+        Err(Error::Parser(kind, _)) => kind,
+        res => panic!("{:?}", res),
+    };
+
+    assert_eq!(
+        kind(from_str::<i8>("-0x81").map(i64::from)),
+        out_of_range("-0x81", "i8")
+    );
+    assert_eq!(
+        kind(from_str::<u32>("+4_294_967_296").map(i64::from)),
+        out_of_range("+4_294_967_296", "u32")
+    );
+    assert_eq!(
+        from_str::<i128>("170141183460469231731687303715884105728")
+            .unwrap_err()
+            .to_string(),
+        "1:1: Integer `170141183460469231731687303715884105728` doesn't fit into `i128`, \
+         which ranges from -170141183460469231731687303715884105728 \
+         to 170141183460469231731687303715884105727"
+    );
+    assert_eq!(from_str("-0x80"), Ok(i8::MIN));
+    assert_eq!(from_str("-0"), Ok(0u8));
+}
+
+#[test]
+fn test_underscores() {
+    use ron::de::from_str;
+    use ron::value::Value;
+
+    assert_eq!(from_str("1_000_000"), Ok(1_000_000u32));
+    assert_eq!(from_str("-1_000"), Ok(-1_000i32));
+    assert_eq!(from_str("0xFF_FF"), Ok(0xFF_FFu16));
+    assert_eq!(from_str("0b1010_1010"), Ok(0b1010_1010u8));
+    assert_eq!(from_str("1_000.000_1"), Ok(1_000.000_1f64));
+    assert_eq!(from_str("1e1_0"), Ok(1e10f64));
+    assert_eq!(from_str("[1_0, 2_0]"), Ok(vec![10u8, 20]));
+    assert_eq!(from_str::<Value>("1_000"), from_str("1000"));
+    // An underscore may follow the prefix, like in Rust
+    assert_eq!(from_str("0x_FF"), Ok(0xFFu32));
+    assert_eq!(from_str("0b_1010"), Ok(0b1010u8));
+    assert_eq!(from_str("0o_17"), Ok(0o17u16));
+    assert_eq!(from_str("-0x_F_F"), Ok(-0xFFi32));
+    assert_eq!(from_str::<Value>("0x_FF"), from_str("255"));
+
+    assert!(from_str::<u32>("1_000_").is_err());
+    assert!(from_str::<u32>("0x_").is_err());
+    assert!(from_str::<u32>("0x_FF_").is_err());
+    assert!(from_str::<f64>("1_.5").is_err());
+    assert!(from_str::<f64>("1._5").is_err());
+}
+
+#[test]
+fn test_hand_written_forms() {
+    use ron::de::from_str;
+    use ron::ser::to_string;
+
+    assert_eq!(from_str("+3"), Ok(3u8));
+    assert_eq!(from_str("+3"), Ok(3i8));
+    assert_eq!(from_str("+0x10"), Ok(16u32));
+    assert_eq!(from_str("+0.5"), Ok(0.5f32));
+    assert_eq!(from_str(".5"), Ok(0.5f64));
+    assert_eq!(from_str("-.5"), Ok(-0.5f64));
+    assert_eq!(from_str("5."), Ok(5.0f64));
+    assert_eq!(from_str("5.e3"), Ok(5000.0f32));
+    assert!(from_str::<u8>("++3").is_err());
+    assert!(from_str::<i8>("+-3").is_err());
+    assert!(from_str::<f64>(".").is_err());
+
+    // They're written in the standard form
+    let values: (u8, f32, f64, f64) = from_str("(+3, +0.5, .5, 5.)").unwrap();
+    let serial = to_string(&values).unwrap();
+
+    assert_eq!(serial, "(3,0.5,0.5,5,)");
+    assert_eq!(from_str(&serial), Ok(values));
+}
+
+#[test]
+fn test_float_rounding() {
+    use ron::de::from_str;
+    use ron::ser::to_string;
+
+    // Inputs whose rounding is hard to get right
+    assert_eq!(from_str("9007199254740993"), Ok(9007199254740992f64));
+    assert_eq!(from_str("9007199254740995"), Ok(9007199254740996f64));
+    assert_eq!(from_str("2.2250738585072011e-308"), Ok(2.225073858507201e-308f64));
+    assert_eq!(from_str("4.9406564584124654e-324"), Ok(5e-324f64));
+    assert_eq!(from_str("1.7976931348623157e308"), Ok(f64::MAX));
+    assert_eq!(from_str("0.1"), Ok(0.1f64));
+    assert_eq!(from_str("16777217"), Ok(16777216f32));
+    assert_eq!(from_str("1.00000017881393432617187499"), Ok(1.0000001f32));
+    assert_eq!(from_str("1.000000178813934326171875"), Ok(1.0000002f32));
+
+    // Every float reads back as itself
+    let mut state = 0x853c_49e6_748f_ea9bu64;
+    for _ in 0..10_000 {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+
+        let double = f64::from_bits(state);
+        if double.is_finite() {
+            assert_eq!(from_str::<f64>(&to_string(&double).unwrap()), Ok(double));
+        }
+
+        let single = f32::from_bits((state >> 32) as u32);
+        if single.is_finite() {
+            assert_eq!(from_str::<f32>(&to_string(&single).unwrap()), Ok(single));
+        }
+    }
+}
@@ -0,0 +1,127 @@
+extern crate ron;
+
+use ron::de::{Error, Event, ParseError, Parser, Position};
+use ron::extensions::Extensions;
+
+/// Feeds `input` in chunks of `size` bytes, and collects the events.
+fn events(input: &str, size: usize) -> Result<Vec<Event>, Error> {
+    let mut parser = Parser::new();
+    let mut events = Vec::new();
+
+    for chunk in input.as_bytes().chunks(size) {
+        events.extend(parser.feed(chunk)?);
+    }
+    events.extend(parser.finish()?);
+
+    Ok(events)
+}
+
+fn check(input: &str, expected: &[Event]) {
+    for size in 1..=input.len() {
+        assert_eq!(events(input, size).unwrap(), expected, "chunks of {}", size);
+    }
+}
+
+fn name(s: &str) -> Event {
+    Event::Identifier(s.to_owned())
+}
+
+fn field(s: &str) -> Event {
+    Event::Field(s.to_owned())
+}
+
+#[test]
+fn test_struct() {
+    check(
+        "Config(width: 800, \"title\": \"Grüße\", ratio: 1.5, fullscreen: false,)",
+        &[
+            name("Config"),
+            Event::StructStart,
+            field("width"),
+            Event::Integer(800),
+            field("title"),
+            Event::Str("Grüße".to_owned()),
+            field("ratio"),
+            Event::Float(1.5),
+            field("fullscreen"),
+            Event::Bool(false),
+            Event::StructEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_collections() {
+    check(
+        "[Some(-3), None, (1, 'ß'), { \"a\": b\"\\x01\", x\"ff\": r#\"\"q\"\"# }]",
+        &[
+            Event::SeqStart,
+            name("Some"),
+            Event::StructStart,
+            Event::Integer(-3),
+            Event::StructEnd,
+            name("None"),
+            Event::StructStart,
+            Event::Integer(1),
+            Event::Char('ß'),
+            Event::StructEnd,
+            Event::MapStart,
+            Event::Str("a".to_owned()),
+            Event::Bytes(vec![1]),
+            Event::Bytes(vec![0xff]),
+            Event::Str("\"q\"".to_owned()),
+            Event::MapEnd,
+            Event::SeqEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_comments_and_attributes() {
+    check(
+        "#![enable(implicit_some)] // about\n/* a /* nested */ one */ &0 [*0, inf] // end",
+        &[
+            Event::Extensions(Extensions::IMPLICIT_SOME),
+            Event::Anchor(0),
+            Event::SeqStart,
+            Event::Reference(0),
+            Event::Float(f64::INFINITY),
+            Event::SeqEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_events_as_they_come() {
+    let mut parser = Parser::new();
+
+    assert_eq!(parser.feed(b"[12").unwrap(), vec![Event::SeqStart]);
+    assert_eq!(parser.feed(b"3, Un").unwrap(), vec![Event::Integer(123)]);
+    assert_eq!(parser.feed(b"it]").unwrap(), vec![name("Unit"), Event::SeqEnd]);
+    assert_eq!(parser.finish().unwrap(), vec![]);
+}
+
+#[test]
+fn test_errors() {
+    let err = |kind, line, col, offset| {
+        Err(Error::Parser(kind, Position { line, col, offset }))
+    };
+    let found = |token: &str| Some(token.to_owned());
+
+    assert_eq!(events("[1 2]", 1), err(ParseError::ExpectedComma(found("2")), 1, 4, 3));
+    assert_eq!(events("(a: 1]", 2), err(ParseError::ExpectedStructEnd(found("]")), 1, 6, 5));
+    assert_eq!(events("{1 2}", 3), err(ParseError::ExpectedMapColon(found("2")), 1, 4, 3));
+    assert_eq!(events("1\n2", 1), err(ParseError::TrailingCharacters, 2, 1, 2));
+    assert_eq!(
+        events("[\n  \"é\\q\"]", 1),
+        err(ParseError::InvalidEscape("Unknown escape character"), 2, 7, 9)
+    );
+    assert_eq!(events("[1,", 1), err(ParseError::Eof, 1, 4, 3));
+    assert_eq!(events("[1 /* x", 2), err(ParseError::UnclosedBlockComment, 1, 8, 7));
+    assert_eq!(events("", 1), err(ParseError::Eof, 1, 1, 0));
+
+    // The error stays
+    let mut parser = Parser::new();
+    assert!(parser.feed(b"]").is_err());
+    assert_eq!(parser.feed(b"1"), err(ParseError::UnexpectedByte(']'), 1, 1, 0));
+}
@@ -0,0 +1,68 @@
+extern crate ron;
+#[macro_use]
+extern crate serde;
+
+use ron::de::from_str;
+use ron::ser::{to_string, to_string_pretty, PrettyConfig};
+use ron::value::RawValue;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Plugin {
+    name: String,
+    config: RawValue,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Audio {
+    volume: u8,
+    muted: bool,
+}
+
+#[test]
+fn roundtrip() {
+    let input = "[(name:\"audio\",config:(volume:80,muted:false),),\
+                 (name:\"unknown\",config:Future([1,2,3]),),]";
+    let plugins: Vec<Plugin> = from_str(input).unwrap();
+
+    assert_eq!(
+        plugins[0].config.into_rust::<Audio>(),
+        Ok(Audio {
+            volume: 80,
+            muted: false,
+        })
+    );
+    assert_eq!(plugins[1].config.get_ron(), "Future([1,2,3])");
+
+    // Sections nobody read are written back the way they were
+    assert_eq!(to_string(&plugins).unwrap(), input);
+
+    let plugin = Plugin {
+        name: "audio".to_owned(),
+        config: RawValue::from_rust(&Audio {
+            volume: 10,
+            muted: true,
+        })
+        .unwrap(),
+    };
+    let config = PrettyConfig {
+        new_line: "\n".to_owned(),
+        ..PrettyConfig::default()
+    };
+
+    assert_eq!(
+        to_string_pretty(&plugin, config).unwrap(),
+        "(\n    name: \"audio\",\n    config: (volume:10,muted:true,),\n)"
+    );
+}
+
+#[test]
+fn other_formats() {
+    let plugin: Plugin = from_str("(name: \"audio\", config: (volume: 80))").unwrap();
+    let json = serde_json::to_string(&plugin).unwrap();
+
+    assert_eq!(json, r#"{"name":"audio","config":"(volume: 80)"}"#);
+
+    let plugin: Plugin = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(plugin.config.get_ron(), "(volume: 80)");
+}
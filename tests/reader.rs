@@ -0,0 +1,206 @@
+extern crate ron;
+#[macro_use]
+extern crate serde;
+
+use std::io::{self, BufReader, Read};
+
+use ron::de::{Error, Options, ParseError, Position, ReaderDeserializer};
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Record {
+    id: u32,
+    tags: Vec<String>,
+    parent: Option<u32>,
+}
+
+fn record(id: u32, tags: &[&str], parent: Option<u32>) -> Record {
+    Record {
+        id,
+        tags: tags.iter().map(|&tag| tag.to_owned()).collect(),
+        parent,
+    }
+}
+
+/// Reads an endless sequence of records, which can't be read at once.
+struct Endless {
+    next: u32,
+    pending: Vec<u8>,
+}
+
+impl Read for Endless {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            self.pending = match self.next {
+                0 => b"[".to_vec(),
+                id => format!("(id: {}, tags: [\"a, b\"], parent: None),\n", id).into_bytes(),
+            };
+            self.next += 1;
+        }
+
+        let len = self.pending.len().min(buf.len());
+        buf[..len].copy_from_slice(&self.pending[..len]);
+        self.pending.drain(..len);
+
+        Ok(len)
+    }
+}
+
+#[test]
+fn elements() {
+    let input: &[u8] = b"#!/bin/import
+#![enable(implicit_some)]
+// exported records
+[
+    (id: 1, tags: [\"x]\", \"y,\"], parent: None),
+    (id: 2, tags: [/* none ] */], parent: 1),
+    (id: 3, tags: [r#\"\"],\"#], parent: 2), // last
+]
+";
+
+    // A tiny buffer makes the reader refill often
+    for &capacity in &[1, 7, 4096] {
+        let records: Vec<Record> = ReaderDeserializer::elements(BufReader::with_capacity(
+            capacity, input,
+        ))
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+        assert_eq!(
+            records,
+            vec![
+                record(1, &["x]", "y,"], None),
+                record(2, &[], Some(1)),
+                record(3, &["\"],"], Some(2)),
+            ]
+        );
+    }
+
+    let empty: Vec<u8> = ReaderDeserializer::elements(&b" [ ] "[..])
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn endless_elements() {
+    let reader = BufReader::new(Endless {
+        next: 0,
+        pending: Vec::new(),
+    });
+    let records = ReaderDeserializer::<_, Record>::elements(reader).take(10_000);
+
+    assert_eq!(records.last(), Some(Ok(record(10_000, &["a, b"], None))));
+}
+
+#[test]
+fn documents() {
+    let input: &[u8] = b"5 Some(6)\n&0 [7] \"8\" Pair (9, 10) #![enable(implicit_some)] 11";
+    let values: Vec<ron::value::Value> = ReaderDeserializer::new(input)
+        .with_options(Options::new().with_shared_values(true))
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(values.len(), 6);
+
+    let values: Vec<ron::value::Value> = ReaderDeserializer::new(&b"None\n(1, 2) true (3)"[..])
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(values.len(), 4);
+    assert_eq!(values[0], ron::value::Value::Option(None));
+
+    let numbers: Vec<u8> = ReaderDeserializer::new(&b"1 2\n\n 3 "[..])
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(numbers, vec![1, 2, 3]);
+
+    let numbers: Vec<u8> = ReaderDeserializer::new(&b"\xEF\xBB\xBF#!/bin/sum\n1 2"[..])
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(numbers, vec![1, 2]);
+    assert_eq!(ReaderDeserializer::<_, u8>::new(&b"  "[..]).count(), 0);
+}
+
+#[test]
+fn errors() {
+    let input: &[u8] = b"[\n  (id: 1, tags: [], parent: None),\n  (id: 2, tags: [], parent: x),\n]";
+    let mut records = ReaderDeserializer::<_, Record>::elements(input);
+
+    assert!(records.next().unwrap().is_ok());
+    match records.next() {
+        Some(Err(Error::Parser(_, position))) => assert_eq!(
+            position,
+            Position {
+                col: 29,
+                line: 3,
+                offset: 65,
+            }
+        ),
+        other => panic!("{:?}", other),
+    }
+    assert!(records.next().is_none());
+
+    let error = |input: &'static [u8]| {
+        ReaderDeserializer::<_, u8>::elements(input)
+            .find_map(Result::err)
+            .unwrap()
+    };
+
+    assert_eq!(
+        error(b"(1)"),
+        Error::Parser(
+            ParseError::ExpectedArray(Some("(".to_owned())),
+            Position {
+                col: 1,
+                line: 1,
+                offset: 0,
+            }
+        )
+    );
+    match error(b"[1, 2") {
+        Error::Parser(ParseError::ExpectedArrayEnd(None), position) => assert_eq!(position.col, 6),
+        other => panic!("{:?}", other),
+    }
+    match error(b"[1] 2") {
+        Error::Parser(ParseError::TrailingCharacters, position) => assert_eq!(position.col, 5),
+        other => panic!("{:?}", other),
+    }
+    match error(b"[1 2]") {
+        Error::Parser(ParseError::TrailingCharacters, position) => assert_eq!(position.col, 4),
+        other => panic!("{:?}", other),
+    }
+    match error(b"[\"a\"]") {
+        Error::Parser(_, position) => assert_eq!(position.col, 2),
+        other => panic!("{:?}", other),
+    }
+    match error(b"\xEF\xBB\xBF[1 2]") {
+        Error::Parser(ParseError::TrailingCharacters, position) => {
+            assert_eq!((position.col, position.offset), (4, 6))
+        }
+        other => panic!("{:?}", other),
+    }
+    match error(b"\xFF\xFE[\x00]\x00") {
+        Error::Parser(ParseError::UnsupportedEncoding("UTF-16LE"), position) => {
+            assert_eq!(position.offset, 0)
+        }
+        other => panic!("{:?}", other),
+    }
+
+    // Limits apply to every element on its own
+    let options = Options::new().with_limits(ron::de::Limits {
+        max_input_len: Some(4),
+        ..Default::default()
+    });
+    let numbers: Vec<u32> = ReaderDeserializer::elements(&b"[1234, 5678]"[..])
+        .with_options(options.clone())
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(numbers, vec![1234, 5678]);
+    assert!(ReaderDeserializer::<_, u32>::elements(&b"[12345]"[..])
+        .with_options(options)
+        .any(|res| res.is_err()));
+}
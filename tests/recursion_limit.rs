@@ -0,0 +1,173 @@
+extern crate ron;
+#[macro_use]
+extern crate serde;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ron::ser::{to_string, to_string_pretty, Error, PrettyConfig, Serializer};
+use serde::ser::{Serialize, SerializeStruct, Serializer as SerdeSerializer};
+
+struct Node {
+    name: &'static str,
+    next: RefCell<Option<Rc<Node>>>,
+}
+
+impl Serialize for Node {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: SerdeSerializer,
+    {
+        let next = self.next.borrow();
+        let mut s = serializer.serialize_struct("Node", 2)?;
+        s.serialize_field("name", self.name)?;
+        s.serialize_field("next", &next.as_ref().map(|node| &**node))?;
+        s.end()
+    }
+}
+
+fn node(name: &'static str) -> Rc<Node> {
+    Rc::new(Node {
+        name,
+        next: RefCell::new(None),
+    })
+}
+
+#[test]
+fn test_cycle() {
+    let a = node("a");
+    let b = node("b");
+    *a.next.borrow_mut() = Some(b.clone());
+
+    assert_eq!(
+        to_string(&*a).unwrap(),
+        "(name:\"a\",next:Some((name:\"b\",next:None,)),)"
+    );
+
+    *b.next.borrow_mut() = Some(a.clone());
+
+    let mut s = Serializer::with_config(None);
+    s.set_recursion_limit(Some(128));
+
+    assert_eq!(a.serialize(&mut s), Err(Error::CircularOrTooDeep));
+
+    let config = PrettyConfig {
+        recursion_limit: Some(128),
+        ..PrettyConfig::default()
+    };
+
+    assert_eq!(
+        to_string_pretty(&*a, config.clone()),
+        Err(Error::CircularOrTooDeep)
+    );
+
+    let config = PrettyConfig {
+        max_width: Some(80),
+        ..config
+    };
+
+    assert_eq!(to_string_pretty(&*a, config), Err(Error::CircularOrTooDeep));
+
+    // Break the cycle, so the nodes can be dropped
+    b.next.borrow_mut().take();
+}
+
+#[derive(Serialize)]
+enum Nested {
+    Leaf,
+    Seq(Vec<Nested>),
+    Boxed(Box<Nested>),
+}
+
+fn nested(depth: usize) -> Nested {
+    (0..depth).fold(Nested::Leaf, |inner, i| {
+        if i % 2 == 0 {
+            Nested::Seq(vec![inner])
+        } else {
+            Nested::Boxed(Box::new(inner))
+        }
+    })
+}
+
+#[test]
+fn test_recursion_limit() {
+    // Values aren't limited unless a limit is set
+    assert!(to_string(&nested(500)).is_ok());
+    assert!(to_string_pretty(&nested(500), PrettyConfig::default()).is_ok());
+
+    // Every level adds a variant and, for `Seq`, a sequence
+    let config = PrettyConfig {
+        recursion_limit: Some(10),
+        ..PrettyConfig::default()
+    };
+
+    assert!(to_string_pretty(&nested(6), config.clone()).is_ok());
+    assert_eq!(
+        to_string_pretty(&nested(7), config),
+        Err(Error::CircularOrTooDeep)
+    );
+
+    let mut s = Serializer::with_config(None);
+    s.set_recursion_limit(Some(2));
+
+    assert_eq!(nested(2).serialize(&mut s), Err(Error::CircularOrTooDeep));
+}
+
+#[test]
+fn test_deserializer_recursion_limit() {
+    use ron::de::{from_str, Deserializer, Error as DeError, ParseError};
+    use ron::value::{Number, Value};
+    use serde::Deserialize;
+
+    let seqs = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+
+    // Untyped values are read without recursion, however deep they are
+    let deep = "[(a: {\"b\": Some(".repeat(10_000) + "1" + &")})]".repeat(10_000);
+    let mut value = from_str::<Value>(&deep).unwrap();
+    // Unwrapped one by one, as dropping it would recurse
+    let mut depth = 0;
+    loop {
+        value = match value {
+            Value::Seq(mut seq) => seq.pop().unwrap(),
+            Value::Map(map) => map.into_iter().next().unwrap().1,
+            Value::Option(Some(value)) => *value,
+            Value::Number(_) => break,
+            other => panic!("{:?}", other),
+        };
+        depth += 1;
+    }
+    assert_eq!(depth, 40_000);
+    assert!(from_str::<serde::de::IgnoredAny>(&seqs(100_000)).is_ok());
+
+    // Map keys as well
+    let keys = "{".repeat(10_000) + "1" + &": 2}".repeat(10_000);
+    let mut value = from_str::<Value>(&keys).unwrap();
+    while let Value::Map(map) = value {
+        value = map.into_iter().next().unwrap().0;
+    }
+    assert_eq!(value, Value::Number(Number::new(1.0)));
+
+    let input = seqs(129);
+    let mut de = Deserializer::from_str(&input).unwrap();
+    de.set_recursion_limit(Some(128));
+
+    match Value::deserialize(&mut de) {
+        Err(DeError::Parser(ParseError::ExceededRecursionLimit, position)) => {
+            assert_eq!(position.col, 130)
+        }
+        other => panic!("{:?}", other),
+    }
+
+    // Options and newtypes count as well
+    let somes = "Some(".repeat(129) + "1" + &")".repeat(129);
+    let mut de = Deserializer::from_str(&somes).unwrap();
+    de.set_recursion_limit(Some(128));
+
+    assert!(Value::deserialize(&mut de).is_err());
+    assert!(from_str::<Value>(&somes).is_ok());
+
+    let mut de = Deserializer::from_str("[[1]]").unwrap();
+    de.set_recursion_limit(Some(1));
+
+    assert!(Vec::<Vec<u8>>::deserialize(&mut de).is_err());
+}
@@ -118,6 +118,7 @@ fn roundtrip_sep_tuple_members() {
         indentor: "    ".to_owned(),
         separate_tuple_members: true,
         enumerate_arrays: false,
+        ..Default::default()
     };
     let serial = ron::ser::to_string_pretty(&value, pretty).unwrap();
 
@@ -127,3 +128,491 @@ fn roundtrip_sep_tuple_members() {
 
     assert_eq!(Ok(value), deserial);
 }
+
+fn check_roundtrip<T>(value: T)
+where
+    T: ::std::fmt::Debug + PartialEq + serde::Serialize + for<'de> serde::Deserialize<'de>,
+{
+    let serial = ron::ser::to_string(&value).unwrap();
+
+    println!("Serialized: {}", serial);
+
+    assert_eq!(Ok(value), ron::de::from_str(&serial));
+}
+
+#[test]
+fn roundtrip_structs() {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Named {
+        name: String,
+        health: u32,
+        pos: (f32, f32),
+    }
+
+    check_roundtrip(UnitStruct);
+    check_roundtrip(NewType(-4.5));
+    check_roundtrip(TupleStruct(UnitStruct, 127));
+    check_roundtrip(Named {
+        name: "Player \"One\"".to_owned(),
+        health: 100,
+        pos: (0.25, -8.0),
+    });
+}
+
+#[test]
+fn roundtrip_enums() {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    enum Shape {
+        Point,
+        Circle(f64),
+        Rect(f64, f64),
+        Polygon { sides: u8, closed: bool },
+    }
+
+    check_roundtrip(Shape::Point);
+    check_roundtrip(Shape::Circle(1.5));
+    check_roundtrip(Shape::Rect(2.0, 3.0));
+    check_roundtrip(Shape::Polygon {
+        sides: 6,
+        closed: true,
+    });
+    check_roundtrip(vec![Enum::Unit, Enum::Chars('\'', "\\".to_owned())]);
+}
+
+#[test]
+fn roundtrip_options() {
+    check_roundtrip(Some(5u8));
+    check_roundtrip(None::<u8>);
+    check_roundtrip(Some(Some(UnitStruct)));
+    check_roundtrip(Some(None::<String>));
+    check_roundtrip(vec![Some("a".to_owned()), None]);
+}
+
+#[test]
+fn roundtrip_maps() {
+    use std::collections::BTreeMap;
+
+    let mut map = BTreeMap::new();
+    map.insert("first".to_owned(), vec![1, 2, 3]);
+    map.insert("second".to_owned(), vec![]);
+
+    check_roundtrip(map);
+    check_roundtrip(BTreeMap::<u8, bool>::new());
+    check_roundtrip(
+        vec![(Key(1), Enum::Bool(true)), (Key(2), Enum::Unit)]
+            .into_iter()
+            .collect::<HashMap<_, _>>(),
+    );
+}
+
+#[test]
+fn roundtrip_tuples() {
+    check_roundtrip(());
+    check_roundtrip((1u8,));
+    check_roundtrip((true, 'c', "str".to_owned()));
+    check_roundtrip(((1, 2), [3, 4], (UnitStruct, NewType(1.0))));
+}
+
+#[test]
+fn roundtrip_crlf() {
+    let value = Struct {
+        tuple: ((), NewType(0.5), TupleStruct(UnitStruct, -5)),
+        vec: vec![None, Some(UnitStruct)],
+        map: vec![(Key(5), Enum::Unit)].into_iter().collect(),
+    };
+
+    let pretty = ron::ser::PrettyConfig {
+        new_line: "\r\n".to_owned(),
+        enumerate_arrays: true,
+        ..Default::default()
+    };
+    let serial = ron::ser::to_string_pretty(&value, pretty).unwrap();
+
+    assert!(!serial.replace("\r\n", "").contains('\n'));
+    assert_eq!(Ok(value), ron::de::from_str(&serial));
+}
+
+#[test]
+fn roundtrip_max_width() {
+    let value = Struct {
+        tuple: ((), NewType(0.5), TupleStruct(UnitStruct, -5)),
+        vec: vec![None, Some(UnitStruct)],
+        map: vec![(Key(5), Enum::Unit), (Key(9), Enum::Chars('x', "".to_string()))]
+            .into_iter()
+            .collect(),
+    };
+
+    for &width in &[0, 20, 40, 80] {
+        let pretty = ron::ser::PrettyConfig {
+            max_width: Some(width),
+            ..Default::default()
+        };
+        let serial = ron::ser::to_string_pretty(&value, pretty).unwrap();
+
+        println!("Serialized: {}", serial);
+
+        assert_eq!(Ok(&value), ron::de::from_str(&serial).as_ref());
+    }
+}
+
+#[test]
+fn roundtrip_struct_names() {
+    let value = Struct {
+        tuple: ((), NewType(0.5), TupleStruct(UnitStruct, -5)),
+        vec: vec![None, Some(UnitStruct)],
+        map: vec![(Key(5), Enum::Unit), (Key(9), Enum::Chars('x', "".to_string()))]
+            .into_iter()
+            .collect(),
+    };
+
+    for &struct_names in &[false, true] {
+        let pretty = ron::ser::PrettyConfig {
+            struct_names,
+            ..Default::default()
+        };
+        let serial = ron::ser::to_string_pretty(&value, pretty).unwrap();
+
+        println!("Serialized: {}", serial);
+
+        assert_eq!(serial.starts_with("Struct("), struct_names);
+        assert_eq!(Ok(&value), ron::de::from_str(&serial).as_ref());
+    }
+}
+
+#[test]
+fn roundtrip_implicit_some() {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Optional {
+        a: Option<u32>,
+        b: Option<Enum>,
+        c: Option<TupleStruct>,
+    }
+
+    let values = vec![
+        Optional {
+            a: Some(3),
+            b: None,
+            c: Some(TupleStruct(UnitStruct, 4)),
+        },
+        Optional {
+            a: None,
+            b: Some(Enum::Unit),
+            c: None,
+        },
+    ];
+
+    for value in values {
+        for &(implicit_some, omit_none_fields) in &[(true, false), (false, true), (true, true)] {
+            let pretty = ron::ser::PrettyConfig {
+                implicit_some,
+                omit_none_fields,
+                ..Default::default()
+            };
+            let mut serial = ron::ser::to_string_pretty(&value, pretty).unwrap();
+            if implicit_some {
+                serial.insert_str(0, "#![enable(implicit_some)]\n");
+            }
+
+            println!("Serialized: {}", serial);
+
+            assert_eq!(serial.contains("None"), !omit_none_fields);
+            assert_eq!(Ok(&value), ron::de::from_str(&serial).as_ref());
+        }
+    }
+}
+
+#[test]
+fn roundtrip_unsigned_radix() {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Flags {
+        mask: u16,
+        bytes: Vec<u8>,
+        wide: u64,
+        signed: i8,
+    }
+
+    let value = Flags {
+        mask: 0xFF00,
+        bytes: vec![0, 1, 255],
+        wide: !0,
+        signed: -1,
+    };
+
+    for &unsigned_radix in &[
+        ron::ser::Radix::Decimal,
+        ron::ser::Radix::Hexadecimal,
+        ron::ser::Radix::Octal,
+        ron::ser::Radix::Binary,
+    ] {
+        let pretty = ron::ser::PrettyConfig {
+            unsigned_radix,
+            ..Default::default()
+        };
+        let serial = ron::ser::to_string_pretty(&value, pretty).unwrap();
+
+        println!("Serialized: {}", serial);
+
+        assert_eq!(Ok(&value), ron::de::from_str(&serial).as_ref());
+    }
+}
+
+#[test]
+fn roundtrip_commented() {
+    use ron::ser::Commented;
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Config {
+        tuple: Commented<TupleStruct>,
+        vec: Vec<Commented<Option<UnitStruct>>>,
+        map: Commented<HashMap<Key, Enum>>,
+    }
+
+    let config = |comments: [&str; 4]| Config {
+        tuple: Commented::new(comments[0], TupleStruct(UnitStruct, -5)),
+        vec: vec![
+            Commented::new(comments[1], None),
+            Commented::new(comments[2], Some(UnitStruct)),
+        ],
+        map: Commented::new(
+            comments[3],
+            vec![(Key(5), Enum::Unit), (Key(9), Enum::Bool(true))]
+                .into_iter()
+                .collect(),
+        ),
+    };
+    let value = config(["A tuple */ struct", "None", "Some\n\"unit\"", "// Nested"]);
+    let serial = ron::ser::to_string_pretty(&value, Default::default()).unwrap();
+
+    println!("Serialized: {}", serial);
+
+    assert!(serial.contains("// // Nested"));
+    assert_eq!(Ok(config(["", "", "", ""])), ron::de::from_str(&serial));
+}
+
+#[test]
+fn roundtrip_canonical() {
+    let keys = || (0..64).map(Key);
+    let a: HashMap<Key, u32> = keys().map(|k| (k, 1)).collect();
+    let mut b: HashMap<Key, u32> = HashMap::with_capacity(256);
+    b.extend(keys().rev().map(|k| (k, 1)));
+
+    let serial = ron::ser::to_string_canonical(&a).unwrap();
+
+    println!("Serialized: {}", serial);
+
+    assert_eq!(serial, ron::ser::to_string_canonical(&b).unwrap());
+    assert_eq!(Ok(&a), ron::de::from_str(&serial).as_ref());
+}
+
+#[test]
+fn roundtrip_skip_default_fields() {
+    use ron::ser::SkipDefault;
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Settings {
+        #[serde(default)]
+        scale: SkipDefault<f32>,
+        #[serde(default)]
+        vec: SkipDefault<Vec<Option<UnitStruct>>>,
+        #[serde(default)]
+        map: SkipDefault<HashMap<Key, Enum>>,
+    }
+
+    let values = vec![
+        Settings {
+            scale: SkipDefault(0.0),
+            vec: SkipDefault(vec![]),
+            map: SkipDefault(HashMap::new()),
+        },
+        Settings {
+            scale: SkipDefault(0.5),
+            vec: SkipDefault(vec![None]),
+            map: SkipDefault(vec![(Key(5), Enum::Unit)].into_iter().collect()),
+        },
+    ];
+
+    for value in values {
+        let pretty = ron::ser::PrettyConfig {
+            skip_default_fields: true,
+            ..Default::default()
+        };
+        let serial = ron::ser::to_string_pretty(&value, pretty).unwrap();
+
+        println!("Serialized: {}", serial);
+
+        assert_eq!(Ok(&value), ron::de::from_str(&serial).as_ref());
+    }
+}
+
+#[test]
+fn roundtrip_root_struct_name() {
+    let value = Struct {
+        tuple: ((), NewType(0.5), TupleStruct(UnitStruct, -5)),
+        vec: vec![Some(UnitStruct)],
+        map: vec![(Key(5), Enum::Unit)].into_iter().collect(),
+    };
+    let pretty = ron::ser::PrettyConfig {
+        root_struct_name: true,
+        ..Default::default()
+    };
+    let serial = ron::ser::to_string_pretty(&value, pretty).unwrap();
+
+    println!("Serialized: {}", serial);
+
+    assert!(serial.starts_with("Struct("));
+    assert!(!serial.contains("TupleStruct"));
+    assert_eq!(Ok(value), ron::de::from_str(&serial));
+}
+
+#[test]
+fn roundtrip_stream() {
+    use ron::de::{Deserializer, ParseError};
+
+    let records = vec![Enum::Unit, Enum::Bool(true), Enum::Chars('a', "b".to_owned())];
+    let serial = records
+        .iter()
+        .map(|record| ron::ser::to_string(record).unwrap())
+        .collect::<Vec<_>>()
+        .join("\n// Next\n");
+
+    println!("Serialized: {}", serial);
+
+    let stream = Deserializer::from_str(&serial).unwrap().into_iter::<Enum>();
+
+    assert_eq!(stream.collect::<Result<Vec<_>, _>>(), Ok(records));
+
+    let mut stream = Deserializer::from_str("Unit  Bool(tru) Unit")
+        .unwrap()
+        .into_iter::<Enum>();
+
+    assert_eq!(stream.next(), Some(Ok(Enum::Unit)));
+    match stream.next() {
+        Some(Err(ron::de::Error::Parser(ParseError::ExpectedBoolean(_), _))) => {}
+        other => panic!("{:?}", other),
+    }
+    assert_eq!(stream.next(), None);
+
+    let mut empty = Deserializer::from_str("  ").unwrap().into_iter::<Enum>();
+
+    assert_eq!(empty.next(), None);
+}
+
+#[test]
+fn roundtrip_multiline_strings() {
+    use ron::ser::{Escape, PrettyConfig};
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Shader {
+        name: String,
+        source: String,
+    }
+
+    let value = Shader {
+        name: "flat".to_owned(),
+        source: "void main() {\n    gl_FragColor = vec4(1.0);\n}\n".to_owned(),
+    };
+
+    // Line breaks are escaped by default, and can be written as they are
+    for escape in &[Escape::Utf8, Escape::Minimal] {
+        let pretty = PrettyConfig {
+            escape: *escape,
+            ..PrettyConfig::default()
+        };
+        let serial = ron::ser::to_string_pretty(&value, pretty).unwrap();
+
+        println!("Serialized: {}", serial);
+
+        assert_eq!(Ok(&value), ron::de::from_str(&serial).as_ref());
+    }
+
+    // Literal line breaks keep the indentation of the next line,
+    // while continuations drop it
+    let written = "(
+    name: \"fl\\
+          at\",
+    source: \"void main() {
+    gl_FragColor = \\
+        vec4(1.0);
+}
+\",
+)";
+
+    assert_eq!(Ok(value), ron::de::from_str(written));
+}
+
+#[test]
+fn roundtrip_unknown_fields() {
+    use ron::value::{UnknownFields, Value};
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Settings {
+        volume: u8,
+        #[serde(flatten)]
+        unknown: UnknownFields,
+    }
+
+    let settings: Settings =
+        ron::de::from_str("Settings(volume: 80, colour: \"red\", sizes: [1, 2])").unwrap();
+
+    assert_eq!(settings.volume, 80);
+    assert_eq!(
+        settings.unknown.keys().collect::<Vec<_>>(),
+        ["colour", "sizes"]
+    );
+    assert_eq!(settings.unknown["colour"], Value::String("red".to_owned()));
+
+    // Flattened structs are written as maps
+    let serial = ron::ser::to_string(&settings).unwrap();
+
+    println!("Serialized: {}", serial);
+
+    assert_eq!(ron::de::from_str(&serial), Ok(settings));
+
+    let empty: Settings = ron::de::from_str("(volume: 0)").unwrap();
+
+    assert!(empty.unknown.is_empty());
+}
+
+#[test]
+fn roundtrip_raw_identifiers() {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    enum Version {
+        #[serde(rename = "1.0")]
+        V1,
+        #[serde(rename = "2.0-beta")]
+        V2Beta,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    #[serde(rename = "3d-model")]
+    struct Model {
+        #[serde(rename = "type")]
+        kind: String,
+        #[serde(rename = "1st-version")]
+        first_version: Version,
+        versions: Vec<Version>,
+    }
+
+    let model = Model {
+        kind: "mesh".to_owned(),
+        first_version: Version::V1,
+        versions: vec![Version::V1, Version::V2Beta],
+    };
+    let serial = ron::ser::to_string(&model).unwrap();
+
+    assert_eq!(
+        serial,
+        "(type:\"mesh\",r#1st-version:r#1.0,versions:[r#1.0,r#2.0-beta,],)"
+    );
+    assert_eq!(ron::de::from_str(&serial).as_ref(), Ok(&model));
+
+    let config = ron::ser::PrettyConfig {
+        struct_names: true,
+        ..ron::ser::PrettyConfig::default()
+    };
+
+    let serial = ron::ser::to_string_pretty(&model, config).unwrap();
+
+    assert!(serial.starts_with("r#3d-model("));
+    assert_eq!(ron::de::from_str(&serial), Ok(model));
+}
@@ -0,0 +1,173 @@
+extern crate ron;
+#[macro_use]
+extern crate serde;
+
+use std::rc::Rc;
+use std::sync::Arc;
+
+use ron::de::{from_str, Error as DeError, Options, ParseError, Result};
+use ron::ser::{
+    to_string, to_string_canonical, to_string_pretty, Error, PrettyConfig, Shared, SharedArc,
+};
+use ron::value::{Number, Value};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Mesh {
+    name: String,
+    vertices: Vec<u32>,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Prefab {
+    mesh: Shared<Mesh>,
+    children: Vec<Prefab>,
+}
+
+fn shared_from_str<'a, T: serde::Deserialize<'a>>(s: &'a str) -> Result<T> {
+    Options::new().with_shared_values(true).from_str(s)
+}
+
+fn tree() -> Prefab {
+    let cube = Shared::new(Mesh {
+        name: "cube".to_owned(),
+        vertices: vec![0, 1, 2],
+    });
+    let leaf = || Prefab {
+        mesh: cube.clone(),
+        children: vec![],
+    };
+
+    Prefab {
+        mesh: Shared::new(Mesh {
+            name: "root".to_owned(),
+            vertices: vec![],
+        }),
+        children: vec![leaf(), leaf()],
+    }
+}
+
+#[test]
+fn test_shared_pretty() {
+    let config = PrettyConfig {
+        new_line: "\n".to_owned(),
+        max_width: Some(60),
+        ..PrettyConfig::default()
+    };
+    let serial = to_string_pretty(&tree(), config).unwrap();
+
+    assert_eq!(
+        serial,
+        "(
+    mesh: &0 (name: \"root\", vertices: []),
+    children: [
+        (
+            mesh: &1 (name: \"cube\", vertices: [0, 1, 2]),
+            children: [],
+        ),
+        (mesh: *1, children: []),
+    ],
+)"
+    );
+
+    let prefab: Prefab = shared_from_str(&serial).unwrap();
+
+    assert_eq!(prefab, tree());
+    assert!(Rc::ptr_eq(
+        &prefab.children[0].mesh.0,
+        &prefab.children[1].mesh.0
+    ));
+    assert!(!Rc::ptr_eq(&prefab.mesh.0, &prefab.children[0].mesh.0));
+}
+
+#[test]
+fn test_shared_anchors() {
+    // Anchors are numbered per document
+    let serial = to_string(&tree()).unwrap();
+
+    assert_eq!(to_string(&tree()).unwrap(), serial);
+    assert!(serial.contains("mesh:*1,"));
+
+    // Values without anchors are read as well
+    let prefab: Prefab = from_str("(mesh: (name: \"a\", vertices: []), children: [])").unwrap();
+
+    assert_eq!(prefab.mesh.name, "a");
+
+    // Anchors are opt-in
+    match from_str::<Prefab>(&serial) {
+        Err(DeError::Parser(ParseError::UnexpectedAnchor, position)) => assert_eq!(position.col, 7),
+        other => panic!("{:?}", other),
+    }
+
+    // Untyped values skip anchors, but can't hold references
+    assert_eq!(
+        shared_from_str::<Value>("&3 [&4 1]"),
+        Ok(Value::Seq(vec![Value::Number(Number::new(1.0))]))
+    );
+    match shared_from_str::<Value>(&serial) {
+        Err(DeError::Parser(ParseError::UntypedReference, _)) => {}
+        other => panic!("{:?}", other),
+    }
+
+    assert!(shared_from_str::<Shared<u8>>("*0").is_err());
+    assert!(shared_from_str::<Vec<Shared<u8>>>("[&0 1, *1]").is_err());
+    assert!(shared_from_str::<(Shared<u8>, Shared<i8>)>("(&0 1, *0)").is_err());
+    assert_eq!(
+        shared_from_str::<(Shared<u8>, Shared<u8>)>("(&0 1, *0)")
+            .map(|(a, b)| Rc::ptr_eq(&a.0, &b.0)),
+        Ok(true)
+    );
+}
+
+#[test]
+fn test_shared_arc() {
+    let mesh = SharedArc::new(vec![1, 2]);
+    let serial = to_string(&(mesh.clone(), mesh)).unwrap();
+
+    assert_eq!(serial, "(&0 [1,2,],*0,)");
+
+    let (a, b): (SharedArc<Vec<u8>>, SharedArc<Vec<u8>>) = shared_from_str(&serial).unwrap();
+
+    assert!(Arc::ptr_eq(&a.0, &b.0));
+    assert!(shared_from_str::<(Shared<Vec<u8>>, SharedArc<Vec<u8>>)>(&serial).is_err());
+}
+
+#[test]
+fn test_shared_sorted() {
+    #[derive(Serialize)]
+    struct Two {
+        b: Shared<u8>,
+        a: Shared<u8>,
+    }
+
+    let value = Shared::new(1);
+    let two = Two {
+        b: value.clone(),
+        a: value,
+    };
+
+    // Sorting would move the reference in front of the anchor
+    assert_eq!(to_string_canonical(&two), Err(Error::SortedSharedValue));
+    assert_eq!(to_string(&two).unwrap(), "(b:&0 1,a:*0,)");
+}
+
+#[test]
+fn test_shared_redacted() {
+    let config = PrettyConfig {
+        new_line: "\n".to_owned(),
+        max_width: Some(100),
+        redacted_fields: vec!["mesh".to_owned()],
+        ..PrettyConfig::default()
+    };
+    let serial = to_string_pretty(&tree().children, config).unwrap();
+
+    assert_eq!(
+        serial,
+        "[
+    (
+        mesh: &0 (name: \"<redacted>\", vertices: [\"<redacted>\", \"<redacted>\", \"<redacted>\"]),
+        children: [],
+    ),
+    (mesh: *0, children: []),
+]"
+    );
+}
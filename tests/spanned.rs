@@ -0,0 +1,61 @@
+extern crate ron;
+#[macro_use]
+extern crate serde;
+
+use std::ops::Range;
+
+use ron::de::from_str;
+use ron::ser::to_string;
+use ron::Spanned;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Listener {
+    host: Spanned<String>,
+    ports: Vec<Spanned<u32>>,
+    tls: Option<Spanned<Tls>>,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+enum Tls {
+    Off,
+    Cert(String),
+}
+
+#[test]
+fn spans() {
+    let input = "#![enable(implicit_some)]
+(
+    host: \"localhost\", // the default
+    ports: [80, /* fallback */ 8080],
+    tls: Cert(\"a.pem\"),
+)";
+    let listener: Listener = from_str(input).unwrap();
+    let text = |span: &Range<usize>| &input[span.clone()];
+
+    assert_eq!(*listener.host, "localhost");
+    assert_eq!(text(&listener.host.span), "\"localhost\"");
+    assert_eq!(
+        listener.ports.iter().map(|port| **port).collect::<Vec<_>>(),
+        [80, 8080]
+    );
+    assert_eq!(text(&listener.ports[1].span), "8080");
+
+    let tls = listener.tls.unwrap();
+
+    assert_eq!(tls.value, Tls::Cert("a.pem".to_owned()));
+    assert_eq!(text(&tls.span), "Cert(\"a.pem\")");
+}
+
+#[test]
+fn serialized_as_value() {
+    let listener = Listener {
+        host: Spanned::new("localhost".to_owned(), 0..0),
+        ports: vec![Spanned::new(80, 0..0)],
+        tls: Some(Spanned::new(Tls::Off, 0..0)),
+    };
+
+    assert_eq!(
+        to_string(&listener).unwrap(),
+        "(host:\"localhost\",ports:[80,],tls:Some(Off),)"
+    );
+}
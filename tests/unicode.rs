@@ -1,4 +1,6 @@
 extern crate ron;
+#[macro_use]
+extern crate serde;
 
 use ron::de::from_str;
 
@@ -13,3 +15,90 @@ fn test_string() {
     let de: String = from_str("\"My string: ऄ\"").unwrap();
     assert_eq!(de, "My string: ऄ");
 }
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+enum Farbe {
+    Grün,
+    #[serde(rename = "青")]
+    Blue,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename = "Größe")]
+struct Size {
+    #[serde(rename = "höhe")]
+    height: u32,
+    #[serde(rename = "幅")]
+    width: u32,
+    farbe: Farbe,
+}
+
+#[test]
+fn test_identifiers() {
+    let size = Size {
+        height: 2,
+        width: 3,
+        farbe: Farbe::Grün,
+    };
+
+    assert_eq!(from_str("Größe(höhe: 2, 幅: 3, farbe: Grün)").as_ref(), Ok(&size));
+    assert_eq!(from_str("青"), Ok(Farbe::Blue));
+
+    let serial = ron::ser::to_string(&size).unwrap();
+
+    assert_eq!(serial, "(höhe:2,幅:3,farbe:Grün,)");
+    assert_eq!(from_str(&serial), Ok(size));
+
+    // `true` followed by a char which continues an identifier isn't a bool
+    assert!(from_str::<bool>("trueé").is_err());
+    assert!(from_str::<Farbe>("→").is_err());
+}
+
+#[test]
+fn test_byte_order_mark() {
+    use ron::de::{from_bytes, Error, ParseError, Position};
+
+    assert_eq!(from_str("\u{feff}(1, \"ü\")"), Ok((1, "ü".to_owned())));
+    assert_eq!(from_bytes(b"\xEF\xBB\xBF#!/bin/run\n5"), Ok(5));
+    assert_eq!(
+        from_str::<(u8, u8)>("\u{feff}(1 2)"),
+        Err(Error::Parser(
+            ParseError::Message("invalid length 1, expected a tuple of size 2".to_owned()),
+            Position {
+                col: 4,
+                line: 1,
+                offset: 6,
+            }
+        ))
+    );
+
+    let start = Position {
+        col: 1,
+        line: 1,
+        offset: 0,
+    };
+    let utf16 = |encoding| Err(Error::Parser(ParseError::UnsupportedEncoding(encoding), start));
+
+    assert_eq!(from_bytes::<u8>(b"\xFF\xFE5\x00"), utf16("UTF-16LE"));
+    assert_eq!(from_bytes::<u8>(b"\xFE\xFF\x005"), utf16("UTF-16BE"));
+    assert_eq!(from_bytes::<u8>(b"5\x00 \x00"), utf16("UTF-16LE"));
+    assert_eq!(from_bytes::<u8>(b"\x00[\x00]"), utf16("UTF-16BE"));
+    assert_eq!(from_bytes::<u8>(b"\xFF\xFE\x00\x005\x00\x00\x00"), utf16("UTF-32LE"));
+
+    // A stray zero byte is a syntax error, not another encoding
+    assert_eq!(
+        from_bytes::<u8>(b"5\x00"),
+        Err(Error::Parser(
+            ParseError::TrailingCharacters,
+            Position {
+                col: 2,
+                line: 1,
+                offset: 1,
+            }
+        ))
+    );
+    assert!(match from_bytes::<Vec<u8>>(b"\x00[1]") {
+        Err(Error::Parser(ParseError::UnsupportedEncoding(_), _)) => false,
+        result => result.is_err(),
+    });
+}